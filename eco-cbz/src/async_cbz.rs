@@ -0,0 +1,204 @@
+use std::{
+    fs::File,
+    io::{Cursor, Read, Seek},
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use camino::Utf8PathBuf;
+
+use crate::cbz::{Reader, Writer};
+use crate::errors::Result;
+use crate::image::Image;
+
+/// Runs the blocking closure on the tokio blocking thread pool
+async fn run_blocking<T, F>(f: F) -> Result<T>
+where
+    F: FnOnce() -> Result<T> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await?
+}
+
+/// Async counterpart of `Reader`, all the zip and image operations are run on the blocking thread pool
+/// so the async runtime is never blocked.
+/// It can be cheaply cloned and shared between tasks.
+#[derive(Debug)]
+pub struct AsyncReader<R> {
+    inner: Arc<Mutex<Reader<R>>>,
+}
+
+impl<R> Clone for AsyncReader<R> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<R> AsyncReader<R>
+where
+    R: Read + Seek + Send + 'static,
+{
+    pub fn new(reader: Reader<R>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(reader)),
+        }
+    }
+
+    /// Runs the closure with the underlying `Reader` on the blocking thread pool
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the closure fails or if the blocking task panics
+    pub async fn with_reader<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Reader<R>) -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let inner = Arc::clone(&self.inner);
+        run_blocking(move || f(&mut inner.lock().unwrap_or_else(PoisonError::into_inner))).await
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the blocking task panics
+    pub async fn len(&self) -> Result<usize> {
+        self.with_reader(|reader| Ok(reader.len())).await
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the blocking task panics
+    pub async fn is_empty(&self) -> Result<bool> {
+        self.with_reader(|reader| Ok(reader.is_empty())).await
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the blocking task panics
+    pub async fn file_names(&self) -> Result<Vec<String>> {
+        self.with_reader(|reader| Ok(reader.file_names())).await
+    }
+
+    /// ## Errors
+    ///
+    /// Same behavior as `Reader::read_by_name`
+    pub async fn read_by_name(&self, name: impl Into<String>) -> Result<Image> {
+        let name = name.into();
+        self.with_reader(move |reader| reader.read_by_name(&name))
+            .await
+    }
+
+    /// ## Errors
+    ///
+    /// Same behavior as `Reader::read_by_index`
+    pub async fn read_by_index(&self, index: usize) -> Result<Image> {
+        self.with_reader(move |reader| reader.read_by_index(index))
+            .await
+    }
+
+    /// Reads the raw bytes of the page, without decoding the image
+    ///
+    /// ## Errors
+    ///
+    /// Same behavior as `Reader::raw_read_by_index`
+    pub async fn read_bytes_by_index(&self, index: usize) -> Result<Vec<u8>> {
+        self.with_reader(move |reader| {
+            let mut file = reader.raw_read_by_index(index)?;
+            #[allow(clippy::cast_possible_truncation)]
+            let mut bytes = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        })
+        .await
+    }
+}
+
+impl AsyncReader<File> {
+    /// ## Errors
+    ///
+    /// Same behavior as `Reader::try_from_path`
+    pub async fn try_from_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let reader = run_blocking(move || Reader::try_from_path(path)).await?;
+
+        Ok(Self::new(reader))
+    }
+}
+
+impl AsyncReader<Cursor<Vec<u8>>> {
+    /// ## Errors
+    ///
+    /// Same behavior as `Reader::try_from_bytes`
+    pub async fn try_from_bytes(bytes: impl Into<Vec<u8>> + Send + 'static) -> Result<Self> {
+        let reader = run_blocking(move || Reader::try_from_bytes(bytes)).await?;
+
+        Ok(Self::new(reader))
+    }
+}
+
+/// Async counterpart of the in memory `Writer`, the images are encoded on the blocking thread pool
+#[derive(Default)]
+pub struct AsyncWriter {
+    inner: Arc<Mutex<Writer<Cursor<Vec<u8>>>>>,
+}
+
+impl AsyncWriter {
+    /// ## Errors
+    ///
+    /// Same behavior as `Writer::insert`
+    pub async fn insert(&self, image: Image) -> Result<()> {
+        let inner = Arc::clone(&self.inner);
+        run_blocking(move || {
+            inner
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(image)
+        })
+        .await
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the blocking task panics
+    pub async fn len(&self) -> Result<usize> {
+        let inner = Arc::clone(&self.inner);
+        run_blocking(move || Ok(inner.lock().unwrap_or_else(PoisonError::into_inner).len())).await
+    }
+
+    /// Finishes the archive and returns its bytes
+    ///
+    /// ## Errors
+    ///
+    /// Same behavior as `Writer::write_to`
+    pub async fn into_bytes(self) -> Result<Vec<u8>> {
+        let writer = self.into_writer();
+        run_blocking(move || {
+            let mut bytes = Vec::new();
+            writer.write_to(&mut bytes)?;
+            Ok(bytes)
+        })
+        .await
+    }
+
+    /// ## Errors
+    ///
+    /// Same behavior as `Writer::write_to_path`
+    pub async fn write_to_path(self, path: impl Into<Utf8PathBuf>) -> Result<()> {
+        let path = path.into();
+        let writer = self.into_writer();
+        run_blocking(move || writer.write_to_path(path)).await
+    }
+
+    fn into_writer(self) -> Writer<Cursor<Vec<u8>>> {
+        match Arc::try_unwrap(self.inner) {
+            Ok(inner) => inner.into_inner().unwrap_or_else(PoisonError::into_inner),
+            // A blocking task whose future has been dropped may still be running,
+            // the insertions it didn't complete yet are lost.
+            Err(inner) => {
+                std::mem::take(&mut *inner.lock().unwrap_or_else(PoisonError::into_inner))
+            }
+        }
+    }
+}
@@ -1,39 +1,106 @@
 use std::{
-    fs::{File, OpenOptions},
+    cell::OnceCell,
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
     io::{Cursor, Read, Seek, Write},
     path::Path,
 };
 
 use camino::Utf8Path;
 use tracing::debug;
-use zip::{read::ZipFile, write::FileOptions, ZipArchive, ZipWriter};
+use zip::{
+    read::ZipFile,
+    result::{InvalidPassword, ZipError},
+    write::FileOptions,
+    CompressionMethod, DateTime, ZipArchive, ZipWriter,
+};
 
+#[cfg(feature = "metadata")]
+use crate::comic_info::{Bookmark, ComicInfo, COMIC_INFO_FILE_NAME};
+use crate::errors::write_error;
 pub use crate::errors::{Error, Result};
-use crate::image::Image;
+use crate::image::{Image, PageFormat};
+#[cfg(feature = "manifest")]
+use crate::manifest::{Manifest, ManifestPage, ManifestReport, MANIFEST_FILE_NAME};
+use crate::sort::cmp_paths;
 
-/// We artificially limit the amount of accepted files to 65535 files per Cbz
+/// By default we artificially limit the amount of accepted files to 65535 files per Cbz
 /// First as it'd be rather impractical for the user to read such enormous Cbz
-/// Also, this size has been chosen as it was the limit of the very first zip spec
+/// Also, this size has been chosen as it was the limit of the very first zip spec.
+/// The limit can be changed with `Writer::with_max_file_number`, ZIP64 archives are then produced when needed.
 pub static MAX_FILE_NUMBER: usize = u16::MAX as usize;
 
 /// The length of 65535 used to name the inserted file with a proper padding
 static COUNTER_SIZE: usize = 5;
 
+/// Extensions of the non image files that are recognized as metadata, like `ComicInfo.xml`.
+/// They're not considered as pages, but can be carried over when the archive is rewritten.
+pub static SIDECAR_EXTENSIONS: &[&str] = &["xml", "json", "txt", "nfo"];
+
+/// Extensions of the image formats that are already compressed, deflating them wastes time for a few bytes
+static COMPRESSED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "webp", "avif", "jxl", "gif"];
+
+/// How the pages are compressed in the archive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// The already compressed formats (jpeg, webp, avif, jxl, gif) are stored as is, the other ones (png, bmp...) are deflated
+    #[default]
+    Auto,
+    Stored,
+    Deflated,
+}
+
+impl Compression {
+    fn method(self, extension: Option<&str>) -> CompressionMethod {
+        match self {
+            Self::Auto => {
+                let is_compressed = extension.is_some_and(|ext| {
+                    COMPRESSED_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+                });
+                if is_compressed {
+                    CompressionMethod::Stored
+                } else {
+                    CompressionMethod::Deflated
+                }
+            }
+            Self::Stored => CompressionMethod::Stored,
+            Self::Deflated => CompressionMethod::Deflated,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Reader<R> {
     archive: ZipArchive<R>,
+    /// The sorted names of the pages, computed once on first access
+    entries: OnceCell<Vec<String>>,
+    /// Used to decrypt the entries of password protected archives
+    password: Option<Vec<u8>>,
 }
 
 impl<R> Reader<R> {
     pub fn new(archive: ZipArchive<R>) -> Self {
-        Self { archive }
+        Self {
+            archive,
+            entries: OnceCell::new(),
+            password: None,
+        }
+    }
+
+    /// Sets the password used to decrypt the entries, only the legacy `ZipCrypto` encryption is supported
+    #[must_use]
+    pub fn with_password(mut self, password: impl Into<Vec<u8>>) -> Self {
+        self.password = Some(password.into());
+        self
     }
 
     pub fn archive(&self) -> &ZipArchive<R> {
         &self.archive
     }
 
+    /// The cached pages list is computed again afterward, as the archive may be changed through it
     pub fn archive_mut(&mut self) -> &mut ZipArchive<R> {
+        self.entries.take();
         &mut self.archive
     }
 }
@@ -43,28 +110,106 @@ where
     R: Read + Seek,
 {
     pub fn len(&self) -> usize {
-        self.file_names().len()
+        self.entries().len()
     }
 
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    /// The sorted names of the pages, the list is computed once and then cached,
+    /// so positional access doesn't require any bookkeeping from the caller.
+    ///
+    /// Pages stored in sub directories are supported, the directory entries themselves are skipped,
+    /// and the pages are sorted by path components in natural order.
+    /// Hidden files and directories (like `__MACOSX`) are skipped too.
+    pub fn entries(&self) -> &[String] {
+        self.entries.get_or_init(|| {
+            let mut file_names = self
+                .archive
+                .file_names()
+                .filter(|file_name| is_page_file(file_name))
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            file_names.sort_by(|name, other| cmp_paths(name, other));
+            file_names
+        })
+    }
+
     pub fn file_names(&self) -> Vec<String> {
-        let mut file_names = self
-            .archive
+        self.entries().to_vec()
+    }
+
+    /// The names of the metadata files (see `SIDECAR_EXTENSIONS`) present in the Cbz
+    pub fn sidecars(&self) -> Vec<String> {
+        self.archive
             .file_names()
             .filter(|file_name| {
-                let path = Utf8Path::new(file_name);
-                let Some(ext) = path.extension() else {
-                    return false;
-                };
-                ext != "xml"
+                is_visible_file(file_name)
+                    && Utf8Path::new(file_name)
+                        .extension()
+                        .is_some_and(is_sidecar_extension)
             })
             .map(ToString::to_string)
-            .collect::<Vec<_>>();
-        file_names.sort();
-        file_names
+            .collect()
+    }
+
+    /// Writes the pages and the metadata files in the directory, which is created if needed.
+    /// Returns the amount of extracted files.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if a file can't be read from the Cbz or written in the directory
+    pub fn extract_to_dir(&mut self, dir: impl AsRef<Utf8Path>) -> Result<usize> {
+        let dir = dir.as_ref();
+        let mut names = self.file_names();
+        names.extend(self.sidecars());
+
+        for name in &names {
+            // The entry names are untrusted, they must not escape the directory
+            let path = dir.join(sanitize_entry_name(name));
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, self.read_bytes_by_name(name)?)?;
+        }
+
+        Ok(names.len())
+    }
+
+    /// Reads the raw content of any file in the Cbz, pages or not
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file doesn't exist or if the content can't be read
+    pub fn read_bytes_by_name(&mut self, name: &str) -> Result<Vec<u8>> {
+        let mut file = self.raw_read_by_name(name)?;
+        #[allow(clippy::cast_possible_truncation)]
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Returns the `Image` at `index` in the sorted pages list
+    ///
+    /// ## Errors
+    ///
+    /// Fails if there is no page at this index, or for the same reasons as `read_by_name`
+    pub fn read_by_index(&mut self, index: usize) -> Result<Image> {
+        let Some(name) = self.entries().get(index).cloned() else {
+            return Err(Error::CbzNotFound(index));
+        };
+        self.read_by_name(&name)
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if there is no page at this index, or if the content can't be read
+    pub fn raw_read_by_index(&mut self, index: usize) -> Result<ZipFile<'_>> {
+        let Some(name) = self.entries().get(index).cloned() else {
+            return Err(Error::CbzNotFound(index));
+        };
+        self.raw_read_by_name(&name)
     }
 
     /// Lookup the image by `name` in Cbz and returns an `Image`
@@ -74,25 +219,50 @@ where
     /// Fails if file size is too large to fit a `usize` on host machine
     /// or if the content can't be read
     pub fn read_by_name(&mut self, name: &str) -> Result<Image> {
-        let file = self.archive.by_name(name)?;
+        let file = self.raw_read_by_name(name)?;
         file.try_into()
     }
 
     /// ## Errors
     ///
-    /// Fails if the content can't be read
+    /// Fails if the content can't be read, or if the entry is encrypted and the password is missing or invalid
     pub fn raw_read_by_name(&mut self, name: &str) -> Result<ZipFile<'_>> {
-        Ok(self.archive.by_name(name)?)
+        let Some(password) = &self.password else {
+            return self.archive.by_name(name).map_err(|err| match err {
+                ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+                    Error::CbzPasswordRequired
+                }
+                err => err.into(),
+            });
+        };
+        match self.archive.by_name_decrypt(name, password)? {
+            Ok(file) => Ok(file),
+            Err(InvalidPassword) => Err(Error::CbzInvalidPassword),
+        }
+    }
+
+    /// Iterates over the images present in the Cbz, in the pages order
+    pub fn iter(&mut self) -> Iter<'_, R> {
+        Iter {
+            reader: self,
+            index: 0,
+        }
+    }
+
+    /// Iterates over the pages names and metadata, without reading the images
+    pub fn iter_entries(&mut self) -> EntriesIter<'_, R> {
+        EntriesIter {
+            reader: self,
+            index: 0,
+        }
     }
 
     /// Iterate over images present in the Cbz.
-    pub fn for_each<F>(&mut self, mut f: F)
+    pub fn for_each<F>(&mut self, f: F)
     where
         F: FnMut(Result<Image>),
     {
-        for file_name in self.file_names() {
-            f(self.read_by_name(&file_name));
-        }
+        self.iter().for_each(f);
     }
 
     /// Iterate over images present in the Cbz.
@@ -105,11 +275,7 @@ where
     where
         F: FnMut(Result<Image>) -> Result<(), E>,
     {
-        for file_name in self.file_names() {
-            f(self.read_by_name(&file_name))?;
-        }
-
-        Ok(())
+        self.iter().try_for_each(f)
     }
 
     /// Creates `Reader` from a `Read`
@@ -146,6 +312,112 @@ where
         // Drop last byte
         Ok(serde_json::from_slice(&buf[..s])?)
     }
+
+    /// Retrieves the metadata from the `ComicInfo.xml` entry, if any
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the entry can't be read or if its xml is invalid
+    #[cfg(feature = "metadata")]
+    pub fn comic_info(&mut self) -> Result<Option<ComicInfo>> {
+        let Some(name) = self
+            .archive
+            .file_names()
+            .find(|name| name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME))
+            .map(ToString::to_string)
+        else {
+            return Ok(None);
+        };
+        let bytes = self.read_bytes_by_name(&name)?;
+
+        Ok(Some(ComicInfo::try_from_xml(&String::from_utf8_lossy(
+            &bytes,
+        ))?))
+    }
+
+    /// The chapters bookmarked in the `ComicInfo.xml` entry, in page order
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the entry can't be read or if its xml is invalid
+    #[cfg(feature = "metadata")]
+    pub fn bookmarks(&mut self) -> Result<Vec<Bookmark>> {
+        Ok(self
+            .comic_info()?
+            .map(|comic_info| comic_info.bookmarks())
+            .unwrap_or_default())
+    }
+
+    /// Retrieves the manifest embedded by `Writer::with_manifest`, if any
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the manifest can't be read or if its json is invalid
+    #[cfg(feature = "manifest")]
+    pub fn manifest(&mut self) -> Result<Option<Manifest>> {
+        if !self
+            .archive
+            .file_names()
+            .any(|name| name == MANIFEST_FILE_NAME)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_slice(
+            &self.read_bytes_by_name(MANIFEST_FILE_NAME)?,
+        )?))
+    }
+
+    /// The dimensions and formats of the pages, in reading order.
+    /// They're taken from the manifest when the archive has one, otherwise the headers of the pages are read.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the manifest is invalid, or if a page can't be read or its format is unknown
+    #[cfg(feature = "manifest")]
+    pub fn pages_info(&mut self) -> Result<Vec<ManifestPage>> {
+        if let Some(manifest) = self.manifest()? {
+            let mut pages = manifest.pages;
+            pages.sort_by(|page, other| cmp_paths(&page.name, &other.name));
+            return Ok(pages);
+        }
+
+        self.file_names()
+            .into_iter()
+            .map(|name| {
+                let bytes = self.read_bytes_by_name(&name)?;
+                ManifestPage::try_from_bytes(name, &bytes)
+            })
+            .collect()
+    }
+
+    /// Checks the pages against the checksums stored in the archive's manifest
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the archive has no manifest, if it's invalid, or if a page can't be read
+    #[cfg(feature = "manifest")]
+    pub fn verify_manifest(&mut self) -> Result<ManifestReport> {
+        let Some(manifest) = self.manifest()? else {
+            return Err(Error::CbzManifestNotFound);
+        };
+
+        let mut report = ManifestReport::default();
+        for page in manifest.pages {
+            if !self.archive.file_names().any(|name| name == page.name) {
+                report.missing.push(page.name);
+                continue;
+            }
+            let bytes = self.read_bytes_by_name(&page.name)?;
+            if crate::manifest::sha256(&bytes) == page.sha256 {
+                report.verified += 1;
+            } else {
+                report.mismatched.push(page.name);
+            }
+        }
+
+        Ok(report)
+    }
 }
 
 impl Reader<File> {
@@ -159,6 +431,18 @@ impl Reader<File> {
 
         Self::try_from_reader(file)
     }
+
+    /// Creates a `Reader` for a password protected archive
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the underlying `ZipArchive` can't be created
+    pub fn try_from_path_with_password(
+        path: impl AsRef<Path>,
+        password: impl Into<Vec<u8>>,
+    ) -> Result<Self> {
+        Ok(Self::try_from_path(path)?.with_password(password))
+    }
 }
 
 impl<'a> Reader<Cursor<&'a [u8]>> {
@@ -187,6 +471,103 @@ impl Reader<Cursor<Vec<u8>>> {
     }
 }
 
+/// Directories entries, and hidden files and directories (like `__MACOSX`), are skipped
+pub(crate) fn is_visible_file(file_name: &str) -> bool {
+    !file_name.ends_with('/')
+        && !file_name.split('/').any(|component| {
+            (component.starts_with('.') && component != "." && component != "..")
+                || component == "__MACOSX"
+        })
+}
+
+/// The visible files with an extension that isn't a sidecar one are the pages, the archive entries use `/` as separator
+#[must_use]
+pub fn is_page_file(file_name: &str) -> bool {
+    is_visible_file(file_name)
+        && Utf8Path::new(file_name)
+            .extension()
+            .is_some_and(|ext| !is_sidecar_extension(ext))
+}
+
+pub(crate) fn is_sidecar_extension(ext: &str) -> bool {
+    SIDECAR_EXTENSIONS
+        .iter()
+        .any(|sidecar_ext| ext.eq_ignore_ascii_case(sidecar_ext))
+}
+
+/// Iterator over the images of a Cbz, created by `Reader::iter`
+pub struct Iter<'a, R> {
+    reader: &'a mut Reader<R>,
+    index: usize,
+}
+
+impl<R> Iterator for Iter<'_, R>
+where
+    R: Read + Seek,
+{
+    type Item = Result<Image>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.reader.len() {
+            return None;
+        }
+        let image = self.reader.read_by_index(self.index);
+        self.index += 1;
+        Some(image)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.reader.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R> ExactSizeIterator for Iter<'_, R> where R: Read + Seek {}
+
+/// A page of the Cbz, without its content
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    /// Uncompressed size, in bytes
+    pub size: u64,
+    pub compressed_size: u64,
+    pub last_modified: DateTime,
+}
+
+/// Iterator over the pages names and metadata of a Cbz, created by `Reader::iter_entries`
+pub struct EntriesIter<'a, R> {
+    reader: &'a mut Reader<R>,
+    index: usize,
+}
+
+impl<R> Iterator for EntriesIter<'_, R>
+where
+    R: Read + Seek,
+{
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.reader.len() {
+            return None;
+        }
+        let entry = self.reader.raw_read_by_index(self.index).map(|file| Entry {
+            name: file.name().to_string(),
+            size: file.size(),
+            compressed_size: file.compressed_size(),
+            last_modified: file.last_modified(),
+        });
+        self.index += 1;
+        Some(entry)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.reader.len().saturating_sub(self.index);
+        (remaining, Some(remaining))
+    }
+}
+
+impl<R> ExactSizeIterator for EntriesIter<'_, R> where R: Read + Seek {}
+
 impl<R> From<ZipArchive<R>> for Reader<R> {
     fn from(archive: ZipArchive<R>) -> Self {
         Self::new(archive)
@@ -202,6 +583,18 @@ impl<R> From<Reader<R>> for ZipArchive<R> {
 pub struct Writer<W: Write + Seek> {
     archive: ZipWriter<W>,
     size: usize,
+    /// The names already used in the archive, lowercased as they'd collide once extracted on case-insensitive file systems
+    names: HashSet<String>,
+    max_file_number: usize,
+    /// The format the inserted images are encoded in, the format of each image by default
+    page_format: Option<PageFormat>,
+    /// Quality of the pages encoded in a lossy `page_format`
+    quality: Option<u8>,
+    /// Used by the insertions without explicit file options
+    compression: Compression,
+    /// The pages recorded so far, `None` when no manifest is embedded
+    #[cfg(feature = "manifest")]
+    manifest: Option<Vec<ManifestPage>>,
 }
 
 impl<W> Writer<W>
@@ -209,7 +602,69 @@ where
     W: Write + Seek,
 {
     pub fn new(archive: ZipWriter<W>) -> Self {
-        Self { archive, size: 0 }
+        Self {
+            archive,
+            size: 0,
+            names: HashSet::new(),
+            max_file_number: MAX_FILE_NUMBER,
+            page_format: None,
+            quality: None,
+            compression: Compression::Auto,
+            #[cfg(feature = "manifest")]
+            manifest: None,
+        }
+    }
+
+    /// Encodes all the inserted images in this format, the raw bytes are still inserted as is
+    #[must_use]
+    pub fn with_page_format(mut self, page_format: PageFormat) -> Self {
+        self.page_format = Some(page_format);
+        self
+    }
+
+    /// Changes how the pages inserted without explicit file options are compressed, `Compression::Auto` by default
+    #[must_use]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// The file options of the page, compressed according to the `Compression`
+    fn file_options(&self, extension: Option<&str>) -> FileOptions {
+        FileOptions::default().compression_method(self.compression.method(extension))
+    }
+
+    #[must_use]
+    pub fn page_format(&self) -> Option<PageFormat> {
+        self.page_format
+    }
+
+    /// Quality of the pages, between 1 and 100, when they're encoded in a lossy `page_format` (jpeg, webp, or avif)
+    #[must_use]
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// Changes the maximum amount of pages, `MAX_FILE_NUMBER` by default.
+    /// Archives with more than 65535 entries are written in the ZIP64 format.
+    #[must_use]
+    pub fn with_max_file_number(mut self, max_file_number: usize) -> Self {
+        self.max_file_number = max_file_number;
+        self
+    }
+
+    #[must_use]
+    pub fn max_file_number(&self) -> usize {
+        self.max_file_number
+    }
+
+    /// Embeds a manifest with the checksum, dimensions, and format of each page, see `Reader::verify_manifest` and `Reader::pages_info`
+    #[cfg(feature = "manifest")]
+    #[must_use]
+    pub fn with_manifest(mut self) -> Self {
+        self.manifest = Some(Vec::new());
+        self
     }
 
     /// Creates a `CbzWriter` from a `Write`
@@ -231,26 +686,55 @@ where
         &self.archive
     }
 
+    /// The entries must not be written through it, their names wouldn't be checked against the existing ones
     pub fn raw_archive_mut(&mut self) -> &mut ZipWriter<W> {
         &mut self.archive
     }
 
+    /// Writes the central directory and returns the underlying writer
+    ///
+    /// ## Errors
+    ///
+    /// Same errors as the underlying `ZipWriter::finish` method
+    pub fn finish(mut self) -> Result<W> {
+        #[cfg(feature = "manifest")]
+        self.write_manifest()?;
+
+        self.archive.finish().map_err(write_error)
+    }
+
+    /// Writes the manifest entry, if enabled, it must be called only once right before the archive is finished
+    #[cfg(feature = "manifest")]
+    fn write_manifest(&mut self) -> Result<()> {
+        let Some(pages) = self.manifest.take() else {
+            return Ok(());
+        };
+        let bytes = serde_json::to_vec_pretty(&Manifest { pages })?;
+
+        self.insert_sidecar(MANIFEST_FILE_NAME, &bytes)
+    }
+
     /// ## Errors
     ///
     /// Same behavior as `insert_with_extension_and_file_options`
     pub fn insert(&mut self, image: Image) -> Result<()> {
-        let extension = image
-            .format()
-            .and_then(|f| f.extensions_str().first().copied())
-            .unwrap_or("png");
-        self.insert_with_extension_and_file_options(image, extension, FileOptions::default())
+        let extension = match self.page_format {
+            Some(page_format) => page_format.extension(),
+            None => image
+                .format()
+                .and_then(|f| f.extensions_str().first().copied())
+                .unwrap_or("png"),
+        };
+        let file_options = self.file_options(Some(extension));
+        self.insert_with_extension_and_file_options(image, extension, file_options)
     }
 
     /// ## Errors
     ///
     /// Same behavior as `insert_with_extension_and_file_options`
     pub fn insert_with_extension(&mut self, image: Image, extension: &str) -> Result<()> {
-        self.insert_with_extension_and_file_options(image, extension, FileOptions::default())
+        let file_options = self.file_options(Some(extension));
+        self.insert_with_extension_and_file_options(image, extension, file_options)
     }
 
     /// ## Errors
@@ -284,26 +768,160 @@ where
 
     /// ## Errors
     ///
-    /// This fails if the Cbz writer can't be written or if it's full (i.e. its size equals `MAX_FILE_NUMBER`)
+    /// This fails if the Cbz writer can't be written or if it's full (i.e. its size equals its max file number)
     pub fn insert_with_extension_and_file_options(
         &mut self,
         image: Image,
         extension: &str,
         file_options: FileOptions,
     ) -> Result<()> {
-        if self.size >= MAX_FILE_NUMBER {
-            return Err(Error::CbzTooLarge(MAX_FILE_NUMBER));
+        // The padding grows with the limit so the names keep sorting lexicographically
+        let counter_size = COUNTER_SIZE.max(self.max_file_number.to_string().len());
+        let filename = format!("{:0>counter_size$}.{}", self.len() + 1, extension);
+
+        self.insert_with_name_and_file_options(image, &filename, file_options)
+    }
+
+    /// Inserts already encoded image bytes as is, named with the counter like the decoded images
+    ///
+    /// ## Errors
+    ///
+    /// Same behavior as `insert_raw_with_name_and_file_options`
+    pub fn insert_raw_with_extension(&mut self, bytes: &[u8], extension: &str) -> Result<()> {
+        let counter_size = COUNTER_SIZE.max(self.max_file_number.to_string().len());
+        let filename = format!("{:0>counter_size$}.{}", self.len() + 1, extension);
+
+        let file_options = self.file_options(Some(extension));
+        self.insert_raw_with_name_and_file_options(bytes, &filename, file_options)
+    }
+
+    /// Inserts the image under the provided name instead of the counter based one,
+    /// so meaningful names like `ch03_p012.jpg` are preserved.
+    ///
+    /// ## Errors
+    ///
+    /// Same behavior as `insert_with_name_and_file_options`
+    pub fn insert_with_name(&mut self, image: Image, name: &str) -> Result<()> {
+        let file_options = self.file_options(Utf8Path::new(name).extension());
+        self.insert_with_name_and_file_options(image, name, file_options)
+    }
+
+    /// Inserts already encoded image bytes as is under the provided name, like `insert_with_name`
+    ///
+    /// ## Errors
+    ///
+    /// Same behavior as `insert_raw_with_name_and_file_options`
+    pub fn insert_raw_with_name(&mut self, bytes: &[u8], name: &str) -> Result<()> {
+        let file_options = self.file_options(Utf8Path::new(name).extension());
+        self.insert_raw_with_name_and_file_options(bytes, name, file_options)
+    }
+
+    /// The name is sanitized, and suffixed with a number if it's already used in the archive.
+    ///
+    /// ## Errors
+    ///
+    /// This fails if the name is empty once sanitized, if the Cbz writer can't be written,
+    /// or if it's full (i.e. its size equals its max file number)
+    pub fn insert_with_name_and_file_options(
+        &mut self,
+        image: Image,
+        name: &str,
+        file_options: FileOptions,
+    ) -> Result<()> {
+        if self.size >= self.max_file_number {
+            return Err(Error::CbzTooLarge(self.max_file_number));
+        }
+
+        let bytes = match self.page_format {
+            Some(page_format) => image.try_into_bytes_with_format(page_format, self.quality)?,
+            None => image.try_into_bytes()?,
+        };
+
+        self.insert_raw_with_name_and_file_options(&bytes, name, file_options)
+    }
+
+    /// Inserts already encoded image bytes as is, they're neither decoded nor validated.
+    /// The name is sanitized, and suffixed with a number if it's already used in the archive.
+    ///
+    /// ## Errors
+    ///
+    /// This fails if the name is empty once sanitized, if the Cbz writer can't be written,
+    /// or if it's full (i.e. its size equals its max file number)
+    pub fn insert_raw_with_name_and_file_options(
+        &mut self,
+        bytes: &[u8],
+        name: &str,
+        file_options: FileOptions,
+    ) -> Result<()> {
+        if self.size >= self.max_file_number {
+            return Err(Error::CbzTooLarge(self.max_file_number));
         }
 
-        let filename = format!("{:0>COUNTER_SIZE$}.{}", self.len() + 1, extension);
+        let filename = self.unique_name(&sanitize_entry_name(name))?;
+        // Files bigger than 4GiB can only be stored in ZIP64 entries
+        let file_options = file_options.large_file(u32::try_from(bytes.len()).is_err());
+        #[cfg(feature = "manifest")]
+        let manifest_page = match self.manifest {
+            Some(_) => Some(ManifestPage::try_from_bytes(filename.as_str(), bytes)?),
+            None => None,
+        };
 
-        self.archive.start_file(filename, file_options)?;
-        self.archive.write_all(&image.try_into_bytes()?)?;
+        self.archive
+            .start_file(filename.as_str(), file_options)
+            .map_err(write_error)?;
+        self.archive.write_all(bytes).map_err(write_error)?;
+        self.names.insert(filename.to_lowercase());
         self.size += 1;
+        #[cfg(feature = "manifest")]
+        if let (Some(pages), Some(page)) = (&mut self.manifest, manifest_page) {
+            pages.push(page);
+        }
 
         Ok(())
     }
 
+    /// Inserts a non image file, like `ComicInfo.xml`, it's not counted as a page.
+    /// The name is sanitized, and suffixed with a number if it's already used in the archive.
+    ///
+    /// ## Errors
+    ///
+    /// This fails if the name is empty once sanitized, or if the Cbz writer can't be written
+    pub fn insert_sidecar(&mut self, name: &str, bytes: &[u8]) -> Result<()> {
+        let filename = self.unique_name(&sanitize_entry_name(name))?;
+
+        self.archive
+            .start_file(filename.as_str(), FileOptions::default())
+            .map_err(write_error)?;
+        self.archive.write_all(bytes).map_err(write_error)?;
+        self.names.insert(filename.to_lowercase());
+
+        Ok(())
+    }
+
+    /// Suffixes the name with a number until it doesn't collide with an existing one, whatever their case.
+    /// The zip writer would otherwise write several entries with the same name without any error.
+    fn unique_name(&self, name: &str) -> Result<String> {
+        if name.is_empty() {
+            return Err(Error::CbzFileNameEmpty);
+        }
+        if !self.names.contains(&name.to_lowercase()) {
+            return Ok(name.to_string());
+        }
+        let path = Utf8Path::new(name);
+        let stem = path.with_extension("");
+        let mut suffix = 2;
+        loop {
+            let candidate = match path.extension() {
+                Some(extension) => format!("{stem}-{suffix}.{extension}"),
+                None => format!("{stem}-{suffix}"),
+            };
+            if !self.names.contains(&candidate.to_lowercase()) {
+                return Ok(candidate);
+            }
+            suffix += 1;
+        }
+    }
+
     /// Set the metadata of the cbz file.
     /// The format has never been specified so any serializable type is accepted.
     ///
@@ -329,6 +947,27 @@ where
 
         Ok(())
     }
+
+    /// Stores the metadata in a `ComicInfo.xml` entry
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the metadata can't be serialized or if the entry can't be written
+    #[cfg(feature = "metadata")]
+    pub fn set_comic_info(&mut self, comic_info: &ComicInfo) -> Result<()> {
+        let xml = comic_info.try_to_xml()?;
+        self.insert_sidecar(COMIC_INFO_FILE_NAME, xml.as_bytes())
+    }
+}
+
+/// Sanitizes each component of the entry path, the empty, current, and parent directory components are removed
+fn sanitize_entry_name(name: &str) -> String {
+    name.split(['/', '\\'])
+        .filter(|component| !component.is_empty() && *component != "." && *component != "..")
+        .map(sanitize_filename::sanitize)
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 impl Writer<Cursor<Vec<u8>>> {
@@ -336,7 +975,12 @@ impl Writer<Cursor<Vec<u8>>> {
     ///
     /// Same errors as the underlying `ZipWriter::finish` method
     pub fn write_to(mut self, mut writer: impl Write) -> Result<()> {
-        writer.write_all(&self.archive.finish()?.into_inner())?;
+        #[cfg(feature = "manifest")]
+        self.write_manifest()?;
+
+        writer
+            .write_all(&self.archive.finish().map_err(write_error)?.into_inner())
+            .map_err(write_error)?;
 
         Ok(())
     }
@@ -359,11 +1003,73 @@ impl Writer<Cursor<Vec<u8>>> {
                         .map(sanitize_filename::sanitize)
                         .unwrap_or_default(),
                 ),
-            )?;
+            )
+            .map_err(write_error)?;
         self.write_to(&mut file)
     }
 }
 
+impl Writer<File> {
+    /// Creates the Cbz file, the pages are written to the disk as soon as they're inserted,
+    /// and `finish` must be called once all the pages are inserted.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file can't be created
+    pub fn try_create_at_path(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let path = path.as_ref();
+        debug!("creating cbz file {path}");
+        let file = File::create(
+            path.with_file_name(
+                path.file_name()
+                    .map(sanitize_filename::sanitize)
+                    .unwrap_or_default(),
+            ),
+        )
+        .map_err(write_error)?;
+
+        Ok(Self::from_writer(file))
+    }
+
+    /// Opens an existing Cbz to append new pages to it, the existing pages and metadata are preserved.
+    /// The new pages are numbered after the existing ones, and `finish` must be called once all the pages are inserted.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file can't be opened or if it's not a valid Cbz
+    pub fn append_to_path(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let path = path.as_ref();
+        debug!("appending to cbz file {path}");
+        let reader = Reader::try_from_path(path)?;
+        let size = reader.len();
+        let names = reader
+            .archive()
+            .file_names()
+            .map(str::to_lowercase)
+            .collect();
+        drop(reader);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(write_error)?;
+        let archive = ZipWriter::new_append(file).map_err(write_error)?;
+
+        Ok(Self {
+            archive,
+            size,
+            names,
+            max_file_number: MAX_FILE_NUMBER,
+            page_format: None,
+            quality: None,
+            compression: Compression::Auto,
+            #[cfg(feature = "manifest")]
+            manifest: None,
+        })
+    }
+}
+
 impl Default for Writer<Cursor<Vec<u8>>> {
     fn default() -> Self {
         Self::from_writer(Cursor::new(Vec::new()))
@@ -0,0 +1,298 @@
+#![cfg(feature = "metadata")]
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cbz_metadata::{ComicBookInfoV1, Credit, Month, Primary};
+use crate::{Error, Result};
+
+/// Name of the entry holding the `ComicInfo` metadata
+pub static COMIC_INFO_FILE_NAME: &str = "ComicInfo.xml";
+
+/// The `ComicInfo.xml` metadata, as defined by the Anansi project: `https://anansi-project.github.io/docs/comicinfo/intro`.
+/// Only the fields that can be mapped from and to `ComicBookInfoV1` are supported.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename = "ComicInfo", rename_all = "PascalCase")]
+pub struct ComicInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    /// The issue number, it's not always numeric (e.g. `1.5`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    /// Amount of issues in the series
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub count: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub month: Option<u8>,
+    /// Comma separated list of people
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub writer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub penciller: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colorist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub letterer: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+    /// Comma separated list of tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<String>,
+    #[serde(rename = "LanguageISO", skip_serializing_if = "Option::is_none")]
+    pub language_iso: Option<String>,
+    /// From 0 to 5
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub community_rating: Option<f32>,
+    /// Per-page information, like the chapters bookmarks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pages: Option<ComicPages>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComicPages {
+    #[serde(rename = "Page", default)]
+    pub pages: Vec<ComicPageInfo>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComicPageInfo {
+    /// Index of the page, starting at 0
+    #[serde(rename = "@Image")]
+    pub image: usize,
+    #[serde(rename = "@Type", skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    #[serde(rename = "@Bookmark", skip_serializing_if = "Option::is_none")]
+    pub bookmark: Option<String>,
+}
+
+/// A chapter mark, stored `ComicRack` style in the `Bookmark` attribute of the page information
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Bookmark {
+    /// Index of the page, starting at 0
+    pub page: usize,
+    pub title: String,
+}
+
+impl FromStr for Bookmark {
+    type Err = Error;
+
+    /// Parses bookmarks like `12:Chapter 2`, the page number starts at 1
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::MetadataValue(format!("invalid bookmark {s}, expected PAGE:TITLE"));
+        let (page, title) = s.split_once(':').ok_or_else(invalid)?;
+        let page = page.trim().parse::<usize>().map_err(|_| invalid())?;
+        let title = title.trim();
+        if page == 0 || title.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            page: page - 1,
+            title: title.to_string(),
+        })
+    }
+}
+
+impl ComicInfo {
+    /// The bookmarked pages, in page order
+    #[must_use]
+    pub fn bookmarks(&self) -> Vec<Bookmark> {
+        let mut bookmarks = self
+            .pages
+            .iter()
+            .flat_map(|pages| &pages.pages)
+            .filter_map(|page| {
+                Some(Bookmark {
+                    page: page.image,
+                    title: page.bookmark.clone()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        bookmarks.sort_by_key(|bookmark| bookmark.page);
+        bookmarks
+    }
+
+    /// Replaces the bookmarks, the other information of the pages (like their type) are kept
+    pub fn set_bookmarks(&mut self, bookmarks: impl IntoIterator<Item = Bookmark>) {
+        let mut pages = self
+            .pages
+            .take()
+            .map(|pages| pages.pages)
+            .unwrap_or_default();
+        for page in &mut pages {
+            page.bookmark = None;
+        }
+        for bookmark in bookmarks {
+            match pages.iter_mut().find(|page| page.image == bookmark.page) {
+                Some(page) => page.bookmark = Some(bookmark.title),
+                None => pages.push(ComicPageInfo {
+                    image: bookmark.page,
+                    type_: None,
+                    bookmark: Some(bookmark.title),
+                }),
+            }
+        }
+        pages.retain(|page| page.type_.is_some() || page.bookmark.is_some());
+        pages.sort_by_key(|page| page.image);
+        self.pages = (!pages.is_empty()).then_some(ComicPages { pages });
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the xml is invalid
+    pub fn try_from_xml(xml: &str) -> Result<Self> {
+        Ok(quick_xml::de::from_str(xml)?)
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the metadata can't be serialized
+    pub fn try_to_xml(&self) -> Result<String> {
+        let xml = quick_xml::se::to_string(self)?;
+
+        Ok(format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n{xml}"))
+    }
+}
+
+fn role_field<'a>(comic_info: &'a mut ComicInfo, role: &str) -> Option<&'a mut Option<String>> {
+    match role.to_lowercase().as_str() {
+        "writer" | "author" | "story" | "scenario" => Some(&mut comic_info.writer),
+        "penciller" | "penciler" | "artist" | "art" | "illustrator" => {
+            Some(&mut comic_info.penciller)
+        }
+        "inker" => Some(&mut comic_info.inker),
+        "colorist" | "colourist" | "colors" => Some(&mut comic_info.colorist),
+        "letterer" => Some(&mut comic_info.letterer),
+        "cover" | "cover artist" | "coverartist" => Some(&mut comic_info.cover_artist),
+        "editor" => Some(&mut comic_info.editor),
+        _ => None,
+    }
+}
+
+fn split_list(list: Option<&String>) -> impl Iterator<Item = String> + '_ {
+    list.into_iter()
+        .flat_map(|list| list.split(','))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToString::to_string)
+}
+
+fn month_from_number(month: u8) -> Option<Month> {
+    Some(match month {
+        1 => Month::Jan,
+        2 => Month::Feb,
+        3 => Month::Mar,
+        4 => Month::Apr,
+        5 => Month::May,
+        6 => Month::Jun,
+        7 => Month::Jul,
+        8 => Month::Aug,
+        9 => Month::Sep,
+        10 => Month::Oct,
+        11 => Month::Nov,
+        12 => Month::Dec,
+        _ => return None,
+    })
+}
+
+/// The country has no `ComicInfo` equivalent and is lost, and the credits with an unknown role are dropped
+impl From<ComicBookInfoV1> for ComicInfo {
+    fn from(info: ComicBookInfoV1) -> Self {
+        let mut comic_info = Self {
+            title: info.title,
+            series: info.series,
+            number: info.issue.map(|issue| issue.to_string()),
+            count: info.number_of_issues,
+            volume: info.volume,
+            summary: info.comments,
+            year: info.publication_year,
+            month: info.publication_month.map(|month| month as u8),
+            publisher: info.publisher,
+            genre: info.genre,
+            tags: info.tags.map(|tags| tags.join(", ")),
+            language_iso: info.language,
+            community_rating: info.rating.map(f32::from),
+            ..Self::default()
+        };
+        for credit in info.credits.unwrap_or_default() {
+            let (Some(person), Some(role)) = (credit.person, credit.role) else {
+                continue;
+            };
+            let Some(field) = role_field(&mut comic_info, &role) else {
+                continue;
+            };
+            match field {
+                Some(people) => {
+                    people.push_str(", ");
+                    people.push_str(&person);
+                }
+                None => *field = Some(person),
+            }
+        }
+        comic_info
+    }
+}
+
+/// Only numeric issue numbers can be kept, `ComicBookInfo` doesn't support other values
+impl From<ComicInfo> for ComicBookInfoV1 {
+    fn from(comic_info: ComicInfo) -> Self {
+        let roles = [
+            ("Writer", &comic_info.writer),
+            ("Penciller", &comic_info.penciller),
+            ("Inker", &comic_info.inker),
+            ("Colorist", &comic_info.colorist),
+            ("Letterer", &comic_info.letterer),
+            ("CoverArtist", &comic_info.cover_artist),
+            ("Editor", &comic_info.editor),
+        ];
+        let mut credits = Vec::new();
+        for (role, people) in roles {
+            credits.extend(split_list(people.as_ref()).map(|person| Credit {
+                person: Some(person),
+                role: Some(role.to_string()),
+                primary: Some(Primary::Yes),
+            }));
+        }
+        let tags = split_list(comic_info.tags.as_ref()).collect::<Vec<_>>();
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let rating = comic_info
+            .community_rating
+            .map(|rating| rating.round().clamp(0.0, 5.0) as u8);
+
+        Self {
+            series: comic_info.series,
+            title: comic_info.title,
+            publisher: comic_info.publisher,
+            publication_month: comic_info.month.and_then(month_from_number),
+            publication_year: comic_info.year,
+            issue: comic_info.number.and_then(|number| number.parse().ok()),
+            number_of_issues: comic_info.count,
+            volume: comic_info.volume,
+            number_of_volumes: None,
+            rating,
+            genre: comic_info.genre,
+            language: comic_info.language_iso,
+            country: None,
+            comments: comic_info.summary,
+            credits: (!credits.is_empty()).then_some(credits),
+            tags: (!tags.is_empty()).then_some(tags),
+        }
+    }
+}
@@ -0,0 +1,161 @@
+#![cfg(feature = "manifest")]
+
+use std::{
+    collections::HashSet,
+    io::{Read, Seek},
+};
+
+use camino::Utf8Path;
+use serde::Serialize;
+
+use crate::{
+    cbz::Reader,
+    errors::Result,
+    manifest::{sha256, ManifestPage},
+};
+
+/// The differences between two archives, the pages are compared by position
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff {
+    pub left_pages: usize,
+    pub right_pages: usize,
+    /// The entries only present in the right archive
+    pub added: Vec<String>,
+    /// The entries only present in the left archive
+    pub removed: Vec<String>,
+    /// The positions where the pages content or dimensions differ
+    pub pages: Vec<PageDiff>,
+    /// The pages are the same, but in a different order
+    pub reordered: bool,
+    /// The metadata files, and the zip comment (`comment`), that differ
+    pub metadata: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageDiff {
+    /// The page number, starting at 1
+    pub page: usize,
+    pub left: Option<ManifestPage>,
+    pub right: Option<ManifestPage>,
+}
+
+impl Diff {
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.left_pages == self.right_pages
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.pages.is_empty()
+            && self.metadata.is_empty()
+    }
+}
+
+/// ## Errors
+///
+/// Fails if an archive can't be opened, or if an entry can't be read
+pub fn diff_files(left: impl AsRef<Utf8Path>, right: impl AsRef<Utf8Path>) -> Result<Diff> {
+    let mut left = Reader::try_from_path(left.as_ref())?;
+    let mut right = Reader::try_from_path(right.as_ref())?;
+
+    diff(&mut left, &mut right)
+}
+
+/// ## Errors
+///
+/// Fails if an entry can't be read
+pub fn diff<L, R>(left: &mut Reader<L>, right: &mut Reader<R>) -> Result<Diff>
+where
+    L: Read + Seek,
+    R: Read + Seek,
+{
+    let left_pages = pages(left)?;
+    let right_pages = pages(right)?;
+
+    let left_names = left.file_names().into_iter().collect::<HashSet<_>>();
+    let right_names = right.file_names().into_iter().collect::<HashSet<_>>();
+    let mut added = right_names
+        .difference(&left_names)
+        .cloned()
+        .collect::<Vec<_>>();
+    added.sort();
+    let mut removed = left_names
+        .difference(&right_names)
+        .cloned()
+        .collect::<Vec<_>>();
+    removed.sort();
+
+    let mut pages = Vec::new();
+    for index in 0..left_pages.len().max(right_pages.len()) {
+        let (left, right) = (left_pages.get(index), right_pages.get(index));
+        let same = matches!((left, right), (Some(left), Some(right)) if left.sha256 == right.sha256
+                && left.width == right.width
+                && left.height == right.height);
+        if !same {
+            pages.push(PageDiff {
+                page: index + 1,
+                left: left.cloned(),
+                right: right.cloned(),
+            });
+        }
+    }
+
+    let left_hashes = left_pages
+        .iter()
+        .map(|page| &page.sha256)
+        .collect::<Vec<_>>();
+    let right_hashes = right_pages
+        .iter()
+        .map(|page| &page.sha256)
+        .collect::<Vec<_>>();
+    let mut sorted_left_hashes = left_hashes.clone();
+    sorted_left_hashes.sort();
+    let mut sorted_right_hashes = right_hashes.clone();
+    sorted_right_hashes.sort();
+    let reordered = left_hashes != right_hashes && sorted_left_hashes == sorted_right_hashes;
+
+    let mut metadata = Vec::new();
+    if left.archive().comment() != right.archive().comment() {
+        metadata.push("comment".to_string());
+    }
+    let mut sidecars = left.sidecars();
+    sidecars.extend(right.sidecars());
+    sidecars.sort();
+    sidecars.dedup();
+    for name in sidecars {
+        if left.read_bytes_by_name(&name).ok() != right.read_bytes_by_name(&name).ok() {
+            metadata.push(name);
+        }
+    }
+
+    Ok(Diff {
+        left_pages: left_pages.len(),
+        right_pages: right_pages.len(),
+        added,
+        removed,
+        pages,
+        reordered,
+        metadata,
+    })
+}
+
+/// Hashes the pages, the pages that can't be decoded have no dimensions
+fn pages<R>(reader: &mut Reader<R>) -> Result<Vec<ManifestPage>>
+where
+    R: Read + Seek,
+{
+    let mut pages = Vec::with_capacity(reader.len());
+    for name in reader.file_names() {
+        let bytes = reader.read_bytes_by_name(&name)?;
+        let page =
+            ManifestPage::try_from_bytes(name.as_str(), &bytes).unwrap_or_else(|_| ManifestPage {
+                sha256: sha256(&bytes),
+                name,
+                width: 0,
+                height: 0,
+                format: None,
+            });
+        pages.push(page);
+    }
+
+    Ok(pages)
+}
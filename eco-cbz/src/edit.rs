@@ -0,0 +1,194 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    str::FromStr,
+};
+
+use camino::Utf8Path;
+use tracing::debug;
+use zip::{read::ZipFile, write::FileOptions, ZipWriter};
+
+use crate::cbz::Reader;
+use crate::errors::{write_error, Error, Result};
+use crate::image::Image;
+
+/// Designates a page of a Cbz
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PageRef {
+    /// Index of the page in the sorted pages list, starting at 0
+    Index(usize),
+    /// Name of the page inside the archive
+    Name(String),
+}
+
+impl FromStr for PageRef {
+    type Err = std::convert::Infallible;
+
+    /// Numbers are parsed as page numbers starting at 1, anything else is used as a page name
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<usize>() {
+            Ok(page) if page > 0 => Ok(Self::Index(page - 1)),
+            _ => Ok(Self::Name(s.to_string())),
+        }
+    }
+}
+
+enum Edit<'a> {
+    Remove,
+    Replace { image: &'a Image, bytes: &'a [u8] },
+}
+
+/// Removes the page from the Cbz, and returns its name.
+/// The other entries and the metadata are copied as is, without being decompressed.
+///
+/// ## Errors
+///
+/// Fails if the page doesn't exist, or if the archive can't be read or rewritten
+pub fn remove_page(path: impl AsRef<Utf8Path>, page: &PageRef) -> Result<String> {
+    edit_page(path.as_ref(), page, &Edit::Remove)
+}
+
+/// Replaces the content of the page, and returns its new name.
+/// The name is kept, only its extension is updated when the new image format is different.
+/// The other entries and the metadata are copied as is, without being decompressed.
+///
+/// ## Errors
+///
+/// Fails if the page doesn't exist, if the bytes are not a valid image,
+/// or if the archive can't be read or rewritten
+pub fn replace_page(path: impl AsRef<Utf8Path>, page: &PageRef, bytes: &[u8]) -> Result<String> {
+    let image = Image::try_from_bytes(bytes)?;
+    edit_page(
+        path.as_ref(),
+        page,
+        &Edit::Replace {
+            image: &image,
+            bytes,
+        },
+    )
+}
+
+/// Rewrites the archive, the entries accepted by `keep` are copied as is, and the `extra` entries are appended.
+/// The archive is written next to the original one, and only replaces it once complete.
+#[cfg_attr(not(feature = "metadata"), allow(dead_code))]
+pub(crate) fn rewrite<F>(
+    path: &Utf8Path,
+    comment: &[u8],
+    keep: F,
+    extra: &[(&str, &[u8])],
+) -> Result<()>
+where
+    F: Fn(&str) -> bool,
+{
+    rewrite_entries(
+        path,
+        comment,
+        |file, writer| {
+            if keep(file.name()) {
+                writer.raw_copy_file(file)?;
+            }
+            Ok(())
+        },
+        extra,
+    )
+}
+
+/// Same as `rewrite`, each entry is given to `copy`, which writes it or not.
+/// The temporary archive is removed when the rewriting fails.
+fn rewrite_entries<F>(
+    path: &Utf8Path,
+    comment: &[u8],
+    copy: F,
+    extra: &[(&str, &[u8])],
+) -> Result<()>
+where
+    F: FnMut(ZipFile<'_>, &mut ZipWriter<File>) -> Result<()>,
+{
+    let mut reader = Reader::try_from_path(path)?;
+    let tmp_path = path.with_extension("cbz.tmp");
+    let written = write_entries(&mut reader, &tmp_path, comment, copy, extra);
+    drop(reader);
+    if let Err(err) = written.and_then(|()| fs::rename(&tmp_path, path).map_err(write_error)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+fn write_entries<F>(
+    reader: &mut Reader<File>,
+    tmp_path: &Utf8Path,
+    comment: &[u8],
+    mut copy: F,
+    extra: &[(&str, &[u8])],
+) -> Result<()>
+where
+    F: FnMut(ZipFile<'_>, &mut ZipWriter<File>) -> Result<()>,
+{
+    let mut writer = ZipWriter::new(File::create(tmp_path).map_err(write_error)?);
+    writer.set_comment(String::from_utf8_lossy(comment));
+    let archive = reader.archive_mut();
+    for index in 0..archive.len() {
+        copy(archive.by_index_raw(index)?, &mut writer)?;
+    }
+    for (name, bytes) in extra {
+        writer
+            .start_file(*name, FileOptions::default())
+            .map_err(write_error)?;
+        writer.write_all(bytes).map_err(write_error)?;
+    }
+    writer.finish().map_err(write_error)?;
+
+    Ok(())
+}
+
+fn edit_page(path: &Utf8Path, page: &PageRef, edit: &Edit<'_>) -> Result<String> {
+    let reader = Reader::try_from_path(path)?;
+    let name = match page {
+        PageRef::Index(index) => reader
+            .entries()
+            .get(*index)
+            .cloned()
+            .ok_or(Error::CbzNotFound(*index))?,
+        PageRef::Name(name) if reader.entries().contains(name) => name.clone(),
+        PageRef::Name(name) => return Err(Error::CbzPageNotFound(name.clone())),
+    };
+    let comment = reader.archive().comment().to_vec();
+    drop(reader);
+
+    let mut new_name = name.clone();
+    rewrite_entries(
+        path,
+        &comment,
+        |file, writer| {
+            if file.name() != name {
+                writer.raw_copy_file(file)?;
+                return Ok(());
+            }
+            if let Edit::Replace { image, bytes } = edit {
+                if let Some(extension) = image
+                    .format()
+                    .and_then(|format| format.extensions_str().first().copied())
+                {
+                    new_name = Utf8Path::new(&name).with_extension(extension).into_string();
+                }
+                let options = FileOptions::default()
+                    .compression_method(file.compression())
+                    .last_modified_time(file.last_modified());
+                drop(file);
+                debug!("replacing {name} with {new_name}");
+                writer
+                    .start_file(new_name.as_str(), options)
+                    .map_err(write_error)?;
+                writer.write_all(bytes).map_err(write_error)?;
+            } else {
+                debug!("removing {name}");
+            }
+            Ok(())
+        },
+        &[],
+    )?;
+
+    Ok(new_name)
+}
@@ -8,6 +8,9 @@ pub enum Error {
     #[error("zip error {0}")]
     Zip(#[from] ZipError),
 
+    #[error("output couldn't be written: {0}")]
+    Write(#[source] std::io::Error),
+
     #[error("cbz file size couldn't be converted")]
     CbzFileSizeConversion,
 
@@ -20,6 +23,9 @@ pub enum Error {
     #[error("file at index {0} not found in cbz")]
     CbzNotFound(usize),
 
+    #[error("page {0} not found in cbz")]
+    CbzPageNotFound(String),
+
     #[error("cbz is too large, it can contain a maximum of {0} files")]
     CbzTooLarge(usize),
 
@@ -29,19 +35,68 @@ pub enum Error {
     #[error("cbz file insertion: no bytes set")]
     CbzInsertionNoBytes,
 
+    #[error("cbz is password protected, a password is required")]
+    CbzPasswordRequired,
+
+    #[error("cbz password is invalid")]
+    CbzInvalidPassword,
+
     #[error("cbz metadata is too large: {0} > 65,535")]
     CbzMetadataSize(usize),
 
     #[error("image error: {0}")]
     Image(#[from] image::ImageError),
 
-    #[cfg(feature = "metadata")]
+    #[error("invalid color: {0}, expected something like #1f2937")]
+    InvalidColor(String),
+
+    #[cfg(feature = "jxl")]
+    #[error("jpeg xl error: {0}")]
+    Jxl(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    #[cfg(feature = "icc")]
+    #[error("color profile error: {0}")]
+    Icc(#[from] lcms2::Error),
+
+    #[cfg(feature = "async")]
+    #[error("async task error: {0}")]
+    AsyncTask(#[from] tokio::task::JoinError),
+
+    #[cfg(any(feature = "metadata", feature = "manifest"))]
     #[error("metadata error: {0}")]
     MetadataFormat(#[from] serde_json::Error),
 
+    #[cfg(feature = "metadata")]
+    #[error("comic info error: {0}")]
+    ComicInfo(#[from] quick_xml::DeError),
+
+    #[cfg(feature = "manifest")]
+    #[error("cbz has no manifest")]
+    CbzManifestNotFound,
+
     #[cfg(feature = "metadata")]
     #[error("metadata value error: {0}")]
     MetadataValue(String),
 }
 
+impl Error {
+    /// The archive or the image can't be read, it's corrupted or in an unsupported format
+    #[must_use]
+    pub fn is_unsupported_format(&self) -> bool {
+        matches!(
+            self,
+            Self::Zip(ZipError::InvalidArchive(_) | ZipError::UnsupportedArchive(_))
+                | Self::Image(image::ImageError::Decoding(_) | image::ImageError::Unsupported(_))
+        )
+    }
+}
+
+/// The io errors of the writers are failures to write the output, the ones of the readers are kept as is
+pub(crate) fn write_error(err: impl Into<Error>) -> Error {
+    match err.into() {
+        Error::IO(err) | Error::Zip(ZipError::Io(err)) => Error::Write(err),
+        err => err,
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
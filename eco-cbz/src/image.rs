@@ -3,59 +3,155 @@ use std::{
     path::Path,
 };
 
-use image::{io::Reader as ImageReader, DynamicImage, ImageFormat};
+#[cfg(feature = "avif")]
+use image::codecs::avif::AvifEncoder;
+#[cfg(feature = "webp-encoder")]
+use image::codecs::webp::{WebPEncoder, WebPQuality};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops, imageops::FilterType, io::Reader as ImageReader,
+    DynamicImage, ImageEncoder, ImageFormat, RgbImage, Rgba, RgbaImage,
+};
+#[cfg(feature = "icc")]
+use image::{
+    codecs::{jpeg::JpegDecoder, png::PngDecoder},
+    ImageDecoder,
+};
+use tracing::debug;
 use zip::read::ZipFile;
 
 use crate::errors::{Error, Result};
 
+/// Sigma of the blur removing the halftone dots, enough for the usual 300-600 dpi scans
+const DESCREEN_BLUR: f32 = 1.2;
+
+/// Sigma and threshold of the unsharp mask restoring the lines after the halftone dots are removed
+const DESCREEN_SHARPEN: f32 = 2.5;
+const DESCREEN_THRESHOLD: i32 = 4;
+
+/// Luma difference with the margins color under which a pixel still belongs to the margins,
+/// tolerates the scan noise and the compression artifacts
+const MARGIN_TOLERANCE: u8 = 24;
+
+/// A row or a column belongs to the margins if less than 1 pixel in this amount differs,
+/// so a few specks of dust don't stop the trimming
+const MARGIN_SPECKS_RATIO: usize = 200;
+
+/// Quality of the avif pages when none is provided, the text stays sharp and the pages are still small
+#[cfg(feature = "avif")]
+const AVIF_QUALITY: u8 = 80;
+
+/// Between 1 (slowest, smallest) and 10, the slowest speeds take several seconds per page
+#[cfg(feature = "avif")]
+const AVIF_SPEED: u8 = 6;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ReadingOrder {
     Rtl,
     Ltr,
 }
 
+/// The filter used to resize the images, from the fastest to the sharpest
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resampling {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    /// Keeps the screentones sharp, but it's the slowest
+    Lanczos3,
+}
+
+impl From<Resampling> for FilterType {
+    fn from(value: Resampling) -> Self {
+        match value {
+            Resampling::Nearest => Self::Nearest,
+            Resampling::Triangle => Self::Triangle,
+            Resampling::CatmullRom => Self::CatmullRom,
+            Resampling::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// The format the pages are encoded in, instead of the format of their source image
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageFormat {
+    Jpeg,
+    Png,
+    WebP,
+    /// Lossless JPEG XL, much smaller than png for the line art
+    #[cfg(feature = "jxl")]
+    Jxl,
+    #[cfg(feature = "avif")]
+    Avif,
+}
+
+impl PageFormat {
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::WebP => "webp",
+            #[cfg(feature = "jxl")]
+            Self::Jxl => "jxl",
+            #[cfg(feature = "avif")]
+            Self::Avif => "avif",
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Image {
     dynamic_image: DynamicImage,
     format: Option<ImageFormat>,
+    /// The embedded ICC profile of the decoded jpeg and png images, the transformed images don't keep it
+    icc_profile: Option<Vec<u8>>,
+    /// The encoded upright jpeg images, so they can be split losslessly, see `try_from_bytes_keeping_jpeg`
+    jpeg_bytes: Option<Vec<u8>>,
 }
 
 impl Image {
+    /// The image is rotated and flipped according to its EXIF orientation, if any
+    ///
     /// ## Errors
     ///
     /// Fails if the image can't be open or decoded
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        let reader = ImageReader::open(&path)?;
-        let format = reader.format();
-        Ok(Self {
-            dynamic_image: reader.decode()?,
-            format,
-        })
+        Self::decode(ImageReader::open(path)?)
     }
 
+    /// The image is rotated and flipped according to its EXIF orientation, if any
+    ///
     /// ## Errors
     ///
     /// Fails if the image format can't be guessed or the image can't be decoded
     pub fn try_from_reader(reader: impl BufRead + Seek) -> Result<Self> {
-        let reader = ImageReader::new(reader).with_guessed_format()?;
-        let format = reader.format();
-        Ok(Self {
-            dynamic_image: reader.decode()?,
-            format,
-        })
+        Self::decode(ImageReader::new(reader).with_guessed_format()?)
     }
 
+    /// The image is rotated and flipped according to its EXIF orientation, if any
+    ///
     /// ## Errors
     ///
     /// Fails if the image format can't be guessed or the image can't be decoded
     pub fn try_from_bytes(bytes: &[u8]) -> Result<Self> {
-        let buf = Cursor::new(bytes);
-        let reader = ImageReader::new(buf).with_guessed_format()?;
-        let format = reader.format();
-        Ok(Self {
-            dynamic_image: reader.decode()?,
-            format,
-        })
+        Self::try_from_reader(Cursor::new(bytes))
+    }
+
+    /// Same as `try_from_bytes`, but the upright jpegs also keep their encoded bytes so they can be split losslessly.
+    /// The transformed images don't keep them.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the image format can't be guessed or the image can't be decoded
+    pub fn try_from_bytes_keeping_jpeg(bytes: Vec<u8>) -> Result<Self> {
+        let mut img = Self::try_from_bytes(&bytes)?;
+        if img.format == Some(ImageFormat::Jpeg)
+            && read_orientation(&mut Cursor::new(&bytes))?.unwrap_or(1) == 1
+        {
+            img.jpeg_bytes = Some(bytes);
+        }
+
+        Ok(img)
     }
 
     #[allow(clippy::missing_errors_doc)]
@@ -67,11 +163,123 @@ impl Image {
         Self::try_from_bytes(&buf)
     }
 
+    /// The metadata are read first, then the reader is rewound to decode the image
+    fn decode<R: BufRead + Seek>(reader: ImageReader<R>) -> Result<Self> {
+        let format = reader.format();
+        let mut inner = reader.into_inner();
+        #[cfg(feature = "jxl")]
+        if format.is_none() && crate::jxl::is_jxl(inner.fill_buf()?) {
+            return Ok(Self::from_dynamic_image(crate::jxl::decode(inner)?, None));
+        }
+        let orientation = read_orientation(&mut inner)?;
+        #[cfg(feature = "icc")]
+        let icc_profile = read_icc_profile(&mut inner, format)?;
+        #[cfg(not(feature = "icc"))]
+        let icc_profile = None;
+        #[cfg(feature = "icc")]
+        if let (Some(ImageFormat::Jpeg), Some(profile)) = (format, &icc_profile) {
+            if let Some(dynamic_image) = decode_cmyk_jpeg(&mut inner, profile)? {
+                return Ok(Self::from_dynamic_image(
+                    apply_orientation(dynamic_image, orientation),
+                    format,
+                ));
+            }
+        }
+        let mut reader = ImageReader::new(inner);
+        if let Some(format) = format {
+            reader.set_format(format);
+        }
+        Ok(Self {
+            dynamic_image: apply_orientation(reader.decode()?, orientation),
+            format,
+            icc_profile,
+            jpeg_bytes: None,
+        })
+    }
+
     fn from_dynamic_image(dynamic_image: DynamicImage, format: Option<ImageFormat>) -> Self {
         Self {
             dynamic_image,
             format,
+            icc_profile: None,
+            jpeg_bytes: None,
+        }
+    }
+
+    #[must_use]
+    pub fn icc_profile(&self) -> Option<&[u8]> {
+        self.icc_profile.as_deref()
+    }
+
+    /// The encoded image, for the jpegs decoded by `try_from_bytes_keeping_jpeg` that haven't been transformed since
+    #[must_use]
+    pub fn jpeg_bytes(&self) -> Option<&[u8]> {
+        self.jpeg_bytes.as_deref()
+    }
+
+    /// Converts the colors from the embedded ICC profile (e.g. Adobe RGB) to sRGB, for the readers ignoring the profiles.
+    /// Only the RGB and grayscale profiles are supported, the CMYK jpegs are converted to sRGB with their profile as they're decoded.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the profile is invalid
+    #[cfg(feature = "icc")]
+    pub fn into_srgb(self) -> Result<Self> {
+        use lcms2::{ColorSpaceSignature, Intent, PixelFormat, Profile, Transform};
+
+        let Some(icc_profile) = &self.icc_profile else {
+            return Ok(self);
+        };
+        let profile = Profile::new_icc(icc_profile)?;
+        let srgb = Profile::new_srgb();
+        let rgb = match profile.color_space() {
+            ColorSpaceSignature::RgbData => {
+                let mut pixels = self
+                    .dynamic_image
+                    .to_rgb8()
+                    .pixels()
+                    .map(|pixel| pixel.0)
+                    .collect::<Vec<_>>();
+                let transform = Transform::<[u8; 3], [u8; 3]>::new(
+                    &profile,
+                    PixelFormat::RGB_8,
+                    &srgb,
+                    PixelFormat::RGB_8,
+                    Intent::Perceptual,
+                )?;
+                transform.transform_in_place(&mut pixels);
+                pixels
+            }
+            ColorSpaceSignature::GrayData => {
+                let luma = self.dynamic_image.to_luma8().into_raw();
+                let mut pixels = vec![[0; 3]; luma.len()];
+                let transform = Transform::<u8, [u8; 3]>::new(
+                    &profile,
+                    PixelFormat::GRAY_8,
+                    &srgb,
+                    PixelFormat::RGB_8,
+                    Intent::Perceptual,
+                )?;
+                transform.transform_pixels(&luma, &mut pixels);
+                pixels
+            }
+            _ => {
+                debug!("ignoring the unsupported color profile");
+                return Ok(Self::from_dynamic_image(self.dynamic_image, self.format));
+            }
+        };
+        // The alpha channel is kept as is
+        let mut rgba = self.dynamic_image.to_rgba8();
+        for (pixel, [r, g, b]) in rgba.pixels_mut().zip(rgb) {
+            *pixel = Rgba([r, g, b, pixel[3]]);
         }
+        let dynamic_image = if self.dynamic_image.color().has_alpha() {
+            DynamicImage::ImageRgba8(rgba)
+        } else {
+            DynamicImage::ImageRgba8(rgba).to_rgb8().into()
+        };
+
+        Ok(Self::from_dynamic_image(dynamic_image, self.format))
     }
 
     #[must_use]
@@ -99,6 +307,133 @@ impl Image {
         Self::from_dynamic_image(self.dynamic_image.blur(blur), self.format)
     }
 
+    /// Removes the halftone patterns (moiré) of the printed then scanned pages,
+    /// the dots are blurred away and the lines are sharpened back
+    #[must_use]
+    pub fn descreen(self) -> Self {
+        Self::from_dynamic_image(
+            self.dynamic_image
+                .blur(DESCREEN_BLUR)
+                .unsharpen(DESCREEN_SHARPEN, DESCREEN_THRESHOLD),
+            self.format,
+        )
+    }
+
+    /// Removes the noise with a median filter, each pixel takes the median value of its neighbors within the radius.
+    /// Unlike a blur, the edges are preserved.
+    #[must_use]
+    pub fn denoise(self, radius: u32) -> Self {
+        if radius == 0 {
+            return self;
+        }
+        let source = self.dynamic_image.to_rgba8();
+        let (width, height) = source.dimensions();
+        // A bigger radius takes the whole image anyway
+        let radius = radius.min(width.max(height));
+        let window_side = |size: u32| (2 * radius as usize + 1).min(size as usize);
+        let mut window = Vec::with_capacity(window_side(width) * window_side(height));
+        let denoised = RgbaImage::from_fn(width, height, |x, y| {
+            let xs = x.saturating_sub(radius)..=x.saturating_add(radius).min(width - 1);
+            let ys = y.saturating_sub(radius)..=y.saturating_add(radius).min(height - 1);
+            let mut pixel = [0; 4];
+            for (channel, value) in pixel.iter_mut().enumerate() {
+                window.clear();
+                for wy in ys.clone() {
+                    for wx in xs.clone() {
+                        window.push(source.get_pixel(wx, wy)[channel]);
+                    }
+                }
+                let middle = window.len() / 2;
+                *value = *window.select_nth_unstable(middle).1;
+            }
+            Rgba(pixel)
+        });
+
+        Self::from_dynamic_image(DynamicImage::ImageRgba8(denoised), self.format)
+    }
+
+    /// Shrinks the image so it fits in the bounds, the aspect ratio is preserved.
+    /// The images already fitting are left untouched, they're never enlarged.
+    #[must_use]
+    pub fn resize_to_fit(self, max_width: u32, max_height: u32, resampling: Resampling) -> Self {
+        if self.dynamic_image.width() <= max_width && self.dynamic_image.height() <= max_height {
+            return self;
+        }
+        Self::from_dynamic_image(
+            self.dynamic_image
+                .resize(max_width, max_height, resampling.into()),
+            self.format,
+        )
+    }
+
+    /// The crop is clamped to the image bounds
+    #[must_use]
+    pub fn crop(self, x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self::from_dynamic_image(
+            self.dynamic_image.crop_imm(x, y, width, height),
+            self.format,
+        )
+    }
+
+    /// The bounds `(x, y, width, height)` of the content, without the uniform margins around it.
+    /// The margins color is the one of the top left pixel,
+    /// returns `None` if the image is empty or has no content.
+    #[must_use]
+    pub fn content_bounds(&self) -> Option<(u32, u32, u32, u32)> {
+        let luma = self.dynamic_image.to_luma8();
+        let (width, height) = luma.dimensions();
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let margins_color = luma.get_pixel(0, 0)[0];
+        let is_content =
+            |x: u32, y: u32| luma.get_pixel(x, y)[0].abs_diff(margins_color) > MARGIN_TOLERANCE;
+        let has_content = |pixels: usize, content_pixels: usize| {
+            content_pixels > 0 && content_pixels >= pixels / MARGIN_SPECKS_RATIO
+        };
+        let row_has_content = |y: u32| {
+            has_content(
+                width as usize,
+                (0..width).filter(|x| is_content(*x, y)).count(),
+            )
+        };
+
+        let top = (0..height).find(|y| row_has_content(*y))?;
+        let bottom = (top..height).rev().find(|y| row_has_content(*y))?;
+        let rows = top..=bottom;
+        let column_has_content = |x: u32| {
+            has_content(
+                rows.clone().count(),
+                rows.clone().filter(|y| is_content(x, *y)).count(),
+            )
+        };
+        let left = (0..width).find(|x| column_has_content(*x))?;
+        let right = (left..width).rev().find(|x| column_has_content(*x))?;
+
+        Some((left, top, right - left + 1, bottom - top + 1))
+    }
+
+    /// Trims the uniform margins around the content, images without content are left untouched
+    #[must_use]
+    pub fn trim_margins(self) -> Self {
+        match self.content_bounds() {
+            Some((x, y, width, height)) => self.crop(x, y, width, height),
+            None => self,
+        }
+    }
+
+    /// Clockwise rotation, only multiples of 90 degrees are supported, the other angles are ignored
+    #[must_use]
+    pub fn rotate(self, degrees: u16) -> Self {
+        let dynamic_image = match degrees % 360 {
+            90 => self.dynamic_image.rotate90(),
+            180 => self.dynamic_image.rotate180(),
+            270 => self.dynamic_image.rotate270(),
+            _ => self.dynamic_image,
+        };
+        Self::from_dynamic_image(dynamic_image, self.format)
+    }
+
     #[must_use]
     pub fn autosplit(self, reading_order: ReadingOrder) -> (Image, Image) {
         let img1 = Self::from_dynamic_image(
@@ -125,6 +460,24 @@ impl Image {
         }
     }
 
+    /// Joins 2 pages into a single spread, it's the reverse of `autosplit`
+    #[must_use]
+    pub fn join(first: Image, second: Image, reading_order: ReadingOrder) -> Image {
+        let format = first.format;
+        let (left, right) = match reading_order {
+            ReadingOrder::Ltr => (first.dynamic_image, second.dynamic_image),
+            ReadingOrder::Rtl => (second.dynamic_image, first.dynamic_image),
+        };
+        let mut spread = RgbImage::new(
+            left.width() + right.width(),
+            left.height().max(right.height()),
+        );
+        imageops::overlay(&mut spread, &left.to_rgb8(), 0, 0);
+        imageops::overlay(&mut spread, &right.to_rgb8(), i64::from(left.width()), 0);
+
+        Self::from_dynamic_image(DynamicImage::ImageRgb8(spread), format)
+    }
+
     #[must_use]
     pub fn dynamic(&self) -> &DynamicImage {
         &self.dynamic_image
@@ -147,6 +500,172 @@ impl Image {
             .write_to(&mut buf, self.format.unwrap_or(ImageFormat::Png))?;
         Ok(buf.into_inner())
     }
+
+    /// Encodes the image in the format, whatever the format of its source.
+    /// The `quality`, between 1 and 100, is used by the lossy formats (jpeg, webp, and avif) only,
+    /// the webp images are lossless without it, or without the `webp-encoder` feature.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the image can't be encoded
+    pub fn try_into_bytes_with_format(
+        self,
+        format: PageFormat,
+        quality: Option<u8>,
+    ) -> Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        match (format, quality) {
+            // Jpeg has no alpha channel
+            (PageFormat::Jpeg, None) => {
+                DynamicImage::from(self.dynamic_image.to_rgb8())
+                    .write_to(&mut buf, ImageFormat::Jpeg)?;
+            }
+            (PageFormat::Jpeg, Some(quality)) => write_with_encoder(
+                JpegEncoder::new_with_quality(&mut buf, quality),
+                &self.dynamic_image.to_rgb8().into(),
+            )?,
+            (PageFormat::Png, _) => self.dynamic_image.write_to(&mut buf, ImageFormat::Png)?,
+            #[cfg(feature = "webp-encoder")]
+            (PageFormat::WebP, Some(quality)) => write_with_encoder(
+                WebPEncoder::new_with_quality(&mut buf, WebPQuality::lossy(quality)),
+                &to_8_bits(self.dynamic_image),
+            )?,
+            (PageFormat::WebP, _) => self.dynamic_image.write_to(&mut buf, ImageFormat::WebP)?,
+            #[cfg(feature = "jxl")]
+            (PageFormat::Jxl, _) => return crate::jxl::encode(&self.dynamic_image),
+            #[cfg(feature = "avif")]
+            (PageFormat::Avif, quality) => write_with_encoder(
+                AvifEncoder::new_with_speed_quality(
+                    &mut buf,
+                    AVIF_SPEED,
+                    quality.unwrap_or(AVIF_QUALITY),
+                ),
+                &to_8_bits(self.dynamic_image),
+            )?,
+        }
+        Ok(buf.into_inner())
+    }
+}
+
+fn write_with_encoder(encoder: impl ImageEncoder, dynamic_image: &DynamicImage) -> Result<()> {
+    encoder.write_image(
+        dynamic_image.as_bytes(),
+        dynamic_image.width(),
+        dynamic_image.height(),
+        dynamic_image.color(),
+    )?;
+    Ok(())
+}
+
+/// The lossy encoders only support the rgb and rgba images with 8 bits per channel
+#[cfg(any(feature = "avif", feature = "webp-encoder"))]
+fn to_8_bits(dynamic_image: DynamicImage) -> DynamicImage {
+    match dynamic_image {
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => dynamic_image,
+        _ if dynamic_image.color().has_alpha() => dynamic_image.to_rgba8().into(),
+        _ => dynamic_image.to_rgb8().into(),
+    }
+}
+
+/// Reads the EXIF orientation tag, between 1 and 8, of the jpeg, png, tiff, and webp images,
+/// the reader is rewound so the image can be decoded afterward
+fn read_orientation(reader: &mut (impl BufRead + Seek)) -> Result<Option<u32>> {
+    let orientation = match exif::Reader::new().read_from_container(reader) {
+        Ok(exif) => exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .and_then(|field| field.value.get_uint(0)),
+        // Most images don't have any EXIF data
+        Err(_) => None,
+    };
+    reader.rewind()?;
+    Ok(orientation)
+}
+
+/// Reads the embedded ICC profile of the jpeg and png images,
+/// the reader is rewound so the image can be decoded afterward
+#[cfg(feature = "icc")]
+fn read_icc_profile(
+    reader: &mut (impl BufRead + Seek),
+    format: Option<ImageFormat>,
+) -> Result<Option<Vec<u8>>> {
+    let icc_profile = match format {
+        Some(ImageFormat::Jpeg) => JpegDecoder::new(&mut *reader)
+            .map(|mut decoder| decoder.icc_profile())
+            .map_err(|err| err.to_string()),
+        Some(ImageFormat::Png) => PngDecoder::new(&mut *reader)
+            .map(|mut decoder| decoder.icc_profile())
+            .map_err(|err| err.to_string()),
+        _ => Ok(None),
+    };
+    reader.rewind()?;
+    // The broken headers are reported by the decoding itself
+    Ok(icc_profile.unwrap_or_else(|err| {
+        debug!("couldn't read the color profile: {err}");
+        None
+    }))
+}
+
+/// The CMYK jpegs are decoded with their profile, the generic decoder converts them to RGB ignoring it.
+/// Returns `None`, the reader being rewound, if the jpeg isn't CMYK or the profile can't be used.
+#[cfg(feature = "icc")]
+fn decode_cmyk_jpeg(
+    reader: &mut (impl BufRead + Seek),
+    icc_profile: &[u8],
+) -> Result<Option<DynamicImage>> {
+    use lcms2::{ColorSpaceSignature, Intent, PixelFormat, Profile, Transform};
+
+    let profile = match Profile::new_icc(icc_profile) {
+        Ok(profile) if profile.color_space() == ColorSpaceSignature::CmykData => profile,
+        _ => return Ok(None),
+    };
+    let mut decoder = jpeg_decoder::Decoder::new(&mut *reader);
+    let samples = decoder.decode();
+    let info = decoder.info();
+    reader.rewind()?;
+    let (samples, info) = match (samples, info) {
+        (Ok(samples), Some(info)) if info.pixel_format == jpeg_decoder::PixelFormat::CMYK32 => {
+            (samples, info)
+        }
+        _ => return Ok(None),
+    };
+    // The decoder returns the amount of ink of each channel, like the profiles expect
+    let cmyk = samples
+        .chunks_exact(4)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+        .collect::<Vec<_>>();
+    let mut rgb = vec![[0; 3]; cmyk.len()];
+    let transform = Transform::<[u8; 4], [u8; 3]>::new(
+        &profile,
+        PixelFormat::CMYK_8,
+        &Profile::new_srgb(),
+        PixelFormat::RGB_8,
+        Intent::Perceptual,
+    )?;
+    transform.transform_pixels(&cmyk, &mut rgb);
+
+    Ok(
+        RgbImage::from_raw(u32::from(info.width), u32::from(info.height), rgb.concat())
+            .map(DynamicImage::ImageRgb8),
+    )
+}
+
+/// The pixels are transformed so the image is displayed upright without its EXIF data,
+/// which is dropped when the image is encoded again
+fn apply_orientation(dynamic_image: DynamicImage, orientation: Option<u32>) -> DynamicImage {
+    match orientation {
+        Some(2) => dynamic_image.fliph(),
+        Some(3) => dynamic_image.rotate180(),
+        Some(4) => dynamic_image.flipv(),
+        Some(5) => dynamic_image.rotate90().fliph(),
+        Some(6) => dynamic_image.rotate90(),
+        Some(7) => dynamic_image.rotate270().fliph(),
+        Some(8) => dynamic_image.rotate270(),
+        Some(1) | None => dynamic_image,
+        Some(orientation) => {
+            debug!("ignoring the invalid EXIF orientation {orientation}");
+            dynamic_image
+        }
+    }
 }
 
 impl TryFrom<Image> for Vec<u8> {
@@ -165,6 +684,12 @@ impl<'a> TryFrom<ZipFile<'a>> for Image {
     }
 }
 
+impl From<DynamicImage> for Image {
+    fn from(dynamic_image: DynamicImage) -> Self {
+        Self::from_dynamic_image(dynamic_image, None)
+    }
+}
+
 impl TryFrom<&[u8]> for Image {
     type Error = Error;
 
@@ -0,0 +1,96 @@
+use std::io::Read;
+
+use image::{
+    ColorType, DynamicImage, GrayAlphaImage, GrayImage, ImageBuffer, Pixel, RgbImage, RgbaImage,
+};
+use jxl_oxide::JxlImage;
+use zune_core::{bit_depth::BitDepth, colorspace::ColorSpace, options::EncoderOptions};
+use zune_jpegxl::JxlSimpleEncoder;
+
+use crate::errors::{Error, Result};
+
+/// Signature of the bare codestreams
+const CODESTREAM_SIGNATURE: &[u8] = &[0xff, 0x0a];
+
+/// Signature of the codestreams wrapped in an ISO BMFF container, with their metadata
+const CONTAINER_SIGNATURE: &[u8] = &[
+    0x00, 0x00, 0x00, 0x0c, 0x4a, 0x58, 0x4c, 0x20, 0x0d, 0x0a, 0x87, 0x0a,
+];
+
+/// The `image` crate doesn't know the JPEG XL format, so it's recognized from its first bytes
+pub fn is_jxl(bytes: &[u8]) -> bool {
+    bytes.starts_with(CODESTREAM_SIGNATURE) || bytes.starts_with(CONTAINER_SIGNATURE)
+}
+
+/// Decodes the first frame, the images with more than 8 bits per channel are reduced to 8 bits
+pub fn decode(reader: impl Read) -> Result<DynamicImage> {
+    let image = JxlImage::from_reader(reader).map_err(|err| Error::Jxl(err.into()))?;
+    let render = image
+        .render_frame(0)
+        .map_err(|err| Error::Jxl(err.into()))?;
+    let framebuffer = render.image();
+    #[allow(clippy::cast_possible_truncation)]
+    let (width, height) = (framebuffer.width() as u32, framebuffer.height() as u32);
+    // The samples are floats between 0 and 1
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let samples = framebuffer
+        .buf()
+        .iter()
+        .map(|sample| (sample.clamp(0.0, 1.0) * 255.0).round() as u8)
+        .collect::<Vec<_>>();
+    let dynamic_image = match framebuffer.channels() {
+        1 => GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+        2 => GrayAlphaImage::from_raw(width, height, samples).map(DynamicImage::ImageLumaA8),
+        3 => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+        4 => RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8),
+        channels => {
+            return Err(Error::Jxl(
+                format!("images with {channels} channels are not supported").into(),
+            ))
+        }
+    };
+
+    dynamic_image.ok_or_else(|| Error::Jxl("the decoded image is truncated".into()))
+}
+
+/// Reads the dimensions from the headers, without rendering the image
+pub fn dimensions(bytes: &[u8]) -> Result<(u32, u32)> {
+    let image = JxlImage::from_reader(bytes).map_err(|err| Error::Jxl(err.into()))?;
+    let header = image.image_header();
+    // The orientations 5 to 8 transpose the image
+    if header.metadata.orientation > 4 {
+        Ok((header.size.height, header.size.width))
+    } else {
+        Ok((header.size.width, header.size.height))
+    }
+}
+
+/// Encodes the image losslessly, with 8 bits per channel
+pub fn encode(dynamic_image: &DynamicImage) -> Result<Vec<u8>> {
+    match dynamic_image.color() {
+        ColorType::L8 | ColorType::L16 => {
+            encode_buffer(&dynamic_image.to_luma8(), ColorSpace::Luma)
+        }
+        ColorType::La8 | ColorType::La16 => {
+            encode_buffer(&dynamic_image.to_luma_alpha8(), ColorSpace::LumaA)
+        }
+        color if color.has_alpha() => encode_buffer(&dynamic_image.to_rgba8(), ColorSpace::RGBA),
+        _ => encode_buffer(&dynamic_image.to_rgb8(), ColorSpace::RGB),
+    }
+}
+
+fn encode_buffer<P>(buffer: &ImageBuffer<P, Vec<u8>>, colorspace: ColorSpace) -> Result<Vec<u8>>
+where
+    P: Pixel<Subpixel = u8>,
+{
+    let options = EncoderOptions::new(
+        buffer.width() as usize,
+        buffer.height() as usize,
+        colorspace,
+        BitDepth::Eight,
+    );
+
+    JxlSimpleEncoder::new(buffer.as_raw(), options)
+        .encode()
+        .map_err(|err| Error::Jxl(format!("{err:?}").into()))
+}
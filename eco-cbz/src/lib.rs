@@ -1,15 +1,48 @@
 #![deny(clippy::all, clippy::pedantic)]
 
+#[cfg(feature = "async")]
+pub mod async_cbz;
+pub mod cancel;
 pub mod cbz;
 pub mod cbz_metadata;
+pub mod comic_info;
+pub mod diff;
+pub mod edit;
 pub mod errors;
 pub mod image;
+#[cfg(feature = "jxl")]
+mod jxl;
+pub mod manifest;
+pub mod markup;
+pub mod measure;
+pub mod migrate;
+pub mod normalize;
+pub mod progress;
+pub mod quality;
+pub mod repair;
+pub mod report;
+pub mod sort;
+pub mod text;
 
-pub use crate::cbz::{Reader as CbzReader, Writer as CbzWriter};
+#[cfg(feature = "async")]
+pub use crate::async_cbz::{AsyncReader as AsyncCbzReader, AsyncWriter as AsyncCbzWriter};
+pub use crate::cancel::CancellationToken;
+pub use crate::cbz::{
+    Compression, Entry as CbzEntry, Reader as CbzReader, Writer as CbzWriter, SIDECAR_EXTENSIONS,
+};
 #[cfg(feature = "metadata")]
 pub use crate::cbz_metadata::{
     ComicBookInfoV1, Credit as CbzCredit, Month, Primary as CbzPrimary,
     UnofficialMetadata as UnofficialCbzMetadata,
 };
+#[cfg(feature = "metadata")]
+pub use crate::comic_info::{Bookmark, ComicInfo, ComicPageInfo, ComicPages};
 pub use crate::errors::{Error, Result};
-pub use crate::image::{Image, ReadingOrder};
+pub use crate::image::{Image, PageFormat, ReadingOrder, Resampling};
+#[cfg(feature = "manifest")]
+pub use crate::manifest::{Manifest, ManifestPage, ManifestReport};
+pub use crate::measure::{Measure, Stage, Timings};
+pub use crate::progress::{Event, NoProgress, Progress};
+pub use crate::report::{Failure, Report, SkippedPage};
+#[cfg(feature = "text")]
+pub use crate::text::{TitleBackground, TitlePage};
@@ -0,0 +1,110 @@
+#![cfg(feature = "manifest")]
+
+use std::{
+    fmt::Write,
+    io::{self, Cursor, Read},
+};
+
+use image::io::Reader as ImageReader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::errors::Result;
+
+/// Name of the entry holding the manifest
+pub static MANIFEST_FILE_NAME: &str = "eco-manifest.json";
+
+/// Checksums, dimensions, and formats of the pages, used to detect corruptions beyond the zip checksums
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub pages: Vec<ManifestPage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestPage {
+    pub name: String,
+    /// Hex encoded SHA-256 of the page content
+    pub sha256: String,
+    pub width: u32,
+    pub height: u32,
+    /// Extension of the page image format, absent from the manifests written by the older versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl ManifestPage {
+    /// ## Errors
+    ///
+    /// Fails if the image format can't be guessed or its dimensions can't be read
+    pub fn try_from_bytes(name: impl Into<String>, bytes: &[u8]) -> Result<Self> {
+        #[cfg(feature = "jxl")]
+        if crate::jxl::is_jxl(bytes) {
+            let (width, height) = crate::jxl::dimensions(bytes)?;
+            return Ok(Self {
+                name: name.into(),
+                sha256: sha256(bytes),
+                width,
+                height,
+                format: Some("jxl".to_string()),
+            });
+        }
+        let reader = ImageReader::new(Cursor::new(bytes)).with_guessed_format()?;
+        let format = reader
+            .format()
+            .and_then(|format| format.extensions_str().first())
+            .map(ToString::to_string);
+        let (width, height) = reader.into_dimensions()?;
+
+        Ok(Self {
+            name: name.into(),
+            sha256: sha256(bytes),
+            width,
+            height,
+            format,
+        })
+    }
+}
+
+/// Outcome of `Reader::verify_manifest`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ManifestReport {
+    /// Amount of pages matching the manifest
+    pub verified: usize,
+    /// The pages which content doesn't match the manifest
+    pub mismatched: Vec<String>,
+    /// The pages listed in the manifest but missing from the archive
+    pub missing: Vec<String>,
+}
+
+impl ManifestReport {
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.mismatched.is_empty() && self.missing.is_empty()
+    }
+}
+
+#[must_use]
+pub fn sha256(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Same as [`sha256`], without loading the whole content in memory
+///
+/// ## Errors
+///
+/// Fails if the reader can't be read
+pub fn sha256_from_reader(mut reader: impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut reader, &mut hasher)?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+fn to_hex(digest: &[u8]) -> String {
+    digest
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
@@ -0,0 +1,26 @@
+/// Escapes the text so it can be safely inserted in xml or html content and attributes
+#[must_use]
+pub fn escape_markup(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Injects the elements at the end of the html head so they take precedence over the page style,
+/// they're prepended to the content without a head
+#[must_use]
+pub fn inject_in_head(content: &str, elements: &str) -> String {
+    match content.find("</head>") {
+        Some(index) => format!("{}{elements}{}", &content[..index], &content[index..]),
+        None => format!("{elements}{content}"),
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    fmt,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tracing::debug;
+
+use crate::progress::{Event, Progress};
+
+/// The steps the pages go through, timed by `Measure`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Stage {
+    /// Reading and decoding the source images
+    Decode,
+    /// The color, contrast, split... transformations
+    Transform,
+    /// Encoding the pages and inserting them in the archive
+    Encode,
+    /// Writing the archives to the disk
+    Write,
+    /// Loading a page for display, in the viewers
+    Load,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [
+        Self::Decode,
+        Self::Transform,
+        Self::Encode,
+        Self::Write,
+        Self::Load,
+    ];
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Decode => "decode",
+            Self::Transform => "transform",
+            Self::Encode => "encode",
+            Self::Write => "write",
+            Self::Load => "load",
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Times a stage until it's dropped, the duration is logged, and sent to the `progress` as an `Event::StageTimed`
+#[must_use]
+pub struct Measure<'a> {
+    stage: Stage,
+    start: Instant,
+    progress: Option<&'a dyn Progress>,
+}
+
+impl<'a> Measure<'a> {
+    /// The duration is only logged
+    pub fn new(stage: Stage) -> Self {
+        Self {
+            stage,
+            start: Instant::now(),
+            progress: None,
+        }
+    }
+
+    pub fn with_progress(stage: Stage, progress: &'a dyn Progress) -> Self {
+        Self {
+            stage,
+            start: Instant::now(),
+            progress: Some(progress),
+        }
+    }
+}
+
+impl Drop for Measure<'_> {
+    fn drop(&mut self) {
+        let duration = self.start.elapsed();
+        debug!("{}: {}ms", self.stage, duration.as_millis());
+        if let Some(progress) = self.progress {
+            progress.on_event(Event::StageTimed {
+                stage: self.stage,
+                duration,
+            });
+        }
+    }
+}
+
+/// The total time spent in each stage, collected from the `Event::StageTimed` events
+#[derive(Debug, Default)]
+pub struct Timings {
+    stages: Mutex<Vec<StageTiming>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StageTiming {
+    pub stage: Stage,
+    pub total: Duration,
+    /// How many times the stage was timed, e.g. once per page
+    pub count: usize,
+}
+
+impl Timings {
+    pub fn record(&self, stage: Stage, duration: Duration) {
+        let mut stages = self
+            .stages
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match stages.iter_mut().find(|timing| timing.stage == stage) {
+            Some(timing) => {
+                timing.total += duration;
+                timing.count += 1;
+            }
+            None => stages.push(StageTiming {
+                stage,
+                total: duration,
+                count: 1,
+            }),
+        }
+    }
+
+    /// The timed stages, in the order of `Stage::ALL`
+    #[must_use]
+    pub fn stages(&self) -> Vec<StageTiming> {
+        let stages = self
+            .stages
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        Stage::ALL
+            .iter()
+            .filter_map(|stage| stages.iter().find(|timing| timing.stage == *stage))
+            .copied()
+            .collect()
+    }
+}
+
+impl Progress for Timings {
+    fn on_event(&self, event: Event) {
+        if let Event::StageTimed { stage, duration } = event {
+            self.record(stage, duration);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+#![cfg(feature = "metadata")]
+
+use camino::Utf8Path;
+use chrono::Utc;
+
+use crate::cbz::Reader;
+use crate::cbz_metadata::UnofficialMetadata;
+use crate::comic_info::{Bookmark, ComicInfo, COMIC_INFO_FILE_NAME};
+use crate::edit::rewrite;
+use crate::{Error, Result};
+
+/// The metadata schemes supported by eco
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    /// Json stored in the zip comment
+    ComicBookInfo,
+    /// Xml stored in the `ComicInfo.xml` entry
+    ComicInfo,
+}
+
+impl MetadataFormat {
+    /// Guesses the format of a metadata file from its extension, `json` for `ComicBookInfo` and `xml` for `ComicInfo`
+    #[must_use]
+    pub fn from_path(path: impl AsRef<Utf8Path>) -> Option<Self> {
+        match path.as_ref().extension()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::ComicBookInfo),
+            "xml" => Some(Self::ComicInfo),
+            _ => None,
+        }
+    }
+}
+
+/// Converts the metadata of the archive to the provided format.
+/// The original metadata are kept, so the archive can be read by tools expecting either format.
+///
+/// ## Errors
+///
+/// Fails if the archive doesn't contain metadata in the other format, or if it can't be read or rewritten
+pub fn migrate_metadata(path: impl AsRef<Utf8Path>, to: MetadataFormat) -> Result<()> {
+    let path = path.as_ref();
+    let mut reader = Reader::try_from_path(path)?;
+    match to {
+        MetadataFormat::ComicInfo => {
+            let Some(info) = reader
+                .metadata::<UnofficialMetadata>()
+                .ok()
+                .and_then(|metadata| metadata.info)
+            else {
+                return Err(Error::MetadataValue(
+                    "no ComicBookInfo metadata found".to_string(),
+                ));
+            };
+            let xml = ComicInfo::from(info).try_to_xml()?;
+            let comment = reader.archive().comment().to_vec();
+            drop(reader);
+
+            rewrite(
+                path,
+                &comment,
+                |name| !name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME),
+                &[(COMIC_INFO_FILE_NAME, xml.as_bytes())],
+            )
+        }
+        MetadataFormat::ComicBookInfo => {
+            let Some(comic_info) = reader.comic_info()? else {
+                return Err(Error::MetadataValue(format!(
+                    "no {COMIC_INFO_FILE_NAME} found"
+                )));
+            };
+            let metadata = reader
+                .metadata::<UnofficialMetadata>()
+                .unwrap_or_default()
+                .with_app_id("eco")
+                .with_last_modified(Utc::now())
+                .with_info(comic_info.into());
+            let comment = serde_json::to_string(&metadata)?;
+            if comment.len() > u16::MAX as usize {
+                return Err(Error::CbzMetadataSize(comment.len()));
+            }
+            drop(reader);
+
+            rewrite(path, comment.as_bytes(), |_| true, &[])
+        }
+    }
+}
+
+/// The metadata of the archive in the provided format, as they'd be written in a standalone file:
+/// the `ComicBookInfo` json is pretty printed, and the `ComicInfo.xml` entry is returned as is.
+///
+/// ## Errors
+///
+/// Fails if the archive doesn't contain metadata in this format, or if it can't be read
+pub fn export_metadata(path: impl AsRef<Utf8Path>, format: MetadataFormat) -> Result<String> {
+    let mut reader = Reader::try_from_path(path.as_ref())?;
+    match format {
+        MetadataFormat::ComicBookInfo => {
+            if reader.archive().comment().is_empty() {
+                return Err(Error::MetadataValue(
+                    "no ComicBookInfo metadata found".to_string(),
+                ));
+            }
+            let metadata = reader.metadata::<UnofficialMetadata>()?;
+            Ok(serde_json::to_string_pretty(&metadata)?)
+        }
+        MetadataFormat::ComicInfo => {
+            let Some(name) = reader
+                .archive()
+                .file_names()
+                .find(|name| name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME))
+                .map(ToString::to_string)
+            else {
+                return Err(Error::MetadataValue(format!(
+                    "no {COMIC_INFO_FILE_NAME} found"
+                )));
+            };
+            Ok(String::from_utf8_lossy(&reader.read_bytes_by_name(&name)?).into_owned())
+        }
+    }
+}
+
+/// Replaces the metadata of the archive in the provided format with the content of a standalone file,
+/// e.g. one written by `export_metadata` then edited by a script.
+/// The content is validated first, the metadata in the other format and the pages are copied as is.
+///
+/// ## Errors
+///
+/// Fails if the content is not valid metadata in this format, or if the archive can't be read or rewritten
+pub fn import_metadata(
+    path: impl AsRef<Utf8Path>,
+    content: &str,
+    format: MetadataFormat,
+) -> Result<()> {
+    let path = path.as_ref();
+    let reader = Reader::try_from_path(path)?;
+    match format {
+        MetadataFormat::ComicBookInfo => {
+            let metadata =
+                serde_json::from_str::<UnofficialMetadata>(content)?.with_last_modified(Utc::now());
+            let comment = serde_json::to_string(&metadata)?;
+            if comment.len() > u16::MAX as usize {
+                return Err(Error::CbzMetadataSize(comment.len()));
+            }
+            drop(reader);
+
+            rewrite(path, comment.as_bytes(), |_| true, &[])
+        }
+        MetadataFormat::ComicInfo => {
+            // Only validated, the file is stored as written
+            ComicInfo::try_from_xml(content)?;
+            let comment = reader.archive().comment().to_vec();
+            drop(reader);
+
+            rewrite(
+                path,
+                &comment,
+                |name| !name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME),
+                &[(COMIC_INFO_FILE_NAME, content.as_bytes())],
+            )
+        }
+    }
+}
+
+/// Replaces the bookmarks of the archive, the `ComicInfo.xml` entry is created when missing.
+/// The other metadata and entries are copied as is.
+///
+/// ## Errors
+///
+/// Fails if a bookmarked page doesn't exist, or if the archive can't be read or rewritten
+pub fn set_bookmarks(path: impl AsRef<Utf8Path>, bookmarks: Vec<Bookmark>) -> Result<()> {
+    let path = path.as_ref();
+    let mut reader = Reader::try_from_path(path)?;
+    if let Some(bookmark) = bookmarks
+        .iter()
+        .find(|bookmark| bookmark.page >= reader.len())
+    {
+        return Err(Error::CbzNotFound(bookmark.page));
+    }
+    let mut comic_info = reader.comic_info()?.unwrap_or_default();
+    comic_info.set_bookmarks(bookmarks);
+    let xml = comic_info.try_to_xml()?;
+    let comment = reader.archive().comment().to_vec();
+    drop(reader);
+
+    rewrite(
+        path,
+        &comment,
+        |name| !name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME),
+        &[(COMIC_INFO_FILE_NAME, xml.as_bytes())],
+    )
+}
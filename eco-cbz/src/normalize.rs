@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use camino::Utf8Path;
+use tracing::{debug, warn};
+
+use crate::cbz::{is_sidecar_extension, is_visible_file, Reader, Writer};
+use crate::errors::Result;
+use crate::sort::cmp_paths;
+
+/// Outcome of `normalize_file`
+#[derive(Debug, Default)]
+pub struct NormalizeReport {
+    /// Amount of pages written to the normalized archive
+    pub pages: usize,
+    /// The files that aren't images nor metadata files (e.g. `Thumbs.db`, store links), left out
+    pub dropped: Vec<String>,
+}
+
+/// Rewrites any zip of images (e.g. the folder-in-zip layout of some DRM-free stores) into a proper Cbz:
+/// the pages are ordered naturally, moved out of their directories, and named after their actual format.
+/// The metadata files are kept at the root, the other files are dropped.
+/// The pages are copied as is, without being re-encoded.
+///
+/// ## Errors
+///
+/// Fails if the zip can't be read, or if the normalized archive can't be written
+pub fn normalize_file(
+    path: impl AsRef<Utf8Path>,
+    output: impl AsRef<Utf8Path>,
+) -> Result<NormalizeReport> {
+    let mut reader = Reader::try_from_path(path.as_ref())?;
+    let mut report = NormalizeReport::default();
+    let mut writer = Writer::default();
+    let comment = reader.archive().comment().to_vec();
+    if !comment.is_empty() {
+        writer
+            .raw_archive_mut()
+            .set_comment(String::from_utf8_lossy(&comment));
+    }
+
+    // All the visible files are sniffed, even the extensionless or misnamed ones that aren't listed as pages
+    let (mut sidecars, mut files): (Vec<String>, Vec<String>) = reader
+        .archive()
+        .file_names()
+        .filter(|name| is_visible_file(name))
+        .map(ToString::to_string)
+        .partition(|name| {
+            Utf8Path::new(name)
+                .extension()
+                .is_some_and(is_sidecar_extension)
+        });
+    files.sort_by(|name, other| cmp_paths(name, other));
+    for name in files {
+        let bytes = reader.read_bytes_by_name(&name)?;
+        let Some(extension) = image_extension(&bytes) else {
+            warn!("{name} is not an image, dropping it");
+            report.dropped.push(name);
+            continue;
+        };
+        debug!("{name} normalized as a {extension} page");
+        writer.insert_raw_with_extension(&bytes, extension)?;
+        report.pages += 1;
+    }
+    // Once moved to the root, only the least nested of the metadata files with the same name is kept
+    sidecars.sort_by_key(|name| name.matches('/').count());
+    let mut sidecar_names = HashSet::new();
+    for name in sidecars {
+        let file_name = Utf8Path::new(&name).file_name().unwrap_or(name.as_str());
+        if !sidecar_names.insert(file_name.to_lowercase()) {
+            warn!("{name} is shadowed by another {file_name}, dropping it");
+            report.dropped.push(name);
+            continue;
+        }
+        let bytes = reader.read_bytes_by_name(&name)?;
+        writer.insert_sidecar(file_name, &bytes)?;
+    }
+
+    writer.write_to_path(output)?;
+
+    Ok(report)
+}
+
+/// The extension of the image format guessed from the first bytes, `None` if it's not an image
+fn image_extension(bytes: &[u8]) -> Option<&'static str> {
+    #[cfg(feature = "jxl")]
+    if crate::jxl::is_jxl(bytes) {
+        return Some("jxl");
+    }
+
+    image::guess_format(bytes)
+        .ok()?
+        .extensions_str()
+        .first()
+        .copied()
+}
@@ -0,0 +1,40 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::measure::Stage;
+
+/// What the long running operations (pack, convert, merge) report while they're running
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// A source file is being read
+    FileStarted(PathBuf),
+    /// A page was written, starting at 1, `total` is `None` when it's not known in advance
+    PageProcessed { page: usize, total: Option<usize> },
+    /// Something was skipped or fixed, the operation goes on
+    Warning(String),
+    /// Time spent in a stage, for a page or a whole file, see `Measure`
+    StageTimed { stage: Stage, duration: Duration },
+}
+
+/// Receives the events of an operation, so the embedders (GUI, server) can surface the progress.
+/// The events can be sent from several threads.
+pub trait Progress: Sync {
+    fn on_event(&self, event: Event);
+}
+
+/// Ignores all the events
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn on_event(&self, _event: Event) {}
+}
+
+impl<F> Progress for F
+where
+    F: Fn(Event) + Sync,
+{
+    fn on_event(&self, event: Event) {
+        self(event);
+    }
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "quality")]
+
+use std::io::{Read, Seek};
+
+use camino::Utf8Path;
+use image::GrayImage;
+use serde::Serialize;
+
+use crate::{cbz::Reader, errors::Result};
+
+/// The pixels whose luminance gradient is at least this strong, out of 510, are part of an edge
+const EDGE_GRADIENT: f64 = 32.0;
+
+/// Below this part of edge pixels, the page is too flat for its sharpness to mean anything
+const MIN_EDGE_DENSITY: f64 = 0.001;
+
+/// Thresholds of the audit, relative to the median of the archive pages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QualityOptions {
+    /// Pages with less pixels than this ratio of the median are flagged as low resolution
+    pub min_resolution_ratio: f64,
+    /// Pages with a sharpness below this ratio of the median are flagged as blurry,
+    /// which is how the upscaled pages usually look like
+    pub min_sharpness_ratio: f64,
+}
+
+impl Default for QualityOptions {
+    fn default() -> Self {
+        Self {
+            min_resolution_ratio: 0.5,
+            min_sharpness_ratio: 0.4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QualityIssue {
+    LowResolution,
+    Blurry,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageQuality {
+    /// The page number, starting at 1
+    pub page: usize,
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /// How thin the edges of the page are, the higher the sharper,
+    /// `None` when the page has too few edges to tell (e.g. a blank page)
+    pub sharpness: Option<f64>,
+    pub issues: Vec<QualityIssue>,
+}
+
+/// Outcome of `audit`
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct QualityReport {
+    pub pages: usize,
+    pub median_pixels: u64,
+    pub median_sharpness: f64,
+    /// The pages with at least one issue
+    pub flagged: Vec<PageQuality>,
+}
+
+/// ## Errors
+///
+/// Fails if the archive can't be opened, or if a page can't be read
+pub fn audit_file(path: impl AsRef<Utf8Path>, options: &QualityOptions) -> Result<QualityReport> {
+    let mut reader = Reader::try_from_path(path.as_ref())?;
+
+    audit(&mut reader, options)
+}
+
+/// Flags the pages whose resolution is far below the rest of the archive, or that look upscaled.
+/// All the pages are decoded.
+///
+/// ## Errors
+///
+/// Fails if a page can't be read
+pub fn audit<R>(reader: &mut Reader<R>, options: &QualityOptions) -> Result<QualityReport>
+where
+    R: Read + Seek,
+{
+    let names = reader.file_names();
+    let mut pages = Vec::with_capacity(names.len());
+    for (index, name) in names.into_iter().enumerate() {
+        let image = reader.read_by_index(index)?;
+        let luma = image.dynamic().to_luma8();
+        pages.push(PageQuality {
+            page: index + 1,
+            name,
+            width: luma.width(),
+            height: luma.height(),
+            sharpness: sharpness(&luma),
+            issues: Vec::new(),
+        });
+    }
+
+    let median_pixels = median(
+        pages
+            .iter()
+            .map(|page| u64::from(page.width) * u64::from(page.height))
+            .collect(),
+        u64::cmp,
+    )
+    .unwrap_or_default();
+    let median_sharpness = median(
+        pages.iter().filter_map(|page| page.sharpness).collect(),
+        f64::total_cmp,
+    )
+    .unwrap_or_default();
+
+    let total = pages.len();
+    #[allow(clippy::cast_precision_loss)]
+    let flagged = pages
+        .into_iter()
+        .filter_map(|mut page| {
+            let pixels = u64::from(page.width) * u64::from(page.height);
+            if (pixels as f64) < median_pixels as f64 * options.min_resolution_ratio {
+                page.issues.push(QualityIssue::LowResolution);
+            }
+            if page
+                .sharpness
+                .is_some_and(|sharpness| sharpness < median_sharpness * options.min_sharpness_ratio)
+            {
+                page.issues.push(QualityIssue::Blurry);
+            }
+            (!page.issues.is_empty()).then_some(page)
+        })
+        .collect();
+
+    Ok(QualityReport {
+        pages: total,
+        median_pixels,
+        median_sharpness,
+        flagged,
+    })
+}
+
+/// Average laplacian over the average gradient of the edge pixels: the edges of the upscaled images are spread
+/// over several pixels, so their laplacian is weaker than the one of the native images for the same gradient.
+/// Only the edges are measured and the ratio doesn't depend on their contrast,
+/// so the flat pages and the text pages can be compared with the drawn ones.
+fn sharpness(luma: &GrayImage) -> Option<f64> {
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return None;
+    }
+    let pixel = |x: u32, y: u32| f64::from(luma.get_pixel(x, y)[0]);
+    let (mut laplacians, mut gradients, mut edge_pixels) = (0.0, 0.0, 0_u64);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let gradient = (pixel(x + 1, y) - pixel(x - 1, y)).abs()
+                + (pixel(x, y + 1) - pixel(x, y - 1)).abs();
+            if gradient < EDGE_GRADIENT {
+                continue;
+            }
+            let laplacian = 4.0 * pixel(x, y)
+                - pixel(x - 1, y)
+                - pixel(x + 1, y)
+                - pixel(x, y - 1)
+                - pixel(x, y + 1);
+            laplacians += laplacian.abs();
+            gradients += gradient;
+            edge_pixels += 1;
+        }
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let edge_density = edge_pixels as f64 / (f64::from(width - 2) * f64::from(height - 2));
+    if edge_density < MIN_EDGE_DENSITY {
+        return None;
+    }
+
+    Some(laplacians / gradients)
+}
+
+fn median<T: Copy>(mut values: Vec<T>, cmp: impl FnMut(&T, &T) -> std::cmp::Ordering) -> Option<T> {
+    values.sort_by(cmp);
+    values.get(values.len() / 2).copied()
+}
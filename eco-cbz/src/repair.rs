@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    io::{Cursor, Read},
+};
+
+use camino::Utf8Path;
+use tracing::{debug, warn};
+use zip::{read::read_zipfile_from_stream, write::FileOptions, ZipArchive};
+
+use crate::cbz::{is_sidecar_extension, Writer};
+use crate::errors::Result;
+use crate::image::Image;
+
+/// Signature starting each local file header
+static LOCAL_FILE_HEADER_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+
+/// Signature starting each central directory header
+static CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x01, 0x02];
+
+/// Signature of the data descriptors, it's optional
+static DATA_DESCRIPTOR_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x07, 0x08];
+
+/// Set in the flags of the entries whose checksum and sizes follow their data, as in the streamed archives
+const DATA_DESCRIPTOR_FLAG: u16 = 1 << 3;
+
+/// Id of the ZIP64 extra field, the data descriptor then stores the sizes on 8 bytes
+const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+
+/// An entry that could be read from a damaged archive
+#[derive(Debug)]
+pub struct SalvagedEntry {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// Result of a tolerant scan, see `scan`
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub entries: Vec<SalvagedEntry>,
+    /// The entries that have been found but couldn't be read
+    pub lost: Vec<String>,
+    /// The archive comment, which holds the metadata, if the central directory is readable
+    pub comment: Option<Vec<u8>>,
+}
+
+/// Reads all the readable entries from a possibly damaged archive.
+///
+/// When the central directory is readable, each entry is read independently, and the ones that are
+/// truncated or corrupted (i.e. their checksum doesn't match) are reported as lost.
+/// Otherwise the archive is scanned for local file headers, so the entries stored before
+/// the damaged part can still be salvaged.
+#[must_use]
+pub fn scan(bytes: &[u8]) -> ScanReport {
+    match ZipArchive::new(Cursor::new(bytes)) {
+        Ok(archive) => scan_with_central_directory(archive),
+        Err(err) => {
+            warn!("central directory can't be read ({err}), scanning local headers");
+            scan_local_headers(bytes)
+        }
+    }
+}
+
+fn scan_with_central_directory(mut archive: ZipArchive<Cursor<&[u8]>>) -> ScanReport {
+    let mut report = ScanReport {
+        comment: Some(archive.comment().to_vec()),
+        ..ScanReport::default()
+    };
+    for index in 0..archive.len() {
+        let mut file = match archive.by_index(index) {
+            Ok(file) => file,
+            Err(err) => {
+                warn!("entry {index} can't be read: {err}");
+                report.lost.push(format!("entry #{index}"));
+                continue;
+            }
+        };
+        if file.is_dir() {
+            continue;
+        }
+        let name = file.name().to_string();
+        let mut bytes = Vec::new();
+        // The checksum is verified once the whole entry has been read
+        match file.read_to_end(&mut bytes) {
+            Ok(_) => report.entries.push(SalvagedEntry { name, bytes }),
+            Err(err) => {
+                warn!("{name} is damaged: {err}");
+                report.lost.push(name);
+            }
+        }
+    }
+    report
+}
+
+fn scan_local_headers(bytes: &[u8]) -> ScanReport {
+    let mut report = ScanReport::default();
+    let mut offset = 0;
+    while let Some(position) = find_local_file_header(&bytes[offset..]) {
+        let start = offset + position;
+        // On failure, the scan resumes right after the signature
+        offset = start + LOCAL_FILE_HEADER_SIGNATURE.len();
+        let mut entry = &bytes[start..];
+        // zip can't read the entries with a data descriptor from a stream, their header is completed first
+        let completed;
+        if read_u16(entry, 6).is_some_and(|flags| flags & DATA_DESCRIPTOR_FLAG != 0) {
+            let Some(completed_entry) = complete_local_header(entry) else {
+                let name =
+                    local_file_name(entry).unwrap_or_else(|| format!("entry at offset {start}"));
+                warn!("{name} can't be read: its data descriptor is missing");
+                report.lost.push(name);
+                continue;
+            };
+            completed = completed_entry;
+            entry = &completed;
+        }
+        let mut cursor = Cursor::new(entry);
+        let mut file = match read_zipfile_from_stream(&mut cursor) {
+            Ok(Some(file)) => file,
+            Ok(None) => continue,
+            Err(err) => {
+                let name = local_file_name(&bytes[start..])
+                    .unwrap_or_else(|| format!("entry at offset {start}"));
+                warn!("{name} can't be read: {err}");
+                report.lost.push(name);
+                continue;
+            }
+        };
+        let name = file.name().to_string();
+        if file.is_dir() {
+            continue;
+        }
+        let mut entry_bytes = Vec::new();
+        match file.read_to_end(&mut entry_bytes) {
+            Ok(_) => {
+                drop(file);
+                #[allow(clippy::cast_possible_truncation)]
+                let consumed = cursor.position() as usize;
+                offset = start + consumed;
+                debug!("salvaged {name}");
+                report.entries.push(SalvagedEntry {
+                    name,
+                    bytes: entry_bytes,
+                });
+            }
+            Err(err) => {
+                warn!("{name} is damaged: {err}");
+                report.lost.push(name);
+            }
+        }
+    }
+    report
+}
+
+fn find_local_file_header(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(LOCAL_FILE_HEADER_SIGNATURE.len())
+        .position(|window| window == LOCAL_FILE_HEADER_SIGNATURE)
+}
+
+/// Reads the file name from a local file header, even if the rest of the entry is unreadable
+fn local_file_name(header: &[u8]) -> Option<String> {
+    let name_length = usize::from(read_u16(header, 26)?);
+    let name = header.get(30..30 + name_length)?;
+    Some(String::from_utf8_lossy(name).into_owned())
+}
+
+/// Copies the entry up to the end of its data, with the checksum and sizes of its data descriptor
+/// moved into its local header
+fn complete_local_header(entry: &[u8]) -> Option<Vec<u8>> {
+    let name_end = 30 + usize::from(read_u16(entry, 26)?);
+    let data_start = name_end + usize::from(read_u16(entry, 28)?);
+    let zip64 = has_zip64_extra_field(entry.get(name_end..data_start)?);
+    let descriptor = find_data_descriptor(entry, data_start, zip64)?;
+    let mut completed = entry.get(..descriptor.data_end)?.to_vec();
+    let flags = read_u16(entry, 6)? & !DATA_DESCRIPTOR_FLAG;
+    completed[6..8].copy_from_slice(&flags.to_le_bytes());
+    completed[14..18].copy_from_slice(&descriptor.crc32.to_le_bytes());
+    completed[18..22].copy_from_slice(&descriptor.compressed_size.to_le_bytes());
+    completed[22..26].copy_from_slice(&descriptor.uncompressed_size.to_le_bytes());
+    Some(completed)
+}
+
+#[derive(Debug)]
+struct DataDescriptor {
+    /// Offset of the descriptor in the entry, where the compressed data ends
+    data_end: usize,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+}
+
+/// The compressed size isn't known upfront, the descriptor is the first one whose compressed size
+/// matches its distance to the start of the data. Without its optional signature, it directly precedes the next header
+fn find_data_descriptor(entry: &[u8], data_start: usize, zip64: bool) -> Option<DataDescriptor> {
+    let read_size = |offset: usize| {
+        if zip64 {
+            read_u64(entry, offset).and_then(|size| u32::try_from(size).ok())
+        } else {
+            read_u32(entry, offset)
+        }
+    };
+    let sizes_length = if zip64 { 16 } else { 8 };
+    (data_start..entry.len()).find_map(|position| {
+        let signature = entry.get(position..position + 4)?;
+        let (data_end, crc32_offset) = if signature == DATA_DESCRIPTOR_SIGNATURE {
+            (position, position + 4)
+        } else if signature == LOCAL_FILE_HEADER_SIGNATURE
+            || signature == CENTRAL_DIRECTORY_SIGNATURE
+        {
+            let data_end = position.checked_sub(4 + sizes_length)?;
+            (data_end, data_end)
+        } else {
+            return None;
+        };
+        let compressed_size = u32::try_from(data_end.checked_sub(data_start)?).ok()?;
+        if read_size(crc32_offset + 4)? != compressed_size {
+            return None;
+        }
+        Some(DataDescriptor {
+            data_end,
+            crc32: read_u32(entry, crc32_offset)?,
+            compressed_size,
+            uncompressed_size: read_size(crc32_offset + 4 + sizes_length / 2)?,
+        })
+    })
+}
+
+fn has_zip64_extra_field(mut extra_field: &[u8]) -> bool {
+    while let (Some(id), Some(length)) = (read_u16(extra_field, 0), read_u16(extra_field, 2)) {
+        if id == ZIP64_EXTRA_FIELD_ID {
+            return true;
+        }
+        let Some(rest) = extra_field.get(4 + usize::from(length)..) else {
+            return false;
+        };
+        extra_field = rest;
+    }
+    false
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        bytes.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Outcome of `repair_file`
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// Amount of pages written to the repaired archive
+    pub recovered: usize,
+    /// The pages that couldn't be recovered
+    pub lost: Vec<String>,
+}
+
+/// Salvages the readable pages and metadata files from a damaged archive, and writes them into a fresh archive.
+/// The pages are checked to be valid images but are copied as is, without being re-encoded.
+///
+/// ## Errors
+///
+/// Fails if the damaged archive can't be read from the disk, or if the repaired archive can't be written
+pub fn repair_file(
+    path: impl AsRef<Utf8Path>,
+    output: impl AsRef<Utf8Path>,
+) -> Result<RepairReport> {
+    let bytes = fs::read(path.as_ref())?;
+    let scan_report = scan(&bytes);
+    let mut report = RepairReport {
+        lost: scan_report.lost,
+        ..RepairReport::default()
+    };
+    let mut writer = Writer::default();
+    if let Some(comment) = scan_report.comment.filter(|comment| !comment.is_empty()) {
+        writer
+            .raw_archive_mut()
+            .set_comment(String::from_utf8_lossy(&comment));
+    }
+
+    for entry in scan_report.entries {
+        let is_sidecar = Utf8Path::new(&entry.name)
+            .extension()
+            .is_some_and(is_sidecar_extension);
+        if is_sidecar {
+            writer.insert_sidecar(&entry.name, &entry.bytes)?;
+            continue;
+        }
+        if let Err(err) = Image::try_from_bytes(&entry.bytes) {
+            warn!("{} is not a valid image: {err}", entry.name);
+            report.lost.push(entry.name);
+            continue;
+        }
+        writer.insert_raw_with_name_and_file_options(
+            &entry.bytes,
+            &entry.name,
+            FileOptions::default(),
+        )?;
+        report.recovered += 1;
+    }
+
+    writer.write_to_path(output)?;
+
+    Ok(report)
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+use camino::Utf8PathBuf;
+
+/// A source page left out of the output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedPage {
+    /// The page in the source, starting at 1
+    pub page: usize,
+    pub reason: String,
+}
+
+/// An input that couldn't be processed, the operation went on without it in keep going mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    /// The input file, or the page of the input for the operations reading a single file
+    pub input: String,
+    pub error: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} failed: {}", self.input, self.error)
+    }
+}
+
+/// What a pack, convert, or merge operation produced, so the embedders don't have to open the output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The written archive or directory, a split merge writes several archives
+    pub outputs: Vec<Utf8PathBuf>,
+
+    /// Amount of pages read from the source
+    pub extracted_pages: usize,
+
+    /// Amount of pages written, more than the extracted pages when the landscape ones are split
+    pub written_pages: usize,
+
+    pub skipped_pages: Vec<SkippedPage>,
+
+    /// The inputs left out in keep going mode
+    pub failures: Vec<Failure>,
+
+    /// Size of the output in bytes, the size of all the files for a directory or a split merge
+    pub size: u64,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} pages written out of {} extracted, {} skipped, {} bytes",
+            self.outputs
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.written_pages,
+            self.extracted_pages,
+            self.skipped_pages.len(),
+            self.size
+        )?;
+        for skipped_page in &self.skipped_pages {
+            writeln!(
+                f,
+                "  page {} skipped: {}",
+                skipped_page.page, skipped_page.reason
+            )?;
+        }
+        for failure in &self.failures {
+            writeln!(f, "  {failure}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use std::cmp::Ordering;
+
+/// Compares two entry paths component by component, using a natural order for each component,
+/// so `ch2/010.jpg` comes before `ch10/001.jpg` and all the pages of a directory stay together.
+#[must_use]
+pub fn cmp_paths(path: &str, other: &str) -> Ordering {
+    let mut components = path.split('/');
+    let mut other_components = other.split('/');
+    loop {
+        let ordering = match (components.next(), other_components.next()) {
+            (Some(component), Some(other_component)) => natural_cmp(component, other_component),
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Compares the strings with the numbers they contain compared by value, i.e. `vol2` < `vol10`.
+/// The other characters are compared case insensitively first.
+#[must_use]
+pub fn natural_cmp(s: &str, other: &str) -> Ordering {
+    let mut chunks = Chunks(s);
+    let mut other_chunks = Chunks(other);
+    loop {
+        let ordering = match (chunks.next(), other_chunks.next()) {
+            (Some(Chunk::Number(number)), Some(Chunk::Number(other_number))) => {
+                cmp_numbers(number, other_number)
+            }
+            (Some(Chunk::Text(text)), Some(Chunk::Text(other_text))) => text
+                .to_lowercase()
+                .cmp(&other_text.to_lowercase())
+                .then_with(|| text.cmp(other_text)),
+            // Numbers come before text, like in the lexicographic order
+            (Some(Chunk::Number(_)), Some(Chunk::Text(_))) => Ordering::Less,
+            (Some(Chunk::Text(_)), Some(Chunk::Number(_))) => Ordering::Greater,
+            (None, None) => return s.cmp(other),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Numbers of any length are compared without being parsed
+fn cmp_numbers(number: &str, other: &str) -> Ordering {
+    let number = number.trim_start_matches('0');
+    let other = other.trim_start_matches('0');
+    number
+        .len()
+        .cmp(&other.len())
+        .then_with(|| number.cmp(other))
+}
+
+enum Chunk<'a> {
+    Number(&'a str),
+    Text(&'a str),
+}
+
+/// Splits a string into its numeric and non numeric parts
+struct Chunks<'a>(&'a str);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Chunk<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = self.0.chars().next()?;
+        let is_digit = first.is_ascii_digit();
+        let end = self
+            .0
+            .find(|c: char| c.is_ascii_digit() != is_digit)
+            .unwrap_or(self.0.len());
+        let (chunk, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Some(if is_digit {
+            Chunk::Number(chunk)
+        } else {
+            Chunk::Text(chunk)
+        })
+    }
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "text")]
+
+use std::{str::FromStr, sync::OnceLock};
+
+use camino::Utf8PathBuf;
+use image::{imageops::FilterType, DynamicImage, ImageFormat, Rgb, RgbImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rusttype::{Font, Scale};
+
+use crate::{
+    errors::{Error, Result},
+    image::Image,
+};
+
+/// `DejaVu` Sans Mono, see `assets/DejaVuSansMono-LICENSE.txt`
+static FONT_BYTES: &[u8] = include_bytes!("../assets/DejaVuSansMono.ttf");
+
+/// The height of the series line, as a fraction of the page height
+const SERIES_HEIGHT_RATIO: f32 = 0.06;
+
+/// The height of the volume and chapter lines, as a fraction of the page height
+const SUBTITLE_HEIGHT_RATIO: f32 = 0.04;
+
+/// The bundled font, parsed once, used by the title pages and the watermarks
+///
+/// ## Panics
+///
+/// Never, the bundled font is valid
+pub fn font() -> &'static Font<'static> {
+    static FONT: OnceLock<Font<'static>> = OnceLock::new();
+    FONT.get_or_init(|| Font::try_from_bytes(FONT_BYTES).expect("the bundled font is valid"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TitleBackground {
+    Color(Rgb<u8>),
+    /// The image is resized to fill the page, and darkened so the text stays readable
+    Image(Utf8PathBuf),
+}
+
+impl Default for TitleBackground {
+    fn default() -> Self {
+        Self::Color(Rgb([255, 255, 255]))
+    }
+}
+
+impl FromStr for TitleBackground {
+    type Err = Error;
+
+    /// Parses colors like `#1f2937`, anything else is used as the path to an image
+    fn from_str(s: &str) -> Result<Self> {
+        let Some(hex) = s.strip_prefix('#') else {
+            return Ok(Self::Image(Utf8PathBuf::from(s)));
+        };
+        let invalid = || Error::InvalidColor(s.to_string());
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let channel = |index: usize| {
+            u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())
+        };
+
+        Ok(Self::Color(Rgb([channel(0)?, channel(1)?, channel(2)?])))
+    }
+}
+
+/// A generated page showing the series, the volume, and the chapter, centered over the background
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TitlePage {
+    pub series: Option<String>,
+    pub volume: Option<String>,
+    pub background: TitleBackground,
+}
+
+impl TitlePage {
+    /// The size of the title page of a chapter starting with `page`, the double pages give the size of a single page
+    #[must_use]
+    pub fn size_of(page: &Image) -> (u32, u32) {
+        let (width, height) = (page.dynamic().width(), page.dynamic().height());
+        if page.is_landscape() {
+            ((width / 2).max(1), height)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Renders the page, the `chapter` is specific to each chapter (e.g. the name of the merged archive)
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the background image can't be read
+    pub fn render(&self, chapter: Option<&str>, width: u32, height: u32) -> Result<Image> {
+        let font = font();
+        let (mut canvas, text_color) = match &self.background {
+            TitleBackground::Color(color) => (
+                RgbImage::from_pixel(width, height, *color),
+                text_color(*color),
+            ),
+            TitleBackground::Image(path) => {
+                let mut canvas = Image::open(path)?
+                    .dynamic()
+                    .resize_to_fill(width, height, FilterType::Lanczos3)
+                    .to_rgb8();
+                for pixel in canvas.pixels_mut() {
+                    for value in &mut pixel.0 {
+                        *value /= 2;
+                    }
+                }
+                (canvas, Rgb([255, 255, 255]))
+            }
+        };
+
+        #[allow(clippy::cast_precision_loss)]
+        let lines = [
+            (self.series.as_deref(), SERIES_HEIGHT_RATIO),
+            (self.volume.as_deref(), SUBTITLE_HEIGHT_RATIO),
+            (chapter, SUBTITLE_HEIGHT_RATIO),
+        ]
+        .into_iter()
+        .filter_map(|(text, ratio)| {
+            let text = text.map(str::trim).filter(|text| !text.is_empty())?;
+            let scale = Scale::uniform((height as f32 * ratio).max(8.0));
+            let (text_width, text_height) = text_size(scale, font, text);
+            Some((text, scale, text_width, text_height))
+        })
+        .collect::<Vec<_>>();
+
+        // The lines are separated by half their height, and the block is centered vertically
+        let block_height = lines
+            .iter()
+            .map(|(_, _, _, text_height)| text_height + text_height / 2)
+            .sum::<i32>();
+        let page_width = i32::try_from(width).unwrap_or(i32::MAX);
+        let page_height = i32::try_from(height).unwrap_or(i32::MAX);
+        let mut y = (page_height - block_height).max(0) / 2;
+        for (text, scale, text_width, text_height) in lines {
+            let x = (page_width - text_width).max(0) / 2;
+            draw_text_mut(&mut canvas, text_color, x, y, scale, font, text);
+            y += text_height + text_height / 2;
+        }
+
+        let mut image = Image::from(DynamicImage::ImageRgb8(canvas));
+        image.set_format(ImageFormat::Png);
+
+        Ok(image)
+    }
+}
+
+/// Black on the light backgrounds, white on the dark ones
+fn text_color(background: Rgb<u8>) -> Rgb<u8> {
+    let [red, green, blue] = background.0;
+    let luminance = 0.299 * f32::from(red) + 0.587 * f32::from(green) + 0.114 * f32::from(blue);
+    if luminance > 128.0 {
+        Rgb([0, 0, 0])
+    } else {
+        Rgb([255, 255, 255])
+    }
+}
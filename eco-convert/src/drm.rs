@@ -0,0 +1,42 @@
+use std::{fs, path::Path};
+
+use tracing::debug;
+
+use crate::{Error, Result};
+
+/// Offset of the first record's offset in the Palm database header
+const FIRST_RECORD_OFFSET: usize = 78;
+
+/// Offset of the encryption type in the `PalmDOC` header, which starts the first record
+const ENCRYPTION_OFFSET: usize = 12;
+
+/// Mobi and azw3 files are encrypted when the encryption type of their `PalmDOC` header is not 0
+///
+/// ## Errors
+///
+/// Fails with `Error::DrmProtected` if the file is encrypted
+pub fn ensure_mobi_drm_free(path: &Path, bytes: &[u8]) -> Result<()> {
+    let encryption = bytes
+        .get(FIRST_RECORD_OFFSET..FIRST_RECORD_OFFSET + 4)
+        .and_then(|offset| usize::try_from(u32::from_be_bytes(offset.try_into().ok()?)).ok())
+        .and_then(|offset| bytes.get(offset + ENCRYPTION_OFFSET..offset + ENCRYPTION_OFFSET + 2))
+        .map_or(0, |encryption| {
+            u16::from_be_bytes([encryption[0], encryption[1]])
+        });
+    debug!("mobi encryption type {encryption}");
+    if encryption != 0 {
+        return Err(Error::DrmProtected(path.display().to_string()));
+    }
+
+    Ok(())
+}
+
+/// Pdf files protected with a user password or a DRM handler can't be opened, but they declare an `/Encrypt` dictionary.
+/// The pdfs only restricted by their permissions are opened as usual.
+pub fn is_pdf_encrypted(path: impl AsRef<Path>) -> bool {
+    let Ok(bytes) = fs::read(path) else {
+        return false;
+    };
+
+    bytes.windows(8).any(|window| window == b"/Encrypt")
+}
@@ -0,0 +1,322 @@
+use std::{
+    collections::HashSet,
+    io::{Read, Seek},
+    path::Path,
+};
+
+use eco_cbz::image::Image;
+use epub::doc::EpubDoc;
+use tl::ParserOptions;
+use tracing::{debug, warn};
+
+use crate::{utils::decoded, Result};
+
+/// Extracts the images in reading order: the spine is followed, and the images of each page are kept in document order.
+/// An image used several times (e.g. the cover) is only extracted once.
+///
+/// ## Errors
+///
+/// Fails if the epub can't be opened
+pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
+    Ok(decoded(read(path)?))
+}
+
+/// Same as `convert_to_imgs`, the images that can't be decoded are returned as errors, in their place
+///
+/// ## Errors
+///
+/// Fails if the epub can't be opened
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<eco_cbz::Result<Image>>> {
+    imgs_in_spine_order(&mut EpubDoc::new(path)?)
+}
+
+fn imgs_in_spine_order<R: Read + Seek>(
+    doc: &mut EpubDoc<R>,
+) -> Result<Vec<eco_cbz::Result<Image>>> {
+    let mut seen = HashSet::new();
+    let mut imgs = Vec::new();
+
+    for page in 0..doc.get_num_pages() {
+        doc.set_current_page(page);
+        let Some(page_path) = doc.get_current_path() else {
+            continue;
+        };
+        // Epub paths are always separated by slashes, whatever the platform
+        let page_path = page_path.to_string_lossy().replace('\\', "/");
+        let mime = doc
+            .get_resource_mime_by_path(&page_path)
+            .unwrap_or_default();
+
+        // Some fixed layout epubs reference the images directly in their spine
+        let image_paths = if mime.starts_with("image/") {
+            vec![page_path]
+        } else {
+            let Some(bytes) = doc.get_resource_by_path(&page_path) else {
+                warn!("page {page_path} not found");
+                continue;
+            };
+            image_paths(&page_path, &String::from_utf8_lossy(&bytes))?
+        };
+
+        for image_path in image_paths {
+            if !seen.insert(image_path.clone()) {
+                debug!("skipping already extracted {image_path}");
+                continue;
+            }
+            let Some(bytes) = doc.get_resource_by_path(&image_path) else {
+                warn!("image {image_path} not found");
+                continue;
+            };
+            imgs.push(Image::try_from_bytes(&bytes));
+        }
+    }
+
+    Ok(imgs)
+}
+
+/// Lists the images of a xhtml page in document order, including the svg ones and the css backgrounds.
+/// Fixed layout pages are often made of a background and panels overlays, the backgrounds come first as they're drawn behind.
+fn image_paths(page_path: &str, html: &str) -> Result<Vec<String>> {
+    let dom = tl::parse(html, ParserOptions::default())?;
+    let mut paths = Vec::new();
+    for node in dom.nodes() {
+        let Some(tag) = node.as_tag() else {
+            continue;
+        };
+        let name = tag.name().as_utf8_str().to_lowercase();
+        // Only the page's own styles are considered, the shared stylesheets may reference the backgrounds of all the pages
+        if name == "style" {
+            for url in css_urls(&tag.inner_text(dom.parser())) {
+                paths.push(resolve_path(page_path, url));
+            }
+            continue;
+        }
+        if let Some(Some(style)) = tag.attributes().get("style") {
+            for url in css_urls(&style.as_utf8_str()) {
+                paths.push(resolve_path(page_path, url));
+            }
+        }
+        let attribute = match name.as_str() {
+            "img" => "src",
+            "image" if tag.attributes().contains("xlink:href") => "xlink:href",
+            "image" => "href",
+            _ => continue,
+        };
+        let Some(Some(src)) = tag.attributes().get(attribute) else {
+            continue;
+        };
+        paths.push(resolve_path(page_path, &src.as_utf8_str()));
+    }
+
+    Ok(paths)
+}
+
+/// Lists the `url(...)` of a stylesheet, the inlined data are skipped
+fn css_urls(css: &str) -> Vec<&str> {
+    css.split("url(")
+        .skip(1)
+        .filter_map(|rest| rest.split_once(')'))
+        .map(|(url, _)| url.trim().trim_matches(['"', '\'']).trim())
+        .filter(|url| !url.is_empty() && !url.starts_with("data:"))
+        .collect()
+}
+
+/// Resolves a link relatively to the page, the fragment and the query are dropped, and the escaped characters decoded
+fn resolve_path(page_path: &str, link: &str) -> String {
+    let link = percent_decode(link.split(['#', '?']).next().unwrap_or_default());
+    let mut components = page_path.split('/').collect::<Vec<_>>();
+    // The page's file name
+    components.pop();
+    for component in link.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+
+    components.join("/")
+}
+
+/// Decodes the `%20` like escapes of a link, the invalid ones are kept as is
+fn percent_decode(link: &str) -> String {
+    let bytes = link.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| bytes.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use image::{DynamicImage, ImageFormat};
+    use zip::{write::FileOptions, ZipWriter};
+
+    use super::*;
+
+    /// A fixed layout epub, the manifest lists the pages in the opposite order of the spine
+    fn fixed_layout_epub(
+        pages: &[(&str, &str)],
+        spine: &[&str],
+        images: &[(&str, u32)],
+    ) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut write = |name: &str, bytes: &[u8]| {
+            zip.start_file(name, FileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        };
+        write("mimetype", b"application/epub+zip");
+        write(
+            "META-INF/container.xml",
+            br#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+        );
+        let mut manifest = String::new();
+        for (name, _) in pages.iter().rev() {
+            manifest.push_str(&format!(
+                r#"<item id="{name}" href="text/{name}.xhtml" media-type="application/xhtml+xml"/>"#
+            ));
+        }
+        for (index, (name, _)) in images.iter().enumerate().rev() {
+            let href = name.replace(' ', "%20");
+            manifest.push_str(&format!(
+                r#"<item id="image-{index}" href="images/{href}" media-type="image/png"/>"#
+            ));
+        }
+        let spine = spine
+            .iter()
+            .map(|id| format!(r#"<itemref idref="{id}"/>"#))
+            .collect::<String>();
+        write(
+            "OEBPS/content.opf",
+            format!(
+                r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">fixture</dc:identifier>
+    <dc:title>Fixture</dc:title>
+    <meta property="rendition:layout">pre-paginated</meta>
+  </metadata>
+  <manifest>{manifest}</manifest>
+  <spine>{spine}</spine>
+</package>"#
+            )
+            .as_bytes(),
+        );
+        for (name, body) in pages {
+            write(
+                &format!("OEBPS/text/{name}.xhtml"),
+                format!(
+                    r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:xlink="http://www.w3.org/1999/xlink"><body>{body}</body></html>"#
+                )
+                .as_bytes(),
+            );
+        }
+        for (name, width) in images {
+            let mut png = Cursor::new(Vec::new());
+            DynamicImage::new_rgb8(*width, 1)
+                .write_to(&mut png, ImageFormat::Png)
+                .unwrap();
+            write(&format!("OEBPS/images/{name}"), &png.into_inner());
+        }
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    /// The images are told apart by their width
+    fn widths(epub: Vec<u8>) -> Vec<u32> {
+        let mut doc = EpubDoc::from_reader(Cursor::new(epub)).unwrap();
+        imgs_in_spine_order(&mut doc)
+            .unwrap()
+            .into_iter()
+            .map(|img| img.unwrap().dynamic().width())
+            .collect()
+    }
+
+    #[test]
+    fn pages_follow_the_spine_not_the_manifest() {
+        let epub = fixed_layout_epub(
+            &[
+                ("p1", r#"<img src="../images/1.png"/>"#),
+                ("p2", r#"<img src="../images/2.png"/>"#),
+                ("p3", r#"<img src="../images/3.png"/>"#),
+            ],
+            &["p2", "p3", "p1"],
+            &[("1.png", 1), ("2.png", 2), ("3.png", 3)],
+        );
+
+        assert_eq!(widths(epub), vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn backgrounds_come_before_the_panels_and_svg_images_are_extracted() {
+        let epub = fixed_layout_epub(
+            &[(
+                "p1",
+                r#"<div style="background-image: url('../images/background.png')"><img src="../images/panel.png"/></div>
+<svg><image xlink:href="../images/svg.png"/></svg>"#,
+            )],
+            &["p1"],
+            &[("panel.png", 1), ("background.png", 2), ("svg.png", 3)],
+        );
+
+        assert_eq!(widths(epub), vec![2, 1, 3]);
+    }
+
+    #[test]
+    fn images_in_the_spine_and_reused_images() {
+        let epub = fixed_layout_epub(
+            &[
+                ("p1", r#"<img src="../images/cover.png"/>"#),
+                (
+                    "p2",
+                    r#"<img src="../images/cover.png"/><img src="../images/1.png"/>"#,
+                ),
+            ],
+            &["p1", "image-2", "p2"],
+            &[("cover.png", 1), ("1.png", 2), ("2.png", 3)],
+        );
+
+        assert_eq!(widths(epub), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn escaped_links_are_decoded() {
+        let epub = fixed_layout_epub(
+            &[("p1", r#"<img src="../images/page%201.png#frag"/>"#)],
+            &["p1"],
+            &[("page 1.png", 4)],
+        );
+
+        assert_eq!(widths(epub), vec![4]);
+        assert_eq!(
+            resolve_path("OEBPS/text/p1.xhtml", "../images/page%201.png?q=1"),
+            "OEBPS/images/page 1.png"
+        );
+        assert_eq!(percent_decode("100%.png"), "100%.png");
+    }
+}
@@ -1,3 +1,5 @@
+use camino::Utf8PathBuf;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("io error {0}")]
@@ -6,18 +8,51 @@ pub enum Error {
     #[error("cbz error {0}")]
     Cbz(#[from] eco_cbz::Error),
 
+    #[error("epub error {0}")]
+    Epub(#[from] epub::doc::DocError),
+
     #[error("mobi error {0}")]
     Mobi(#[from] mobi::MobiError),
 
     #[error("pdf error {0}")]
     Pdf(#[from] pdf::PdfError),
 
+    #[error("pdf rendering error {0}")]
+    Pdfium(#[from] pdfium_render::prelude::PdfiumError),
+
+    #[error("pdf page {0} is out of range")]
+    PdfPageIndex(usize),
+
     #[error("ts parse error {0}")]
     TlParse(#[from] tl::ParseError),
 
+    #[error("zip error {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("image error {0}")]
+    Image(#[from] image::ImageError),
+
     #[error("pack error {0}")]
     Pack(#[from] eco_pack::Error),
 
+    #[error("{0} is DRM protected, only DRM-free files can be converted (the DRM must be removed by its legitimate owner first)")]
+    DrmProtected(String),
+
+    #[error("epub rendering error {0}")]
+    Render(String),
+
+    #[error("invalid page size {0}, expected e.g. 1072x1448")]
+    InvalidPageSize(String),
+
+    #[error("the output {0} is the converted file")]
+    OutputIsInput(Utf8PathBuf),
+
+    #[error("cancelled")]
+    Cancelled,
+
+    #[error("no images found")]
+    NoImages,
+
     #[error("invalid mobi version {0}")]
     InvalidMobiVersion(u32),
 }
@@ -0,0 +1,194 @@
+use std::{
+    fmt::Write as _,
+    io::{Cursor, Read, Seek, Write},
+};
+
+use camino::Utf8Path;
+use eco_cbz::{image::ReadingOrder, markup::escape_markup, CbzReader};
+use image::io::Reader as ImageReader;
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::Result;
+
+/// Writes the pages of the Cbz as a fixed layout Kobo epub (kepub), one page per image.
+/// The images are wrapped in `koboSpan`s so that Kobo devices handle the page turns and reading statistics.
+/// The bookmarks of the `ComicInfo.xml` entry, if any, are used as table of contents.
+///
+/// ## Errors
+///
+/// Fails if the Cbz can't be read or if the kepub can't be written
+pub fn write_kepub<R, W>(
+    cbz: &mut CbzReader<R>,
+    title: &str,
+    reading_order: ReadingOrder,
+    writer: W,
+) -> Result<()>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+{
+    let mut archive = ZipWriter::new(writer);
+    let title = escape_markup(title);
+
+    // The mimetype must be the first entry, and it can't be compressed
+    archive.start_file(
+        "mimetype",
+        FileOptions::default().compression_method(CompressionMethod::Stored),
+    )?;
+    archive.write_all(b"application/epub+zip")?;
+    archive.start_file("META-INF/container.xml", FileOptions::default())?;
+    archive.write_all(CONTAINER_XML.as_bytes())?;
+
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    for (index, name) in cbz.file_names().iter().enumerate() {
+        let bytes = cbz.read_bytes_by_name(name)?;
+        let extension = Utf8Path::new(name)
+            .extension()
+            .unwrap_or("jpg")
+            .to_lowercase();
+        let image = format!("images/{index:04}.{extension}");
+        let (width, height) = ImageReader::new(Cursor::new(&bytes))
+            .with_guessed_format()?
+            .into_dimensions()?;
+
+        archive.start_file(format!("OEBPS/{image}"), FileOptions::default())?;
+        archive.write_all(&bytes)?;
+        archive.start_file(
+            format!("OEBPS/pages/{index:04}.xhtml"),
+            FileOptions::default(),
+        )?;
+        archive.write_all(page_xhtml(&title, &image, index, width, height).as_bytes())?;
+
+        let properties = if index == 0 {
+            " properties=\"cover-image\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            manifest,
+            r#"    <item id="image-{index}" href="{image}" media-type="{}"{properties}/>"#,
+            media_type(&extension)
+        );
+        let _ = writeln!(
+            manifest,
+            r#"    <item id="page-{index}" href="pages/{index:04}.xhtml" media-type="application/xhtml+xml"/>"#
+        );
+        let _ = writeln!(spine, r#"    <itemref idref="page-{index}"/>"#);
+    }
+
+    let bookmarks = cbz
+        .bookmarks()?
+        .into_iter()
+        .map(|bookmark| (bookmark.page, bookmark.title))
+        .collect::<Vec<_>>();
+    archive.start_file("OEBPS/nav.xhtml", FileOptions::default())?;
+    archive.write_all(nav_xhtml(&title, &bookmarks).as_bytes())?;
+
+    archive.start_file("OEBPS/content.opf", FileOptions::default())?;
+    archive.write_all(content_opf(&title, reading_order, &manifest, &spine).as_bytes())?;
+
+    archive.finish()?;
+
+    Ok(())
+}
+
+static CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+fn content_opf(title: &str, reading_order: ReadingOrder, manifest: &str, spine: &str) -> String {
+    let direction = match reading_order {
+        ReadingOrder::Rtl => "rtl",
+        ReadingOrder::Ltr => "ltr",
+    };
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" prefix="rendition: http://www.idpf.org/vocab/rendition/#">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:eco:{title}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>und</dc:language>
+    <meta property="dcterms:modified">{modified}</meta>
+    <meta property="rendition:layout">pre-paginated</meta>
+    <meta property="rendition:spread">landscape</meta>
+    <meta name="cover" content="image-0"/>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+{manifest}  </manifest>
+  <spine page-progression-direction="{direction}">
+{spine}  </spine>
+</package>
+"#
+    )
+}
+
+fn page_xhtml(title: &str, image: &str, index: usize, width: u32, height: u32) -> String {
+    let page = index + 1;
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <title>{title}</title>
+  <meta name="viewport" content="width={width}, height={height}"/>
+  <style>body {{ margin: 0; padding: 0; }} img {{ width: 100%; height: 100%; object-fit: contain; }}</style>
+</head>
+<body>
+  <div id="book-columns"><div id="book-inner">
+    <div><span class="koboSpan" id="kobo.{page}.1"><img src="../{image}" alt="{page}"/></span></div>
+  </div></div>
+</body>
+</html>
+"#
+    )
+}
+
+fn nav_xhtml(title: &str, bookmarks: &[(usize, String)]) -> String {
+    let mut items = String::new();
+    if bookmarks.is_empty() {
+        let _ = writeln!(
+            items,
+            r#"      <li><a href="pages/0000.xhtml">{title}</a></li>"#
+        );
+    }
+    for (page, bookmark) in bookmarks {
+        let _ = writeln!(
+            items,
+            r#"      <li><a href="pages/{page:04}.xhtml">{}</a></li>"#,
+            escape_markup(bookmark)
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head><title>{title}</title></head>
+<body>
+  <nav epub:type="toc">
+    <ol>
+{items}    </ol>
+  </nav>
+</body>
+</html>
+"#
+    )
+}
+
+fn media_type(extension: &str) -> &'static str {
+    match extension {
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => "image/jpeg",
+    }
+}
@@ -1,19 +1,38 @@
 #![deny(clippy::all, clippy::pedantic)]
 
-use std::fs;
+use std::{
+    fs::{self, File},
+    io::{Seek, Write},
+    time::Instant,
+};
 
-use camino::Utf8PathBuf;
-use eco_cbz::image::ReadingOrder;
-use eco_pack::pack_imgs_to_cbz;
-use tracing::info;
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{
+    image::Image, Bookmark, CancellationToken, CbzReader, CbzWriter, ComicInfo, ComicPages, Event,
+    Failure, Measure, NoProgress, Progress, Report, SkippedPage, Stage,
+};
+use eco_pack::{insert_img, Ocr, OcrCommand, PageRanges, Transform};
+use tracing::{error, error_span, info};
 
+pub use crate::epub::{convert_to_imgs as epub_to_imgs, read as read_epub};
 pub use crate::errors::{Error, Result};
-pub use crate::mobi::convert_to_imgs as mobi_to_imgs;
-pub use crate::pdf::convert_to_imgs as pdf_to_imgs;
+pub use crate::kepub::write_kepub;
+pub use crate::mobi::{
+    convert_to_imgs as mobi_to_imgs, dump_html as dump_mobi_html, read as read_mobi,
+};
+pub use crate::pdf::{
+    convert_to_imgs as pdf_to_imgs, for_each_img as pdf_for_each_img, PdfOptions,
+};
+pub use crate::render::{render_to_imgs as render_epub, PageSize, RenderOptions};
 
+mod drm;
+mod epub;
 pub mod errors;
+mod kepub;
 mod mobi;
 mod pdf;
+mod render;
+mod spreads;
 mod utils;
 
 #[derive(Debug, Clone, Copy)]
@@ -21,6 +40,28 @@ pub enum Format {
     Mobi,
     Azw3,
     Pdf,
+    Epub,
+    /// The pages of an existing archive, run through the transformations into a new archive
+    Cbz,
+}
+
+/// A chapter of the converted e-book
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chapter {
+    pub title: String,
+    /// Index of the chapter's first page
+    pub page: usize,
+}
+
+/// What the e-book is converted to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Cbz,
+    /// A directory containing the images, named like in the Cbz archives
+    Dir,
+    /// A fixed layout epub for Kobo devices
+    Kepub,
 }
 
 #[derive(Debug)]
@@ -31,47 +72,424 @@ pub struct ConvertOptions {
     /// Source format
     pub from: Format,
 
+    /// Output format
+    pub to: OutputFormat,
+
     /// Dir to output images
     pub outdir: Utf8PathBuf,
 
-    /// The archive name
+    /// The archive name, or the images directory name
     pub name: String,
 
-    /// Adjust images contrast
-    pub contrast: Option<f32>,
+    /// The transformations applied to the pages, and how they're encoded
+    pub transform: Transform,
+
+    /// Pdf only: render the pages without any extractable image at this dpi
+    pub rasterize_dpi: Option<u16>,
+
+    /// Pdf only: keep all the images of a page as successive pages, instead of the first one only
+    pub all_images: bool,
+
+    /// Only the selected pages are converted, for pdfs these are the pdf pages, and the images otherwise
+    pub pages: Option<PageRanges>,
+
+    /// Pdf only: join the spreads stored as 2 halves on consecutive pages
+    pub join_spreads: bool,
+
+    /// Epub only: paginate and rasterize the reflowable chapters, instead of extracting their images
+    pub render: Option<RenderOptions>,
 
-    /// Adjust images brightness
-    pub brightness: Option<i32>,
+    /// Stores the text of each page, recognized by this command, in an `eco-ocr.json` sidecar
+    pub ocr: Option<OcrCommand>,
 
-    /// Blur image (slow with big numbers)
-    pub blur: Option<f32>,
+    /// An image inserted as the first page, transformed like the other pages
+    pub cover_file: Option<Utf8PathBuf>,
 
-    /// Automatically split landscape images into 2 pages
-    pub autosplit: bool,
+    /// Mobi and azw3 only: writes the intermediate html to this path
+    pub debug_dump: Option<Utf8PathBuf>,
 
-    /// Reading order
-    pub reading_order: ReadingOrder,
+    /// The pages that can't be transformed are left out and reported, instead of aborting the conversion
+    pub keep_going: bool,
+
+    /// Aborts the conversion, the partial output is deleted
+    pub cancel: CancellationToken,
 }
 
+/// Returns the report of the conversion, with the path of the written archive or directory
 #[allow(clippy::missing_errors_doc)]
-pub fn convert(opts: ConvertOptions) -> Result<()> {
+pub fn convert(opts: ConvertOptions) -> Result<Report> {
+    convert_with_progress(opts, &NoProgress)
+}
+
+/// Same as `convert`, the progress is reported to `progress`, the total amount of pages is never known in advance
+#[allow(clippy::missing_errors_doc)]
+pub fn convert_with_progress(opts: ConvertOptions, progress: &dyn Progress) -> Result<Report> {
+    // The spans are at the error level, so the logs say which file and page they're about whatever the verbosity
+    let _span = error_span!("file", operation = "convert", file = %opts.path).entered();
+    progress.on_event(Event::FileStarted(opts.path.clone().into()));
     fs::create_dir_all(&opts.outdir)?;
-    let imgs = match opts.from {
-        Format::Mobi | Format::Azw3 => mobi_to_imgs(opts.path)?,
-        Format::Pdf => pdf_to_imgs(opts.path)?,
-    };
-    info!("found {} imgs", imgs.len());
 
-    let cbz_writer = pack_imgs_to_cbz(
-        imgs,
-        opts.contrast,
-        opts.brightness,
-        opts.blur,
-        opts.autosplit,
-        opts.reading_order,
-    )?;
+    let (output, converted) = match opts.to {
+        OutputFormat::Cbz => {
+            let output = opts.outdir.join(format!("{}.cbz", opts.name));
+            check_output(&opts, &output)?;
+            // The pages are written to the disk as soon as they're converted
+            match convert_into(&opts, CbzWriter::try_create_at_path(&output)?, progress) {
+                Ok(converted) => (output, converted),
+                Err(err) => {
+                    // Don't leave an incomplete archive behind
+                    let _ = fs::remove_file(&output);
+                    return Err(err);
+                }
+            }
+        }
+        OutputFormat::Dir => {
+            let output = opts.outdir.join(&opts.name);
+            // The archive is read back so the images are named and transformed exactly like in a Cbz
+            let mut converted = convert_into(&opts, CbzWriter::default(), progress)?;
+            let bytes = std::mem::take(converted.writer.get_mut());
+            let mut cbz_reader = CbzReader::try_from_bytes(bytes)?;
+            if opts.cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            let write = Measure::with_progress(Stage::Write, progress);
+            let created = !output.exists();
+            let count = match cbz_reader.extract_to_dir(&output) {
+                Ok(count) => count,
+                Err(err) => {
+                    // Don't leave a partly filled directory behind, unless it was already there
+                    if created {
+                        let _ = fs::remove_dir_all(&output);
+                    }
+                    return Err(err.into());
+                }
+            };
+            drop(write);
+            info!("{count} files written");
+            (output, converted)
+        }
+        OutputFormat::Kepub => {
+            let output = opts.outdir.join(format!("{}.kepub.epub", opts.name));
+            check_output(&opts, &output)?;
+            let mut converted = convert_into(&opts, CbzWriter::default(), progress)?;
+            let bytes = std::mem::take(converted.writer.get_mut());
+            let mut cbz_reader = CbzReader::try_from_bytes(bytes)?;
+            if opts.cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+            let _write = Measure::with_progress(Stage::Write, progress);
+            if let Err(err) = write_kepub(
+                &mut cbz_reader,
+                &opts.name,
+                opts.transform.reading_order,
+                File::create(&output)?,
+            ) {
+                // Don't leave an incomplete book behind
+                let _ = fs::remove_file(&output);
+                return Err(err);
+            }
+            (output, converted)
+        }
+    };
+    let size = output_size(&output)?;
 
-    cbz_writer.write_to_path(opts.outdir.join(format!("{}.cbz", opts.name)))?;
+    Ok(Report {
+        outputs: vec![output],
+        extracted_pages: converted.extracted_pages,
+        written_pages: converted.written_pages,
+        skipped_pages: converted.skipped_pages,
+        failures: converted.failures,
+        size,
+    })
+}
 
+/// The output is created before the source is read, which would truncate the source if they're the same file
+fn check_output(opts: &ConvertOptions, output: &Utf8Path) -> Result<()> {
+    if let (Ok(path), Ok(canonical_output)) = (opts.path.canonicalize(), output.canonicalize()) {
+        if path == canonical_output {
+            return Err(Error::OutputIsInput(output.to_path_buf()));
+        }
+    }
     Ok(())
 }
+
+/// The finished writer, and what was written to it
+struct Converted<W> {
+    writer: W,
+    extracted_pages: usize,
+    written_pages: usize,
+    skipped_pages: Vec<SkippedPage>,
+    failures: Vec<Failure>,
+}
+
+/// Moves the information of the source pages (bookmarks, types) to the first page they're written to,
+/// the pages that aren't written are dropped
+fn remap_pages(comic_info: &mut ComicInfo, inserted: &[(usize, usize)]) {
+    let Some(pages) = comic_info.pages.take() else {
+        return;
+    };
+    let pages = pages
+        .pages
+        .into_iter()
+        .filter_map(|mut page| {
+            let (_, first_page) = inserted
+                .iter()
+                .find(|(source_page, _)| *source_page == page.image)?;
+            page.image = *first_page;
+            Some(page)
+        })
+        .collect::<Vec<_>>();
+    comic_info.pages = (!pages.is_empty()).then_some(ComicPages { pages });
+}
+
+/// The first page the chapter's first source page is written to, or the next source page if it's not written.
+/// The chapters starting on a page left out by the selection are dropped.
+fn chapter_page(
+    chapter: &Chapter,
+    pages: Option<&PageRanges>,
+    inserted: &[(usize, usize)],
+) -> Option<usize> {
+    if pages.is_some_and(|pages| !pages.contains(chapter.page + 1)) {
+        return None;
+    }
+    let position = inserted.partition_point(|(source_page, _)| *source_page < chapter.page);
+    inserted.get(position).map(|(_, first_page)| *first_page)
+}
+
+/// The size of the file, or of all the files of the directory
+fn output_size(path: &Utf8Path) -> Result<u64> {
+    if !path.is_dir() {
+        return Ok(fs::metadata(path)?.len());
+    }
+    let mut size = 0;
+    for entry in path.read_dir_utf8()? {
+        size += output_size(entry?.path())?;
+    }
+
+    Ok(size)
+}
+
+/// Extracts, transforms, and inserts the images one at a time, then finishes the Cbz.
+/// Pdf images are streamed, so only one of them is held in memory at a time.
+fn convert_into<W>(
+    opts: &ConvertOptions,
+    mut cbz_writer: CbzWriter<W>,
+    progress: &dyn Progress,
+) -> Result<Converted<W>>
+where
+    W: Write + Seek,
+{
+    if let (Format::Mobi | Format::Azw3, Some(debug_dump)) = (opts.from, &opts.debug_dump) {
+        dump_mobi_html(&opts.path, debug_dump)?;
+    }
+    cbz_writer = opts.transform.configure(cbz_writer);
+    // The chapters are read with the images, the file is parsed once
+    let mut chapters = Vec::new();
+    // The metadata of a source archive is carried over, only its pages are moved
+    let mut source_comic_info = None;
+    let mut source_comment = Vec::new();
+    // The source pages that couldn't be read or decoded, recorded outside of `insert` which borrows the failures
+    let mut read_failures = Vec::new();
+    // The landscape images can be split in 2 pages, which shifts the following chapters,
+    // so each inserted source page (its index in the source) is recorded with the first page it's written to
+    let mut inserted = Vec::new();
+    let mut ocr = opts.ocr.clone().map(Ocr::new);
+    // Inserted before the pages so it's named first by the counter, and transformed like them
+    if let Some(cover_file) = &opts.cover_file {
+        insert_img(
+            &mut cbz_writer,
+            Image::open(cover_file)?,
+            &opts.transform,
+            ocr.as_mut(),
+            progress,
+        )?;
+    }
+    let mut failures = Vec::new();
+    // The pdf images are decoded as they're inserted, so the decoding time is the time spent between the insertions
+    let mut decoding_since = Instant::now();
+    let mut insert = |source_page: usize, img| -> Result<()> {
+        progress.on_event(Event::StageTimed {
+            stage: Stage::Decode,
+            duration: decoding_since.elapsed(),
+        });
+        if opts.cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let first_page = cbz_writer.len();
+        let _span = error_span!("page", page = source_page + 1).entered();
+        let result = insert_img(
+            &mut cbz_writer,
+            img,
+            &opts.transform,
+            ocr.as_mut(),
+            progress,
+        );
+        decoding_since = Instant::now();
+        match result {
+            // Only the written pages are recorded, so the chapters starting on a failed page move to the next one
+            Ok(()) => inserted.push((source_page, first_page)),
+            Err(err) if opts.keep_going => {
+                let input = format!("page {}", source_page + 1);
+                error!("{input} couldn't be converted: {err}");
+                failures.push(Failure {
+                    input,
+                    error: err.to_string(),
+                });
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        }
+        progress.on_event(Event::PageProcessed {
+            page: cbz_writer.len(),
+            total: None,
+        });
+        Ok(())
+    };
+    // The pdf pages are selected during the extraction, the other formats have one image per page
+    let is_unselected = |index: usize| {
+        opts.pages
+            .as_ref()
+            .is_some_and(|pages| !pages.contains(index + 1))
+    };
+    let unselected = |index: usize| SkippedPage {
+        page: index + 1,
+        reason: "not selected".to_string(),
+    };
+
+    let skipped_pages = match opts.from {
+        Format::Pdf => {
+            // The images are numbered in extraction order, a pdf page can hold several of them
+            let mut index = 0;
+            pdf_for_each_img(
+                &opts.path,
+                &PdfOptions {
+                    rasterize_dpi: opts.rasterize_dpi,
+                    all_images: opts.all_images,
+                    pages: opts.pages.clone(),
+                    join_spreads: opts.join_spreads.then_some(opts.transform.reading_order),
+                },
+                |img| {
+                    index += 1;
+                    insert(index - 1, img)
+                },
+            )?
+        }
+        Format::Mobi | Format::Azw3 | Format::Epub => {
+            let imgs = match (opts.from, &opts.render) {
+                (Format::Epub, Some(render)) => {
+                    let (imgs, rendered_chapters) = render_epub(&opts.path, render)?;
+                    chapters = rendered_chapters;
+                    imgs.into_iter().map(Ok).collect()
+                }
+                (Format::Epub, None) => read_epub(&opts.path)?,
+                _ => {
+                    let (imgs, mobi_chapters) = read_mobi(&opts.path)?;
+                    chapters = mobi_chapters;
+                    imgs
+                }
+            };
+            let mut skipped_pages = Vec::new();
+            for (index, img) in imgs.into_iter().enumerate() {
+                if is_unselected(index) {
+                    skipped_pages.push(unselected(index));
+                    continue;
+                }
+                match img {
+                    Ok(img) => insert(index, img)?,
+                    Err(err) if opts.keep_going => {
+                        let input = format!("page {}", index + 1);
+                        error!("{input} couldn't be decoded: {err}");
+                        skipped_pages.push(SkippedPage {
+                            page: index + 1,
+                            reason: format!("image couldn't be decoded: {err}"),
+                        });
+                        read_failures.push(Failure {
+                            input,
+                            error: err.to_string(),
+                        });
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            skipped_pages
+        }
+        Format::Cbz => {
+            // The pages are read one at a time, like the pdf images
+            let mut cbz_reader = CbzReader::try_from_path(&opts.path)?;
+            source_comic_info = cbz_reader.comic_info()?;
+            source_comment = cbz_reader.archive().comment().to_vec();
+            let mut skipped_pages = Vec::new();
+            for (index, name) in cbz_reader.file_names().iter().enumerate() {
+                if is_unselected(index) {
+                    skipped_pages.push(unselected(index));
+                    continue;
+                }
+                let img = cbz_reader
+                    .read_bytes_by_name(name)
+                    .and_then(|bytes| opts.transform.decode(bytes));
+                match img {
+                    Ok(img) => insert(index, img)?,
+                    Err(err) if opts.keep_going => {
+                        let input = format!("page {}", index + 1);
+                        error!("{input} couldn't be read: {err}");
+                        skipped_pages.push(SkippedPage {
+                            page: index + 1,
+                            reason: format!("page couldn't be read: {err}"),
+                        });
+                        read_failures.push(Failure {
+                            input,
+                            error: err.to_string(),
+                        });
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+            skipped_pages
+        }
+    };
+    // The pages that were read but couldn't be transformed or written are still extracted
+    let extracted_pages = inserted.len() + failures.len();
+    failures.append(&mut read_failures);
+    info!("found {} imgs", inserted.len());
+    if inserted.is_empty() {
+        return Err(Error::NoImages);
+    }
+    info!("found {} chapters", chapters.len());
+
+    if let Some(ocr) = ocr {
+        ocr.write_to(&mut cbz_writer)?;
+    }
+
+    let mut comic_info = source_comic_info.unwrap_or_default();
+    remap_pages(&mut comic_info, &inserted);
+    if !chapters.is_empty() {
+        comic_info.set_bookmarks(chapters.into_iter().filter_map(|chapter| {
+            Some(Bookmark {
+                page: chapter_page(&chapter, opts.pages.as_ref(), &inserted)?,
+                title: chapter.title,
+            })
+        }));
+    }
+    if comic_info != ComicInfo::default() {
+        cbz_writer.set_comic_info(&comic_info)?;
+    }
+    // The `ComicBookInfo` metadata doesn't refer to the pages, it's copied as is
+    if !source_comment.is_empty() {
+        cbz_writer
+            .raw_archive_mut()
+            .set_comment(String::from_utf8_lossy(&source_comment));
+    }
+
+    let written_pages = cbz_writer.len();
+    let write = Measure::with_progress(Stage::Write, progress);
+    let writer = cbz_writer.finish()?;
+    drop(write);
+
+    Ok(Converted {
+        writer,
+        extracted_pages,
+        written_pages,
+        skipped_pages,
+        failures,
+    })
+}
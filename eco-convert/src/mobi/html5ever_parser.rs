@@ -1,29 +1,28 @@
-use std::{fs, io::BufReader, path::Path};
+use std::io::BufReader;
 
 use eco_cbz::image::Image;
 use html5ever::{parse_document, tendril::TendrilSink, ParseOpts};
 use markup5ever_rcdom::{Node, NodeData, RcDom};
 use mobi::Mobi;
-use tracing::{error, warn};
+use tracing::warn;
 
 use crate::{utils::base_32, Result};
 
 use super::MobiVersion;
 
 #[allow(clippy::missing_errors_doc)]
-pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
-    let mobi = Mobi::from_path(path)?;
-    // Or is it `gen_version`? Both were equal in all the files I tested.
-    let version = MobiVersion::try_from(mobi.metadata.mobi.format_version)?;
-    let dom = get_dom(&mobi)?;
+pub fn convert_to_imgs(
+    mobi: &Mobi,
+    version: MobiVersion,
+    html: &str,
+) -> Result<Vec<eco_cbz::Result<Image>>> {
+    let dom = get_dom(html)?;
     let imgs = mobi.image_records();
     let mut all_imgs = Vec::with_capacity(imgs.len());
     visit_node(version, &dom.document, |fid| {
+        // The images that can't be decoded are kept, so the chapters still point to the right images
         if let Some(img) = imgs.get(fid) {
-            match img.content.try_into() {
-                Ok(img) => all_imgs.push(img),
-                Err(err) => error!("failed to decode image: {err}"),
-            };
+            all_imgs.push(img.content.try_into());
         } else {
             warn!("unknown fid {fid}");
         }
@@ -31,9 +30,7 @@ pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
     Ok(all_imgs)
 }
 
-fn get_dom(m: &Mobi) -> Result<RcDom> {
-    let html = m.content_as_string_lossy();
-    fs::write("index.html", html.as_bytes())?;
+fn get_dom(html: &str) -> Result<RcDom> {
     let mut buf = BufReader::new(html.as_bytes());
     let dom = parse_document(RcDom::default(), ParseOpts::default())
         .from_utf8()
@@ -0,0 +1,481 @@
+use std::collections::HashMap;
+
+use mobi::Mobi;
+use tracing::{debug, warn};
+
+/// Offsets, in the first record, of the indexes describing how the html is split in the KF8 (azw3) files,
+/// and of its table of contents
+const NCX_INDEX_OFFSET: usize = 0xf4;
+const FRAGMENT_INDEX_OFFSET: usize = 0xf8;
+const SKELETON_INDEX_OFFSET: usize = 0xfc;
+
+/// Marks an index missing from the file
+const NO_INDEX: u32 = 0xffff_ffff;
+
+/// The files of a KF8 book reassembled in reading order
+#[derive(Debug)]
+pub struct Book {
+    /// The files, one after the other
+    pub html: Vec<u8>,
+    skeletons: Vec<Skeleton>,
+    fragments: Vec<Fragment>,
+    files_starts: Vec<usize>,
+}
+
+impl Book {
+    /// The position in `html` of an offset in a fragment, which is what the links and the table of contents point to
+    pub fn position(&self, fid: usize, offset: usize) -> Option<usize> {
+        let fragment = self.fragments.get(fid)?;
+        let skeleton = self.skeletons.get(fragment.file)?;
+        let insert_position = fragment.insert_position.checked_sub(skeleton.start)?;
+
+        Some(self.files_starts.get(fragment.file)? + insert_position + offset)
+    }
+}
+
+/// An entry of the table of contents, pointing to an offset in a fragment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NcxEntry {
+    pub label: String,
+    pub fid: usize,
+    pub offset: usize,
+}
+
+/// The html of a KF8 file is split in skeletons (the files of the book, in reading order, without their content)
+/// and fragments (the content, inserted back into its skeleton).
+/// Returns the reassembled book, or `None` if the file doesn't have these indexes.
+pub fn reassemble(mobi: &Mobi, text: &[u8]) -> Option<Book> {
+    let skeletons = read_index(mobi, header_u32(mobi, SKELETON_INDEX_OFFSET)?)?
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            Some(Skeleton {
+                fragments: *entry.tags.get(&1)?.first()?,
+                start: *entry.tags.get(&6)?.first()?,
+                length: *entry.tags.get(&6)?.get(1)?,
+            })
+        })
+        .collect::<Vec<_>>();
+    let fragments = read_index(mobi, header_u32(mobi, FRAGMENT_INDEX_OFFSET)?)?
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            Some(Fragment {
+                insert_position: String::from_utf8_lossy(&entry.key).parse().ok()?,
+                file: *entry.tags.get(&3)?.first()?,
+                length: *entry.tags.get(&6)?.get(1)?,
+            })
+        })
+        .collect::<Vec<_>>();
+    debug!(
+        "{} skeletons and {} fragments",
+        skeletons.len(),
+        fragments.len()
+    );
+
+    let files = assemble(text, &skeletons, &fragments)?;
+    let files_starts = files
+        .iter()
+        .scan(0, |start, file| {
+            let file_start = *start;
+            *start += file.len();
+            Some(file_start)
+        })
+        .collect();
+
+    Some(Book {
+        html: files.concat(),
+        skeletons,
+        fragments,
+        files_starts,
+    })
+}
+
+/// Reads the table of contents, the labels are stored in the strings records of the index
+pub fn read_ncx(mobi: &Mobi) -> Vec<NcxEntry> {
+    let Some(index) = header_u32(mobi, NCX_INDEX_OFFSET).and_then(|index| read_index(mobi, index))
+    else {
+        debug!("no table of contents");
+        return Vec::new();
+    };
+
+    index
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            let label = index.strings.get(entry.tags.get(&3)?.first()?)?;
+            let position = entry.tags.get(&6)?;
+            Some(NcxEntry {
+                label: label.clone(),
+                fid: *position.first()?,
+                offset: *position.get(1)?,
+            })
+        })
+        .collect()
+}
+
+/// A file of the book, its fragments follow it in the text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Skeleton {
+    fragments: usize,
+    start: usize,
+    length: usize,
+}
+
+/// A part of a file, inserted at a position of the reassembled file, offset by the skeleton start
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fragment {
+    insert_position: usize,
+    file: usize,
+    length: usize,
+}
+
+/// The fragments are inserted in order, so each insert position already accounts for the previous fragments
+fn assemble(text: &[u8], skeletons: &[Skeleton], fragments: &[Fragment]) -> Option<Vec<Vec<u8>>> {
+    let mut fragments = fragments.iter();
+    let mut files = Vec::with_capacity(skeletons.len());
+    for skeleton in skeletons {
+        let mut position = skeleton.start + skeleton.length;
+        let mut file = text.get(skeleton.start..position)?.to_vec();
+        for _ in 0..skeleton.fragments {
+            let Some(fragment) = fragments.next() else {
+                warn!("missing fragments, the html is read as is");
+                return None;
+            };
+            let content = text.get(position..position + fragment.length)?;
+            let insert_position = fragment.insert_position.checked_sub(skeleton.start)?;
+            if insert_position > file.len() {
+                warn!("invalid fragment position, the html is read as is");
+                return None;
+            }
+            file.splice(insert_position..insert_position, content.iter().copied());
+            position += fragment.length;
+        }
+        files.push(file);
+    }
+
+    Some(files)
+}
+
+/// The entries of an index, and the strings they reference by offset
+#[derive(Debug, Default)]
+struct Index {
+    entries: Vec<IndexEntry>,
+    strings: HashMap<usize, String>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    key: Vec<u8>,
+    tags: HashMap<u8, Vec<usize>>,
+}
+
+/// A tag of the `TAGX` section, which describes how the tags are encoded in the entries
+#[derive(Debug, Clone, Copy)]
+struct TagDefinition {
+    tag: u8,
+    values_per_entry: u8,
+    mask: u8,
+    end_flag: u8,
+}
+
+/// Reads the `INDX` record at this index, the entries records follow it, then the strings (`CNCX`) records
+fn read_index(mobi: &Mobi, index: u32) -> Option<Index> {
+    if index == NO_INDEX {
+        return None;
+    }
+    let index = usize::try_from(index).ok()?;
+    let header = record(mobi, index)?;
+    if header.get(..4)? != b"INDX" {
+        warn!("index {index} is not an INDX record");
+        return None;
+    }
+    let header_length = read_u32(header, 4)?;
+    let records_count = read_u32(header, 24)?;
+    let strings_count = read_u32(header, 52)?;
+    let (control_bytes, definitions) = read_tags_definitions(header, header_length)?;
+
+    let mut strings = HashMap::new();
+    for string_record in 0..strings_count {
+        let data = record(mobi, index + records_count + 1 + string_record)?;
+        strings.extend(
+            read_strings(data)
+                .into_iter()
+                .map(|(offset, string)| (offset + string_record * 0x10000, string)),
+        );
+    }
+
+    let mut entries = Vec::new();
+    for entries_record in index + 1..=index + records_count {
+        let data = record(mobi, entries_record)?;
+        let positions_start = read_u32(data, 20)?;
+        let count = read_u32(data, 24)?;
+        let mut positions = (0..count)
+            .map(|entry| read_u16(data, positions_start + 4 + 2 * entry))
+            .collect::<Option<Vec<_>>>()?;
+        positions.push(positions_start);
+        for window in positions.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            let key_length = usize::from(*data.get(start)?);
+            let key = data.get(start + 1..start + 1 + key_length)?.to_vec();
+            let tags = read_tags(
+                &definitions,
+                control_bytes,
+                data.get(..end)?,
+                start + 1 + key_length,
+            )?;
+            entries.push(IndexEntry { key, tags });
+        }
+    }
+
+    Some(Index { entries, strings })
+}
+
+/// Returns the amount of control bytes of the entries, and the tags definitions
+fn read_tags_definitions(header: &[u8], start: usize) -> Option<(usize, Vec<TagDefinition>)> {
+    if header.get(start..start + 4)? != b"TAGX" {
+        return None;
+    }
+    let first_entry = read_u32(header, start + 4)?;
+    let control_bytes = read_u32(header, start + 8)?;
+    let definitions = (12..first_entry)
+        .step_by(4)
+        .map(|offset| {
+            let bytes = header.get(start + offset..start + offset + 4)?;
+            Some(TagDefinition {
+                tag: bytes[0],
+                values_per_entry: bytes[1],
+                mask: bytes[2],
+                end_flag: bytes[3],
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((control_bytes, definitions))
+}
+
+/// The control bytes say which tags the entry has, and how many values each one has
+fn read_tags(
+    definitions: &[TagDefinition],
+    control_bytes: usize,
+    data: &[u8],
+    start: usize,
+) -> Option<HashMap<u8, Vec<usize>>> {
+    enum Count {
+        Values(usize),
+        Bytes(usize),
+    }
+
+    let mut control_index = 0;
+    let mut offset = start + control_bytes;
+    let mut counts = Vec::new();
+    for definition in definitions {
+        if definition.end_flag == 1 {
+            control_index += 1;
+            continue;
+        }
+        let value = data.get(start + control_index)? & definition.mask;
+        if value == 0 {
+            continue;
+        }
+        if value == definition.mask && definition.mask.count_ones() > 1 {
+            // The amount of bytes of the values follows the control bytes
+            let (consumed, bytes) = read_variable_width(data, offset)?;
+            offset += consumed;
+            counts.push((definition, Count::Bytes(bytes)));
+        } else {
+            let count = usize::from(value >> definition.mask.trailing_zeros());
+            counts.push((definition, Count::Values(count)));
+        }
+    }
+
+    let mut tags = HashMap::new();
+    for (definition, count) in counts {
+        let mut values = Vec::new();
+        match count {
+            Count::Values(count) => {
+                for _ in 0..count * usize::from(definition.values_per_entry) {
+                    let (consumed, value) = read_variable_width(data, offset)?;
+                    offset += consumed;
+                    values.push(value);
+                }
+            }
+            Count::Bytes(bytes) => {
+                let end = offset + bytes;
+                while offset < end {
+                    let (consumed, value) = read_variable_width(data, offset)?;
+                    offset += consumed;
+                    values.push(value);
+                }
+            }
+        }
+        tags.insert(definition.tag, values);
+    }
+
+    Some(tags)
+}
+
+/// The strings are prefixed with their length, and referenced by their offset in the record
+fn read_strings(data: &[u8]) -> Vec<(usize, String)> {
+    let mut strings = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() && data[offset] != 0 {
+        let Some((consumed, length)) = read_variable_width(data, offset) else {
+            break;
+        };
+        let Some(string) = data.get(offset + consumed..offset + consumed + length) else {
+            break;
+        };
+        strings.push((offset, String::from_utf8_lossy(string).into_owned()));
+        offset += consumed + length;
+    }
+
+    strings
+}
+
+/// The values are stored 7 bits per byte, the high bit is set on the last byte
+fn read_variable_width(data: &[u8], offset: usize) -> Option<(usize, usize)> {
+    let mut value = 0;
+    let mut consumed = 0;
+    loop {
+        let byte = *data.get(offset + consumed)?;
+        consumed += 1;
+        value = (value << 7) | usize::from(byte & 0x7f);
+        if byte & 0x80 != 0 {
+            return Some((consumed, value));
+        }
+    }
+}
+
+/// The records are read from the file content, the mobi crate strips the trailing bytes of the text records from all of them
+fn record(mobi: &Mobi, index: usize) -> Option<&[u8]> {
+    let records = &mobi.metadata.records.records;
+    let start = usize::try_from(records.get(index)?.offset).ok()?;
+    let end = match records.get(index + 1) {
+        Some(next) => usize::try_from(next.offset).ok()?,
+        None => mobi.content.len(),
+    };
+
+    mobi.content.get(start..end)
+}
+
+/// Reads a value of the first record, which starts with the `PalmDOC` and `MOBI` headers
+fn header_u32(mobi: &Mobi, offset: usize) -> Option<u32> {
+    let bytes = record(mobi, 0)?.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 4)?;
+    usize::try_from(u32::from_be_bytes(bytes.try_into().ok()?)).ok()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(usize::from(u16::from_be_bytes(bytes.try_into().ok()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragments_are_inserted_into_their_skeleton() {
+        let text = b"<html><body></body></html><p>1</p><p>2</p><html></html>";
+        let skeletons = [
+            Skeleton {
+                fragments: 2,
+                start: 0,
+                length: 26,
+            },
+            Skeleton {
+                fragments: 0,
+                start: 42,
+                length: 13,
+            },
+        ];
+        let fragments = [
+            Fragment {
+                insert_position: 12,
+                file: 0,
+                length: 8,
+            },
+            Fragment {
+                insert_position: 20,
+                file: 0,
+                length: 8,
+            },
+        ];
+
+        let files = assemble(text, &skeletons, &fragments).unwrap();
+
+        assert_eq!(
+            files,
+            vec![
+                b"<html><body><p>1</p><p>2</p></body></html>".to_vec(),
+                b"<html></html>".to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_point_into_the_reassembled_files() {
+        let book = Book {
+            html: b"<html><body><p>1</p><p>2</p></body></html><html><p>3</p></html>".to_vec(),
+            skeletons: vec![
+                Skeleton {
+                    fragments: 2,
+                    start: 0,
+                    length: 26,
+                },
+                Skeleton {
+                    fragments: 1,
+                    start: 42,
+                    length: 13,
+                },
+            ],
+            fragments: vec![
+                Fragment {
+                    insert_position: 12,
+                    file: 0,
+                    length: 8,
+                },
+                Fragment {
+                    insert_position: 20,
+                    file: 0,
+                    length: 8,
+                },
+                Fragment {
+                    insert_position: 48,
+                    file: 1,
+                    length: 8,
+                },
+            ],
+            files_starts: vec![0, 42],
+        };
+
+        assert_eq!(book.position(1, 3), Some(23));
+        assert_eq!(&book.html[23..24], b"2");
+        assert_eq!(book.position(2, 3), Some(51));
+        assert_eq!(&book.html[51..52], b"3");
+        assert_eq!(book.position(3, 0), None);
+    }
+
+    #[test]
+    fn missing_fragments_are_rejected() {
+        let skeletons = [Skeleton {
+            fragments: 1,
+            start: 0,
+            length: 4,
+        }];
+
+        assert_eq!(assemble(b"<a/>", &skeletons, &[]), None);
+    }
+
+    #[test]
+    fn variable_width_values() {
+        assert_eq!(read_variable_width(&[0x81], 0), Some((1, 1)));
+        assert_eq!(read_variable_width(&[0x01, 0x80], 0), Some((2, 128)));
+        assert_eq!(read_variable_width(&[0x01], 0), None);
+    }
+}
@@ -1,14 +1,83 @@
+use std::{fs, path::Path};
+
+use eco_cbz::image::Image;
+use mobi::Mobi;
+use tracing::{debug, info};
+
 #[cfg(feature = "html5ever")]
-pub use html5ever_parser::convert_to_imgs;
+use html5ever_parser::convert_to_imgs as parse_imgs;
 #[cfg(not(feature = "html5ever"))]
-pub use tl_parser::convert_to_imgs;
+use tl_parser::convert_to_imgs as parse_imgs;
 
-use crate::Error;
+use crate::{drm::ensure_mobi_drm_free, utils::decoded, Chapter, Error, Result};
 
 #[cfg(feature = "html5ever")]
 mod html5ever_parser;
+mod kf8;
 #[cfg(not(feature = "html5ever"))]
 mod tl_parser;
+mod toc;
+
+/// Reads the images, and the chapters of the table of contents, `Chapter::page` is the index of the chapter's first image.
+/// The images that can't be decoded are returned as errors, in their place.
+///
+/// ## Errors
+///
+/// Fails with `Error::DrmProtected` if the file is encrypted, or if it's not a valid mobi
+pub fn read(path: impl AsRef<Path>) -> Result<(Vec<eco_cbz::Result<Image>>, Vec<Chapter>)> {
+    let path = path.as_ref();
+    let bytes = fs::read(path)?;
+    ensure_mobi_drm_free(path, &bytes)?;
+    let mobi = Mobi::new(bytes)?;
+    // Or is it `gen_version`? Both were equal in all the files I tested.
+    let version = MobiVersion::try_from(mobi.metadata.mobi.format_version)?;
+    debug!("mobi version {version:#?}");
+    let (html, chapters) = html(&mobi, version);
+    let imgs = parse_imgs(&mobi, version, &html)?;
+
+    Ok((imgs, chapters))
+}
+
+/// ## Errors
+///
+/// Fails with `Error::DrmProtected` if the file is encrypted, or if it's not a valid mobi
+pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
+    Ok(decoded(read(path)?.0))
+}
+
+/// Writes the html content the images are extracted from, handy to debug a conversion
+///
+/// ## Errors
+///
+/// Fails if the file is not a valid mobi, or if the html can't be written
+pub fn dump_html(path: impl AsRef<Path>, dump_path: impl AsRef<Path>) -> Result<()> {
+    let mobi = Mobi::from_path(path)?;
+    let version = MobiVersion::try_from(mobi.metadata.mobi.format_version)?;
+    fs::write(&dump_path, html(&mobi, version).0)?;
+    info!("html dumped to {}", dump_path.as_ref().display());
+
+    Ok(())
+}
+
+/// The html content in reading order, the KF8 files are reassembled from their skeletons and fragments,
+/// and the chapters its table of contents points to
+fn html(mobi: &Mobi, version: MobiVersion) -> (String, Vec<Chapter>) {
+    let content = mobi.content_as_string_lossy();
+    match version {
+        MobiVersion::Mobi6 => {
+            let chapters = toc::filepos_chapters(content.as_bytes());
+            (content, chapters)
+        }
+        MobiVersion::Mobi8 => {
+            let Some(book) = kf8::reassemble(mobi, content.as_bytes()) else {
+                debug!("the skeletons can't be read, the html is read in records order");
+                return (content, Vec::new());
+            };
+            let chapters = toc::ncx_chapters(&book, kf8::read_ncx(mobi));
+            (String::from_utf8_lossy(&book.html).into_owned(), chapters)
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum MobiVersion {
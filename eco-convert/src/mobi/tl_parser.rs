@@ -1,31 +1,26 @@
-use std::path::Path;
-
 use eco_cbz::image::Image;
 use mobi::Mobi;
 use tl::{HTMLTag, ParserOptions, VDom};
-use tracing::{debug, error, warn};
+use tracing::{debug, warn};
 
 use crate::{utils::base_32, Result};
 
 use super::MobiVersion;
 
 #[allow(clippy::missing_errors_doc)]
-pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
-    let mobi = Mobi::from_path(path)?;
-    // Or is it `gen_version`? Both were equal in all the files I tested.
-    let version = MobiVersion::try_from(mobi.metadata.mobi.format_version)?;
-    debug!("mobi version {version:#?}");
+pub fn convert_to_imgs(
+    mobi: &Mobi,
+    version: MobiVersion,
+    html: &str,
+) -> Result<Vec<eco_cbz::Result<Image>>> {
     let imgs = mobi.image_records();
     debug!("found {} images", imgs.len());
-    let html = mobi.content_as_string_lossy();
-    let dom = tl::parse(&html, ParserOptions::default())?;
+    let dom = tl::parse(html, ParserOptions::default())?;
     let mut all_imgs = Vec::with_capacity(imgs.len());
     for_each_fid(version, &dom, |fid| {
+        // The images that can't be decoded are kept, so the chapters still point to the right images
         if let Some(img) = imgs.get(fid) {
-            match img.content.try_into() {
-                Ok(img) => all_imgs.push(img),
-                Err(err) => error!("failed to decode image: {err}"),
-            };
+            all_imgs.push(img.content.try_into());
         } else {
             warn!("unknown fid {fid}");
         }
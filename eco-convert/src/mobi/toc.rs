@@ -0,0 +1,106 @@
+use tracing::debug;
+
+use crate::Chapter;
+
+use super::kf8::{Book, NcxEntry};
+
+/// The mobi6 tables of contents are `filepos` links, which are offsets in the text,
+/// so the images positions are looked up in the same bytes.
+pub fn filepos_chapters(content: &[u8]) -> Vec<Chapter> {
+    let images = find_all(content, b"recindex=").collect::<Vec<_>>();
+    let mut links = Vec::new();
+
+    for link in find_all(content, b"<a ") {
+        let Some(end) = find(&content[link..], b"</a>").map(|end| link + end) else {
+            continue;
+        };
+        let tag = &content[link..end];
+        let Some(filepos) =
+            find(tag, b"filepos=").and_then(|start| parse_number(&tag[start + 8..]))
+        else {
+            continue;
+        };
+        let Some(text_start) = find(tag, b">") else {
+            continue;
+        };
+        let title = strip_tags(&String::from_utf8_lossy(&tag[text_start + 1..]));
+        links.push((title, filepos));
+    }
+
+    chapters_at(&images, links)
+}
+
+/// The KF8 tables of contents (NCX) point to offsets in the fragments, which are looked up in the reassembled files
+pub fn ncx_chapters(book: &Book, entries: Vec<NcxEntry>) -> Vec<Chapter> {
+    debug!("{} table of contents entries", entries.len());
+    let images = find_all(&book.html, b"<img").collect::<Vec<_>>();
+    let links = entries
+        .into_iter()
+        .filter_map(|entry| {
+            let position = book.position(entry.fid, entry.offset)?;
+            Some((strip_tags(&entry.label), position))
+        })
+        .collect();
+
+    chapters_at(&images, links)
+}
+
+/// Each chapter starts at the first image following its position
+fn chapters_at(images: &[usize], links: Vec<(String, usize)>) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    for (title, position) in links {
+        if title.is_empty() {
+            continue;
+        }
+        let page = images.partition_point(|&image| image < position);
+        // Several links can point to the same chapter, e.g. the guide and the table of contents
+        if chapters.iter().any(|chapter| chapter.page == page) {
+            continue;
+        }
+        chapters.push(Chapter { title, page });
+    }
+
+    chapters.sort_by_key(|chapter| chapter.page);
+    chapters
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle))
+}
+
+fn find_all<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| window.eq_ignore_ascii_case(needle))
+        .map(|(index, _)| index)
+}
+
+/// Parses a number, optionally quoted, like `0000012345` or `"12345"`
+fn parse_number(bytes: &[u8]) -> Option<usize> {
+    let digits = bytes
+        .iter()
+        .skip_while(|byte| **byte == b'"' || **byte == b'\'')
+        .take_while(|byte| byte.is_ascii_digit())
+        .map(|byte| char::from(*byte))
+        .collect::<String>();
+
+    digits.parse().ok()
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
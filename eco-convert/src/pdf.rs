@@ -1,28 +1,136 @@
 use std::{io::Cursor, path::Path};
 
-use eco_cbz::image::Image;
+use eco_cbz::{
+    image::{Image, ReadingOrder},
+    SkippedPage,
+};
+use eco_pack::PageRanges;
 use pdf::{
+    content::Op,
     enc::StreamFilter,
     file::FileOptions as PdfFileOptions,
-    object::{Resolve, XObject},
+    object::{Ref, Resolve, XObject},
 };
-use tracing::error;
+use pdfium_render::prelude::{PdfDocument, PdfRenderConfig, Pdfium};
+use tracing::{debug, error, error_span, warn};
+
+use crate::{drm::is_pdf_encrypted, spreads::SpreadJoiner, Error, Result};
+
+#[derive(Debug, Default, Clone)]
+pub struct PdfOptions {
+    /// When set, the pages without any extractable image are rendered at this dpi (requires the pdfium library)
+    pub rasterize_dpi: Option<u16>,
+
+    /// Emits all the images of a page as successive pages instead of the first one only
+    pub all_images: bool,
 
-use crate::Result;
+    /// Only the selected pages are processed
+    pub pages: Option<PageRanges>,
+
+    /// When set, the spreads stored as 2 halves on consecutive pages are joined, in this reading order
+    pub join_spreads: Option<ReadingOrder>,
+}
 
 #[allow(clippy::missing_errors_doc)]
-pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
-    let pdf = PdfFileOptions::cached().open(path)?;
-    // We may have actually less images than the count but never more,
-    // at worse we request a slightly bigger capacity than necessary but at best we prevent any further allocations.
-    let mut imgs = Vec::with_capacity(pdf.pages().count());
-
-    for page in pdf.pages() {
-        for resource in page?.resources()?.xobjects.values() {
-            let resource = match pdf.get(*resource) {
+pub fn convert_to_imgs(path: impl AsRef<Path>, opts: &PdfOptions) -> Result<Vec<Image>> {
+    let mut imgs = Vec::new();
+    for_each_img(path, opts, |img| {
+        imgs.push(img);
+        Ok(())
+    })?;
+
+    Ok(imgs)
+}
+
+/// Extracts the images one page at a time, and passes them to `f` as soon as they're decoded.
+/// The pdf objects are not cached, so the memory usage stays bounded whatever the size of the pdf.
+/// Returns the selected pages that didn't produce any image, and why.
+///
+/// ## Errors
+///
+/// Fails if the pdf can't be opened, or if `f` fails
+pub fn for_each_img<F>(
+    path: impl AsRef<Path>,
+    opts: &PdfOptions,
+    mut f: F,
+) -> Result<Vec<SkippedPage>>
+where
+    F: FnMut(Image) -> Result<()>,
+{
+    let path = path.as_ref();
+    let pdf = match PdfFileOptions::uncached().open(path) {
+        Ok(pdf) => pdf,
+        Err(_) if is_pdf_encrypted(path) => {
+            return Err(Error::DrmProtected(path.display().to_string()))
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // Pdfium is only bound if some pages need to be rasterized
+    let pdfium = match opts.rasterize_dpi {
+        Some(_) => Some(Pdfium::new(Pdfium::bind_to_system_library()?)),
+        None => None,
+    };
+    let rasterizer = match &pdfium {
+        Some(pdfium) => Some(pdfium.load_pdf_from_file(path, None)?),
+        None => None,
+    };
+    let mut joiner = opts.join_spreads.map(SpreadJoiner::new);
+    let mut skipped_pages = Vec::new();
+    let mut emit = |img| match &mut joiner {
+        Some(joiner) => match joiner.push(img) {
+            Some(img) => f(img),
+            None => Ok(()),
+        },
+        None => f(img),
+    };
+
+    for (index, page) in pdf.pages().enumerate() {
+        if opts
+            .pages
+            .as_ref()
+            .is_some_and(|pages| !pages.contains(index + 1))
+        {
+            skipped_pages.push(SkippedPage {
+                page: index + 1,
+                reason: "not selected".to_string(),
+            });
+            continue;
+        }
+        let _span = error_span!("pdf_page", page = index + 1).entered();
+        let page = page?;
+        let resources = page.resources()?;
+        // The images are drawn in the order of the content stream, which is not the one of the resources dictionary
+        let drawn = match &page.contents {
+            Some(contents) => match contents.operations(&pdf) {
+                Ok(operations) => operations
+                    .into_iter()
+                    .filter_map(|operation| match operation {
+                        Op::XObject { name } => resources.xobjects.get(&name).copied(),
+                        _ => None,
+                    })
+                    .collect(),
+                Err(err) => {
+                    warn!("failed to parse page {} content: {err}", index + 1);
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        let xobjects: Vec<Ref<XObject>> = if drawn.is_empty() {
+            resources.xobjects.values().copied().collect()
+        } else {
+            drawn
+        };
+
+        let mut found = false;
+        // Replaced by the last error, if any, when no image is found
+        let mut reason = "no extractable image found".to_string();
+        for resource in xobjects {
+            let resource = match pdf.get(resource) {
                 Ok(resource) => resource,
                 Err(err) => {
                     error!("failed to get resource from pdf: {err}");
+                    reason = format!("resource couldn't be read: {err}");
                     continue;
                 }
             };
@@ -31,6 +139,7 @@ pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
                     Ok(image_data) => image_data,
                     Err(err) => {
                         error!("failed to get image data: {err}");
+                        reason = format!("image data couldn't be read: {err}");
                         continue;
                     }
                 };
@@ -39,15 +148,57 @@ pub fn convert_to_imgs(path: impl AsRef<Path>) -> Result<Vec<Image>> {
                         Ok(img) => img,
                         Err(err) => {
                             error!("image couldn't be read: {err}");
+                            reason = format!("image couldn't be decoded: {err}");
                             continue;
                         }
                     };
-                    imgs.push(img);
-                    break;
+                    emit(img)?;
+                    found = true;
+                    if !opts.all_images {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let (false, Some(document), Some(dpi)) = (found, &rasterizer, opts.rasterize_dpi) {
+            debug!("no image found on page {}, rasterizing it", index + 1);
+            match rasterize_page(document, index, dpi) {
+                Ok(img) => {
+                    emit(img)?;
+                    found = true;
+                }
+                Err(err) => {
+                    error!("page {} couldn't be rasterized: {err}", index + 1);
+                    reason = format!("page couldn't be rasterized: {err}");
                 }
             }
         }
+        if !found {
+            skipped_pages.push(SkippedPage {
+                page: index + 1,
+                reason,
+            });
+        }
     }
 
-    Ok(imgs)
+    if let Some(img) = joiner.and_then(SpreadJoiner::finish) {
+        f(img)?;
+    }
+
+    Ok(skipped_pages)
+}
+
+/// Renders the whole page at the given dpi
+fn rasterize_page(document: &PdfDocument<'_>, index: usize, dpi: u16) -> Result<Image> {
+    let page = document
+        .pages()
+        .get(u16::try_from(index).map_err(|_| Error::PdfPageIndex(index))?)?;
+    #[allow(clippy::cast_possible_truncation)]
+    let width = (page.width().to_inches() * f32::from(dpi)).round() as i32;
+    let image = page
+        .render_with_config(&PdfRenderConfig::new().set_target_width(width))?
+        .as_image();
+
+    Ok(Image::from(image))
 }
@@ -0,0 +1,216 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::ErrorKind,
+    path::{Component, Path},
+    process::{self, Command},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use eco_cbz::{image::Image, markup::inject_in_head};
+use epub::doc::{EpubDoc, NavPoint};
+use pdfium_render::prelude::{PdfRenderConfig, Pdfium};
+use tracing::{debug, warn};
+use zip::ZipArchive;
+
+use crate::{Chapter, Error, Result};
+
+/// Makes the temporary directory names unique within the process
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Size of the rendered pages, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSize {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for PageSize {
+    type Err = Error;
+
+    /// Parses sizes like `1072x1448`
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidPageSize(s.to_string());
+        let (width, height) = s.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let width = width.trim().parse::<u32>().map_err(|_| invalid())?;
+        let height = height.trim().parse::<u32>().map_err(|_| invalid())?;
+        if width == 0 || height == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Self { width, height })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub page_size: PageSize,
+
+    /// Blank space around the text, in pixels
+    pub margin: u32,
+
+    /// The Chromium (or Chrome) executable laying out the pages
+    pub browser: String,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            // The screen of most 6 inches e-readers
+            page_size: PageSize {
+                width: 1072,
+                height: 1448,
+            },
+            margin: 48,
+            browser: "chromium".to_string(),
+        }
+    }
+}
+
+/// Paginates the chapters of a reflowable epub at the page size, and rasterizes each page.
+/// The chapters are laid out by a headless browser, printed to pdf, and rendered by pdfium.
+/// Returns the pages, and the chapters found in the table of contents.
+///
+/// ## Errors
+///
+/// Fails if the epub can't be extracted, if the browser can't be run, or if the pdfium library is missing
+pub fn render_to_imgs(
+    path: impl AsRef<Path>,
+    opts: &RenderOptions,
+) -> Result<(Vec<Image>, Vec<Chapter>)> {
+    let path = path.as_ref();
+    let dir = env::temp_dir().join(format!(
+        "eco-render-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let rendered = render_in_dir(path, &dir, opts);
+    if let Err(err) = fs::remove_dir_all(&dir) {
+        warn!(
+            "couldn't remove the temporary directory {}: {err}",
+            dir.display()
+        );
+    }
+
+    rendered
+}
+
+fn render_in_dir(
+    path: &Path,
+    dir: &Path,
+    opts: &RenderOptions,
+) -> Result<(Vec<Image>, Vec<Chapter>)> {
+    // The chapters are rendered from the disk, so their stylesheets, images, and fonts are resolved by the browser
+    ZipArchive::new(File::open(path)?)?.extract(dir)?;
+    let mut doc = EpubDoc::new(path)?;
+    let titles = toc_titles(&doc.toc);
+    let pdfium = Pdfium::new(Pdfium::bind_to_system_library()?);
+    let style = page_style(opts);
+
+    let mut imgs = Vec::new();
+    let mut chapters = Vec::new();
+    for page in 0..doc.get_num_pages() {
+        doc.set_current_page(page);
+        let Some(page_path) = doc.get_current_path() else {
+            continue;
+        };
+        // Epub paths are always separated by slashes, whatever the platform
+        let page_path = page_path.to_string_lossy().replace('\\', "/");
+        // The path comes from the epub, it must not escape the directory, like `ZipFile::enclosed_name` checks
+        if !Path::new(&page_path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            warn!("page {page_path} is outside of the epub");
+            continue;
+        }
+        let Some(bytes) = doc.get_resource_by_path(&page_path) else {
+            warn!("page {page_path} not found");
+            continue;
+        };
+
+        // Written next to the original page so the relative links still work
+        let html_path = dir.join(format!("{page_path}.eco-render.xhtml"));
+        let pdf_path = dir.join(format!("{page_path}.eco-render.pdf"));
+        fs::write(
+            &html_path,
+            inject_in_head(&String::from_utf8_lossy(&bytes), &style),
+        )?;
+        print_to_pdf(&opts.browser, &html_path, &pdf_path)?;
+
+        let document = pdfium.load_pdf_from_file(&pdf_path, None)?;
+        if let Some(title) = titles
+            .iter()
+            .find_map(|(path, title)| (*path == page_path).then_some(title))
+        {
+            chapters.push(Chapter {
+                title: title.clone(),
+                page: imgs.len(),
+            });
+        }
+        #[allow(clippy::cast_possible_wrap)]
+        let config = PdfRenderConfig::new().set_target_width(opts.page_size.width as i32);
+        for pdf_page in document.pages().iter() {
+            imgs.push(Image::from(
+                pdf_page.render_with_config(&config)?.as_image(),
+            ));
+        }
+        debug!("{page_path} rendered, {} pages so far", imgs.len());
+    }
+
+    Ok((imgs, chapters))
+}
+
+/// The page size is set by the stylesheet, the browser uses it for the printed pdf
+fn page_style(opts: &RenderOptions) -> String {
+    let PageSize { width, height } = opts.page_size;
+    let max_height = height.saturating_sub(opts.margin * 2);
+    format!(
+        "<style>@page {{ size: {width}px {height}px; margin: {}px; }} \
+         img, svg {{ max-width: 100%; max-height: {max_height}px; }}</style>",
+        opts.margin
+    )
+}
+
+fn print_to_pdf(browser: &str, html_path: &Path, pdf_path: &Path) -> Result<()> {
+    debug!("printing {} with {browser}", html_path.display());
+    let output = match Command::new(browser)
+        .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+        .arg(format!("--print-to-pdf={}", pdf_path.display()))
+        .arg(html_path)
+        .output()
+    {
+        Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Err(Error::Render(format!("{browser} not found")))
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if !output.status.success() || !pdf_path.exists() {
+        return Err(Error::Render(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The chapter titles by page path, only the first entry of each page is kept
+fn toc_titles(nav_points: &[NavPoint]) -> Vec<(String, String)> {
+    let mut titles: Vec<(String, String)> = Vec::new();
+    for nav_point in nav_points {
+        let content = nav_point.content.to_string_lossy().replace('\\', "/");
+        let path = content.split('#').next().unwrap_or_default().to_string();
+        if !titles.iter().any(|(other, _)| *other == path) {
+            titles.push((path, nav_point.label.clone()));
+        }
+        for title in toc_titles(&nav_point.children) {
+            if !titles.iter().any(|(other, _)| *other == title.0) {
+                titles.push(title);
+            }
+        }
+    }
+
+    titles
+}
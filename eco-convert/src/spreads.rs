@@ -0,0 +1,81 @@
+use eco_cbz::image::{Image, ReadingOrder};
+use image::{GenericImageView, Pixel};
+use tracing::debug;
+
+/// Maximum mean difference of luminosity between the 2 sides of the seam
+const MAX_SEAM_DIFFERENCE: f32 = 16.0;
+
+/// Minimum standard deviation of luminosity along the seam, blank margins match each other but are not spreads
+const MIN_SEAM_DEVIATION: f32 = 20.0;
+
+/// Joins the spreads stored as 2 halves on consecutive pages, the images are passed one at a time
+#[derive(Debug)]
+pub struct SpreadJoiner {
+    reading_order: ReadingOrder,
+    pending: Option<Image>,
+}
+
+impl SpreadJoiner {
+    pub fn new(reading_order: ReadingOrder) -> Self {
+        Self {
+            reading_order,
+            pending: None,
+        }
+    }
+
+    /// Returns the image that can be emitted, if any: either the joined spread, or the previous image
+    pub fn push(&mut self, img: Image) -> Option<Image> {
+        let Some(pending) = self.pending.take() else {
+            self.pending = Some(img);
+            return None;
+        };
+        if is_spread(&pending, &img, self.reading_order) {
+            debug!("joining spread halves");
+            return Some(Image::join(pending, img, self.reading_order));
+        }
+        self.pending = Some(img);
+        Some(pending)
+    }
+
+    /// Returns the last image, if it hasn't been emitted yet
+    pub fn finish(self) -> Option<Image> {
+        self.pending
+    }
+}
+
+/// The halves of a spread are portrait images of the same height, and the art continues across the seam
+fn is_spread(first: &Image, second: &Image, reading_order: ReadingOrder) -> bool {
+    let (left, right) = match reading_order {
+        ReadingOrder::Ltr => (first.dynamic(), second.dynamic()),
+        ReadingOrder::Rtl => (second.dynamic(), first.dynamic()),
+    };
+    if !first.is_portrait() || !second.is_portrait() || left.height() != right.height() {
+        return false;
+    }
+
+    let luma = |image: &image::DynamicImage, x, y| f32::from(image.get_pixel(x, y).to_luma()[0]);
+    let left_seam = (0..left.height())
+        .map(|y| luma(left, left.width() - 1, y))
+        .collect::<Vec<_>>();
+    let right_seam = (0..right.height())
+        .map(|y| luma(right, 0, y))
+        .collect::<Vec<_>>();
+
+    #[allow(clippy::cast_precision_loss)]
+    let height = left_seam.len() as f32;
+    let difference = left_seam
+        .iter()
+        .zip(&right_seam)
+        .map(|(left, right)| (left - right).abs())
+        .sum::<f32>()
+        / height;
+    let mean = left_seam.iter().sum::<f32>() / height;
+    let deviation = (left_seam
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f32>()
+        / height)
+        .sqrt();
+
+    difference <= MAX_SEAM_DIFFERENCE && deviation >= MIN_SEAM_DEVIATION
+}
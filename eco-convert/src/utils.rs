@@ -1,3 +1,16 @@
+use eco_cbz::image::Image;
+use tracing::error;
+
+/// Keeps the decoded images, the other ones are logged and dropped
+pub fn decoded(imgs: Vec<eco_cbz::Result<Image>>) -> Vec<Image> {
+    imgs.into_iter()
+        .filter_map(|img| {
+            img.map_err(|err| error!("failed to decode image: {err}"))
+                .ok()
+        })
+        .collect()
+}
+
 static BASE_32_SCALELST: [u64; 8] = [
     1,
     32,
@@ -0,0 +1,58 @@
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// Default name of the catalog, written at the root of the library
+pub static CATALOG_FILE_NAME: &str = "eco-library.json";
+
+/// The archives of a library, with their metadata, page counts, and hashes
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Catalog {
+    /// The scanned directory, the archives paths are relative to it
+    pub root: Utf8PathBuf,
+    pub archives: Vec<CatalogEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    pub path: Utf8PathBuf,
+    /// Size of the archive in bytes
+    pub size: u64,
+    /// Hex encoded SHA-256 of the whole archive
+    pub sha256: String,
+    pub pages: usize,
+    /// Hex encoded SHA-256 of each page, in reading order
+    pub page_hashes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<u16>,
+    /// The cover thumbnail, relative to the catalog directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover: Option<Utf8PathBuf>,
+}
+
+impl Catalog {
+    /// ## Errors
+    ///
+    /// Fails if the file can't be read or if it's not a valid catalog
+    pub fn try_from_path(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let content = fs::read(path.as_ref())?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the file can't be written
+    pub fn write_to_path(&self, path: impl AsRef<Utf8Path>) -> Result<()> {
+        fs::write(path.as_ref(), serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use camino::Utf8PathBuf;
+use serde::Serialize;
+
+use crate::{Catalog, CatalogEntry, Error, Result};
+
+/// The pages found in more archives than this, like blank pages or credits, aren't used to find similar archives,
+/// as every pair of archives containing them would be compared
+const MAX_ARCHIVES_PER_PAGE: usize = 32;
+
+/// How much the archives of a `Duplicate` have in common
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateKind {
+    /// The archives are identical byte for byte
+    Identical,
+    /// The archives contain the same pages in the same order, their names or metadata differ
+    SamePages,
+    /// The archives share most of their pages
+    Similar,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Duplicate {
+    pub kind: DuplicateKind,
+    /// The paths of the archives, relative to the library root
+    pub archives: Vec<Utf8PathBuf>,
+    /// Shared pages over all the distinct pages of the archives, between 0 and 1
+    pub similarity: f32,
+}
+
+/// Finds the archives with the same content, even when they're named differently.
+/// The archives sharing at least `min_similarity` of their pages are reported as similar, in pairs.
+///
+/// ## Errors
+///
+/// Fails if `min_similarity` isn't between 0 and 1
+pub fn find_duplicates(catalog: &Catalog, min_similarity: f32) -> Result<Vec<Duplicate>> {
+    if !(0.0..=1.0).contains(&min_similarity) {
+        return Err(Error::InvalidSimilarity(min_similarity));
+    }
+    let mut duplicates = Vec::new();
+
+    // Only one archive per group is compared to the others in the next steps
+    let mut identical: HashMap<&str, Vec<&CatalogEntry>> = HashMap::new();
+    for entry in &catalog.archives {
+        identical.entry(&entry.sha256).or_default().push(entry);
+    }
+    let mut same_pages: HashMap<&[String], Vec<&CatalogEntry>> = HashMap::new();
+    for group in identical.into_values() {
+        if group.len() > 1 {
+            duplicates.push(duplicate(DuplicateKind::Identical, &group, 1.0));
+        }
+        let first = group[0];
+        same_pages
+            .entry(first.page_hashes.as_slice())
+            .or_default()
+            .push(first);
+    }
+    let mut representatives = Vec::new();
+    for group in same_pages.into_values() {
+        if group.len() > 1 {
+            duplicates.push(duplicate(DuplicateKind::SamePages, &group, 1.0));
+        }
+        representatives.push(group[0]);
+    }
+
+    let pages = representatives
+        .iter()
+        .map(|entry| entry.page_hashes.iter().map(String::as_str).collect())
+        .collect::<Vec<HashSet<&str>>>();
+    // Only the archives sharing at least a page are compared
+    let mut archives_by_page: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, hashes) in pages.iter().enumerate() {
+        for hash in hashes {
+            archives_by_page.entry(hash).or_default().push(index);
+        }
+    }
+    let mut shared: HashMap<(usize, usize), usize> = HashMap::new();
+    for archives in archives_by_page.values() {
+        if archives.len() > MAX_ARCHIVES_PER_PAGE {
+            continue;
+        }
+        for (position, &left) in archives.iter().enumerate() {
+            for &right in &archives[position + 1..] {
+                *shared.entry((left, right)).or_default() += 1;
+            }
+        }
+    }
+    for ((left, right), count) in shared {
+        let union = pages[left].len() + pages[right].len() - count;
+        #[allow(clippy::cast_precision_loss)]
+        let similarity = count as f32 / union as f32;
+        if similarity >= min_similarity {
+            duplicates.push(duplicate(
+                DuplicateKind::Similar,
+                &[representatives[left], representatives[right]],
+                similarity,
+            ));
+        }
+    }
+
+    duplicates.sort_by(|duplicate, other| {
+        duplicate
+            .kind
+            .cmp(&other.kind)
+            .then_with(|| other.similarity.total_cmp(&duplicate.similarity))
+            .then_with(|| duplicate.archives.cmp(&other.archives))
+    });
+    Ok(duplicates)
+}
+
+fn duplicate(kind: DuplicateKind, entries: &[&CatalogEntry], similarity: f32) -> Duplicate {
+    let mut archives = entries
+        .iter()
+        .map(|entry| entry.path.clone())
+        .collect::<Vec<_>>();
+    archives.sort();
+    Duplicate {
+        kind,
+        archives,
+        similarity,
+    }
+}
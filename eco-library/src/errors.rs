@@ -0,0 +1,22 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cbz error {0}")]
+    Cbz(#[from] eco_cbz::Error),
+
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("json error {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("glob error {0}")]
+    Glob(#[from] glob::GlobError),
+
+    #[error("glob pattern error {0}")]
+    GlobPattern(#[from] glob::PatternError),
+
+    #[error("invalid similarity {0}, expected a value between 0 and 1")]
+    InvalidSimilarity(f32),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
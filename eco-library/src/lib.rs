@@ -0,0 +1,133 @@
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::fs::{self, File};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{
+    manifest::{sha256, sha256_from_reader},
+    CbzReader, UnofficialCbzMetadata,
+};
+use glob::{glob, Pattern};
+use tracing::{debug, error};
+
+pub use crate::catalog::{Catalog, CatalogEntry, CATALOG_FILE_NAME};
+pub use crate::dupes::{find_duplicates, Duplicate, DuplicateKind};
+pub use crate::errors::{Error, Result};
+
+mod catalog;
+mod dupes;
+pub mod errors;
+
+/// Name of the directory holding the cover thumbnails, next to the catalog
+pub static THUMBNAILS_DIR_NAME: &str = "eco-thumbnails";
+
+#[derive(Debug)]
+pub struct IndexOptions {
+    /// The root of the library, its sub directories are walked too
+    pub dir: Utf8PathBuf,
+
+    /// Path to the written catalog, defaults to `eco-library.json` in `dir`
+    pub output: Option<Utf8PathBuf>,
+
+    /// Maximum width and height of the cover thumbnails, no thumbnails are generated when `None`
+    pub thumbnail_size: Option<u32>,
+}
+
+/// Scans the library and writes its catalog, the archives that can't be read are skipped.
+/// Returns the path of the written catalog.
+#[allow(clippy::missing_errors_doc)]
+pub fn index(opts: &IndexOptions) -> Result<Utf8PathBuf> {
+    let output = opts
+        .output
+        .clone()
+        .unwrap_or_else(|| opts.dir.join(CATALOG_FILE_NAME));
+    let thumbnails_dir = output
+        .parent()
+        .unwrap_or(Utf8Path::new("."))
+        .join(THUMBNAILS_DIR_NAME);
+    if opts.thumbnail_size.is_some() {
+        fs::create_dir_all(&thumbnails_dir)?;
+    }
+
+    let mut catalog = Catalog {
+        root: opts.dir.clone(),
+        archives: Vec::new(),
+    };
+    // The directory name may contain glob special characters, like `[` or `*`
+    let dir = Pattern::escape(opts.dir.as_str());
+    for path in glob(Utf8Path::new(&dir).join("**").join("*.cbz").as_str())? {
+        let path = path?;
+        let Ok(path) = Utf8PathBuf::from_path_buf(path) else {
+            error!("skipping non utf-8 path");
+            continue;
+        };
+        debug!("indexing {path}");
+        let thumbnail = opts
+            .thumbnail_size
+            .map(|size| (thumbnails_dir.as_path(), size));
+        match index_archive(&opts.dir, &path, thumbnail) {
+            Ok(entry) => catalog.archives.push(entry),
+            Err(err) => error!("{path} couldn't be indexed: {err}"),
+        }
+    }
+    catalog.write_to_path(&output)?;
+
+    Ok(output)
+}
+
+fn index_archive(
+    root: &Utf8Path,
+    path: &Utf8Path,
+    thumbnail: Option<(&Utf8Path, u32)>,
+) -> Result<CatalogEntry> {
+    let size = path.metadata()?.len();
+    let sha256_hex = sha256_from_reader(File::open(path)?)?;
+
+    let mut reader = CbzReader::try_from_path(path)?;
+    let mut page_hashes = Vec::with_capacity(reader.len());
+    for name in reader.file_names() {
+        page_hashes.push(sha256(&reader.read_bytes_by_name(&name)?));
+    }
+
+    let mut entry = CatalogEntry {
+        path: path.strip_prefix(root).unwrap_or(path).to_path_buf(),
+        size,
+        sha256: sha256_hex,
+        pages: reader.len(),
+        page_hashes,
+        title: None,
+        series: None,
+        number: None,
+        volume: None,
+        cover: None,
+    };
+    // ComicInfo.xml is the most complete format, the ComicBookInfo comment is the fallback
+    if let Ok(Some(comic_info)) = reader.comic_info() {
+        entry.title = comic_info.title;
+        entry.series = comic_info.series;
+        entry.number = comic_info.number;
+        entry.volume = comic_info.volume;
+    } else if let Ok(UnofficialCbzMetadata {
+        info: Some(info), ..
+    }) = reader.metadata::<UnofficialCbzMetadata>()
+    {
+        entry.title = info.title;
+        entry.series = info.series;
+        entry.number = info.issue.map(|issue| issue.to_string());
+        entry.volume = info.volume;
+    }
+
+    if let (Some((thumbnails_dir, size)), false) = (thumbnail, reader.is_empty()) {
+        // The archive hash makes the name unique, and stable across the runs
+        let name = format!("{}.png", entry.sha256);
+        reader
+            .read_by_index(0)?
+            .dynamic()
+            .thumbnail(size, size)
+            .save(thumbnails_dir.join(&name))
+            .map_err(eco_cbz::Error::from)?;
+        entry.cover = Some(Utf8Path::new(THUMBNAILS_DIR_NAME).join(name));
+    }
+
+    Ok(entry)
+}
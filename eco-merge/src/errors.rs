@@ -3,6 +3,21 @@ pub enum Error {
     #[error("cbz error {0}")]
     Cbz(#[from] eco_cbz::Error),
 
+    #[error("cancelled")]
+    Cancelled,
+
+    #[error("no images to merge")]
+    NoImages,
+
+    #[error("invalid size: {0}, expected something like 300MB")]
+    InvalidSize(String),
+
+    #[error("invalid split: {0}, expected a size like 300MB or an amount of pages like 500pages")]
+    InvalidSplit(String),
+
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
     #[error("glob error {0}")]
     Glob(#[from] glob::GlobError),
 
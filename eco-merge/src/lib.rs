@@ -1,48 +1,704 @@
 #![deny(clippy::all, clippy::pedantic)]
 
-use camino::Utf8PathBuf;
-use eco_cbz::{CbzReader, CbzWriter};
+use std::{
+    fs::{self, File},
+    io::{Cursor, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Mutex, PoisonError,
+    },
+    thread,
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{
+    comic_info::COMIC_INFO_FILE_NAME, image::Image, sort::cmp_paths, Bookmark, CancellationToken,
+    CbzReader, CbzWriter, ComicInfo, Compression, Event, Failure, Measure, NoProgress, Progress,
+    Report, SkippedPage, Stage, TitlePage,
+};
 use glob::glob;
-use tracing::warn;
+use tracing::{debug, error, error_span, warn};
 
 pub use crate::errors::{Error, Result};
+pub use crate::size::{Size, Split};
 
 pub mod errors;
+mod size;
+
+/// Amount of encoded pages of an archive buffered before its reader waits for the writer
+const PAGES_BUFFER: usize = 16;
 
 #[derive(Debug)]
 pub struct MergeOptions {
-    /// A glob that matches all the archive to merge
-    pub archives_glob: String,
+    /// A glob that matches all the archive to merge, the matches are merged after the `archives`
+    pub archives_glob: Option<String>,
+
+    /// The archives to merge, in this order
+    pub archives: Vec<Utf8PathBuf>,
 
     /// The output directory for the merged archive
     pub outdir: Utf8PathBuf,
 
     /// The merged archive name
     pub name: String,
+
+    /// Keeps the original pages names instead of renaming them with a counter
+    pub preserve_names: bool,
+
+    /// What to do with the metadata files (e.g. `ComicInfo.xml`) found in the merged archives
+    pub sidecars: SidecarPolicy,
+
+    /// Splits the output in several archives of at most this size or amount of pages,
+    /// the merged archives aren't split unless they're bigger.
+    /// The parts are limited to `eco_cbz::cbz::MAX_FILE_NUMBER` pages unless a bigger amount is provided.
+    pub split: Option<Split>,
+
+    /// Password of the protected archives
+    pub password: Option<String>,
+
+    /// Embeds a manifest with the checksum and dimensions of each page
+    pub manifest: bool,
+
+    /// How the pages are compressed in the merged archive
+    pub compression: Compression,
+
+    /// An image inserted as the first page
+    pub cover_file: Option<Utf8PathBuf>,
+
+    /// Inserts a generated title page at the start of each merged archive, its name is used as the chapter
+    pub title_page: Option<TitlePage>,
+
+    /// Order in which the archives matched by the glob are merged
+    pub sort: SortOrder,
+
+    /// Amount of archives read in parallel, defaults to the amount of cores
+    pub jobs: Option<usize>,
+
+    /// The archives that can't be read are left out and reported, instead of aborting the merge
+    pub keep_going: bool,
+
+    /// Aborts the merge, the parts already written are deleted
+    pub cancel: CancellationToken,
+}
+
+/// Order of the merged archives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// The numbers are compared by value, so `vol2.cbz` comes before `vol10.cbz`
+    #[default]
+    Natural,
+    /// The paths are compared character by character, so `vol10.cbz` comes before `vol2.cbz`
+    Lexical,
 }
 
+/// Policy applied to the metadata files (see `eco_cbz::SIDECAR_EXTENSIONS`) of the merged archives
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SidecarPolicy {
+    /// The metadata files are dropped
+    Skip,
+    /// When several archives contain a file with the same name, the first one is kept
+    #[default]
+    KeepFirst,
+    /// When several archives contain a file with the same name, the last one is kept
+    KeepLast,
+    /// All the files are kept, the conflicting names are suffixed with a number
+    KeepAll,
+}
+
+/// Returns the report of the merge, with the paths of the merged archives, there are several of them when the output is split.
+/// The failures are the archives left out in keep going mode.
+#[allow(clippy::missing_errors_doc)]
+pub fn merge(opts: MergeOptions) -> Result<Report> {
+    merge_with_progress(opts, &NoProgress)
+}
+
+/// Same as `merge`, the progress is reported to `progress`, the archives are read in parallel
+/// so their `FileStarted` events can come in any order
 #[allow(clippy::missing_errors_doc, clippy::needless_pass_by_value)]
-pub fn merge(opts: MergeOptions) -> Result<()> {
-    let mut merged_cbz_writer = CbzWriter::default();
-
-    for path in glob(&opts.archives_glob)? {
-        let mut current_cbz = CbzReader::try_from_path(path?)?;
-
-        current_cbz.try_for_each(|image| {
-            let image = match image {
-                Ok(image) => image,
-                Err(err) => {
-                    warn!("not a valid image: {err}");
-                    return Ok::<(), Error>(());
-                }
-            };
-            merged_cbz_writer.insert(image)?;
+pub fn merge_with_progress(opts: MergeOptions, progress: &dyn Progress) -> Result<Report> {
+    let mut paths = opts
+        .archives
+        .iter()
+        .map(|path| path.as_std_path().to_path_buf())
+        .collect::<Vec<_>>();
+    if let Some(archives_glob) = &opts.archives_glob {
+        let mut matches = glob(archives_glob)?.collect::<Result<Vec<_>, _>>()?;
+        // The glob matches are already sorted lexically
+        if opts.sort == SortOrder::Natural {
+            matches.sort_by(|path, other| {
+                cmp_paths(&path.to_string_lossy(), &other.to_string_lossy())
+            });
+        }
+        paths.extend(matches);
+    }
+    let mut failures = Vec::new();
+    let mut parts = Parts::new(&opts, progress);
+    if let Err(err) =
+        merge_into(&mut parts, &paths, &opts, progress, &mut failures).and_then(|()| parts.finish())
+    {
+        // Don't leave the parts of an incomplete merge behind
+        parts.discard();
+        return Err(err);
+    }
 
-            Ok::<(), Error>(())
-        })?;
+    let written_pages = parts.pages;
+    let extracted_pages = parts.extracted_pages;
+    let skipped_pages = std::mem::take(&mut parts.skipped_pages);
+    let outputs = parts.outputs();
+    let mut size = 0;
+    for output in &outputs {
+        size += fs::metadata(output)?.len();
     }
 
-    merged_cbz_writer.write_to_path(opts.outdir.join(format!("{}.cbz", opts.name)))?;
+    Ok(Report {
+        outputs,
+        extracted_pages,
+        written_pages,
+        skipped_pages,
+        failures,
+        size,
+    })
+}
+
+fn record_failure(failures: &mut Vec<Failure>, path: &Path, err: &Error) {
+    error!("{} couldn't be merged: {err}", path.display());
+    failures.push(Failure {
+        input: path.display().to_string(),
+        error: err.to_string(),
+    });
+}
+
+/// Inserts the cover and the pages of the archives in the parts, writing each part once it's full.
+/// The archives that can't be read are recorded in `failures` in keep going mode.
+fn merge_into(
+    parts: &mut Parts<'_>,
+    paths: &[PathBuf],
+    opts: &MergeOptions,
+    progress: &dyn Progress,
+    failures: &mut Vec<Failure>,
+) -> Result<()> {
+    if let Some(cover_file) = &opts.cover_file {
+        let cover = Page::encode(Image::open(cover_file)?, String::new(), progress)?;
+        parts.reserve_cover(cover);
+    }
+
+    let jobs = opts
+        .jobs
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(1)
+        .max(1);
+    // The readers take the archives in order, and queue the receivers of their pages so the writer,
+    // the current thread, gets them in the same order. At most `jobs` archives are read ahead.
+    let paths = Mutex::new(paths.iter());
+    thread::scope(|scope| {
+        let (archives_sender, archives) = mpsc::sync_channel(jobs);
+        for _ in 0..jobs {
+            let archives_sender = archives_sender.clone();
+            let paths = &paths;
+            scope.spawn(move || loop {
+                let (path, sender) = {
+                    let mut paths = paths.lock().unwrap_or_else(PoisonError::into_inner);
+                    let Some(path) = paths.next() else {
+                        return;
+                    };
+                    let (sender, messages) = mpsc::sync_channel(PAGES_BUFFER);
+                    // The writer stopped
+                    if archives_sender.send((path, messages)).is_err() {
+                        return;
+                    }
+                    (path, sender)
+                };
+                read_archive(path, opts, progress, &sender);
+            });
+        }
+        drop(archives_sender);
+
+        for (path, messages) in archives {
+            // Only the archives that can't be read are skipped, the parts can't be written anymore after a write error
+            match insert_chapter(parts, &messages, opts, progress)? {
+                None => {}
+                Some(Error::Cancelled) => return Err(Error::Cancelled),
+                Some(err) if opts.keep_going => record_failure(failures, path, &err),
+                Some(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// What the reader of an archive sends to the writer
+enum Message {
+    /// Sent first, once the archive is open
+    Chapter(Chapter),
+    /// Sent right before the first page
+    TitlePage(Page),
+    Page(Page),
+    /// Sent instead of the pages that can't be read or encoded
+    Skipped(SkippedPage),
+    /// Sent instead of the chapter when the archive can't be read, or when the merge is cancelled
+    Failed(Error),
+}
+
+/// An archive being read, the pages follow
+struct Chapter {
+    name: String,
+    pages: usize,
+    /// Sum of the compressed sizes of the pages in the archive, to guess if they fit in the current part
+    size_hint: u64,
+    sidecars: Vec<(String, Vec<u8>)>,
+}
+
+/// A page encoded as it'll be written, so the size of the parts is known before they're written
+struct Page {
+    name: String,
+    bytes: Vec<u8>,
+    extension: &'static str,
+}
+
+impl Page {
+    fn encode(image: Image, name: String, progress: &dyn Progress) -> Result<Self> {
+        let _encode = Measure::with_progress(Stage::Encode, progress);
+        // Same format as the one the image is encoded in
+        let extension = image
+            .format()
+            .and_then(|format| format.extensions_str().first().copied())
+            .unwrap_or("png");
+        let bytes = image.try_into_bytes()?;
+
+        Ok(Self {
+            name,
+            bytes,
+            extension,
+        })
+    }
+
+    fn size(&self) -> u64 {
+        self.bytes.len() as u64
+    }
+}
+
+/// Sends the pages of the archive to the writer as they're decoded and encoded again,
+/// the invalid images are skipped. The sidecars are read as well, so each archive is only opened once.
+fn read_archive(
+    path: &Path,
+    opts: &MergeOptions,
+    progress: &dyn Progress,
+    sender: &SyncSender<Message>,
+) {
+    // The archives are read by their own thread, which doesn't inherit the span of the caller
+    let _span = error_span!("file", operation = "merge", file = %path.display()).entered();
+    if let Err(err) = send_pages(path, opts, progress, sender) {
+        // The writer may have stopped already
+        let _ = sender.send(Message::Failed(err));
+    }
+}
+
+fn send_pages(
+    path: &Path,
+    opts: &MergeOptions,
+    progress: &dyn Progress,
+    sender: &SyncSender<Message>,
+) -> Result<()> {
+    debug!("reading {}", path.display());
+    progress.on_event(Event::FileStarted(path.to_path_buf()));
+    let mut current_cbz = open(path, opts)?;
+    let mut sidecars = Vec::new();
+    if opts.sidecars != SidecarPolicy::Skip {
+        for name in current_cbz.sidecars() {
+            let bytes = current_cbz.read_bytes_by_name(&name)?;
+            sidecars.push((name, bytes));
+        }
+    }
+    let names = current_cbz.file_names();
+    let size_hint = names
+        .iter()
+        .map(|name| {
+            current_cbz
+                .raw_read_by_name(name)
+                .map_or(0, |file| file.compressed_size())
+        })
+        .sum();
+    let chapter = path.file_stem().map_or_else(
+        || path.display().to_string(),
+        |stem| stem.to_string_lossy().to_string(),
+    );
+    let mut title_page = opts.title_page.as_ref();
+    // The writer stopped
+    if sender
+        .send(Message::Chapter(Chapter {
+            name: chapter.clone(),
+            pages: names.len() + usize::from(title_page.is_some()),
+            size_hint,
+            sidecars,
+        }))
+        .is_err()
+    {
+        return Ok(());
+    }
+    for (index, name) in names.into_iter().enumerate() {
+        if opts.cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let _span = error_span!("page", page = index + 1, entry = %name).entered();
+        let decode = Measure::with_progress(Stage::Decode, progress);
+        let read = current_cbz.read_by_name(&name);
+        drop(decode);
+        let skip = |reason: String| {
+            Message::Skipped(SkippedPage {
+                page: index + 1,
+                reason: format!("{reason} in {}", path.display()),
+            })
+        };
+        let image = match read {
+            Ok(image) => image,
+            Err(err) => {
+                warn!("not a valid image: {err}");
+                progress.on_event(Event::Warning(format!(
+                    "{name} of {} is not a valid image: {err}",
+                    path.display()
+                )));
+                if sender
+                    .send(skip(format!("not a valid image ({err})")))
+                    .is_err()
+                {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        if let Some(template) = title_page.take() {
+            let (width, height) = TitlePage::size_of(&image);
+            // Named after the first page when the names are preserved, so it's sorted right before it
+            let first_name = Utf8Path::new(&name);
+            let title_name = first_name
+                .with_file_name(format!(
+                    "{}-title.png",
+                    first_name.file_stem().unwrap_or("page")
+                ))
+                .into_string();
+            let rendered = template.render(Some(&chapter), width, height)?;
+            let title_page = Page::encode(rendered, title_name, progress)?;
+            if sender.send(Message::TitlePage(title_page)).is_err() {
+                return Ok(());
+            }
+        }
+        let page = match Page::encode(image, name, progress) {
+            Ok(page) => page,
+            Err(err) => {
+                warn!("the page couldn't be encoded: {err}");
+                if sender
+                    .send(skip(format!("couldn't be encoded ({err})")))
+                    .is_err()
+                {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        if sender.send(Message::Page(page)).is_err() {
+            return Ok(());
+        }
+    }
 
     Ok(())
 }
+
+/// Each archive is a chapter, it starts a new part rather than being split when it seems to fit in a part.
+/// The chapter is bookmarked on its first page, and again at the start of the next part when it's split.
+/// Returns the error of the reader when the archive can't be read, the errors of the parts are returned as is.
+fn insert_chapter(
+    parts: &mut Parts<'_>,
+    messages: &Receiver<Message>,
+    opts: &MergeOptions,
+    progress: &dyn Progress,
+) -> Result<Option<Error>> {
+    // The reader only stops without a message when it panicked, which is propagated by the scope
+    let chapter = match messages.recv() {
+        Ok(Message::Chapter(chapter)) => chapter,
+        Ok(Message::Failed(err)) => return Ok(Some(err)),
+        Ok(Message::TitlePage(_) | Message::Page(_) | Message::Skipped(_)) | Err(_) => {
+            return Ok(None)
+        }
+    };
+    parts.add_sidecars(chapter.sidecars);
+    if !parts.fits(chapter.pages, chapter.size_hint) {
+        parts.next_part()?;
+    }
+    let mut bookmarked = false;
+    for message in messages {
+        let (page, is_title_page) = match message {
+            Message::TitlePage(page) => (page, true),
+            Message::Page(page) => (page, false),
+            Message::Skipped(skipped_page) => {
+                parts.skipped_pages.push(skipped_page);
+                continue;
+            }
+            Message::Failed(err) => return Ok(Some(err)),
+            Message::Chapter(_) => continue,
+        };
+        if opts.cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        if !parts.fits(1, page.size()) {
+            parts.next_part()?;
+            bookmarked = false;
+        }
+        if !bookmarked {
+            parts.bookmark(&chapter.name);
+            bookmarked = true;
+        }
+        parts.insert(&page, opts.preserve_names.then_some(page.name.as_str()))?;
+        if !is_title_page {
+            parts.extracted_pages += 1;
+            progress.on_event(Event::PageProcessed {
+                page: parts.pages,
+                total: None,
+            });
+        }
+    }
+
+    Ok(None)
+}
+
+/// A page name sorted before `name`, or before the counter based names without it.
+/// A `0` component is sorted before any other one starting with zeros, like `000.jpg` or `00 - credits.jpg`.
+fn name_before(name: Option<&str>, extension: &str) -> String {
+    let candidate = format!("0.{extension}");
+    let Some(name) = name.filter(|name| cmp_paths(&candidate, name).is_ge()) else {
+        return candidate;
+    };
+    match name.split_once('/') {
+        Some(("0", rest)) => format!("0/{}", name_before(Some(rest), extension)),
+        _ => format!("0/{candidate}"),
+    }
+}
+
+fn open(path: &Path, opts: &MergeOptions) -> Result<CbzReader<File>> {
+    let mut cbz = CbzReader::try_from_path(path)?;
+    if let Some(password) = &opts.password {
+        cbz = cbz.with_password(password.as_str());
+    }
+    Ok(cbz)
+}
+
+/// The merged archives, a new one is started when the current one is full
+struct Parts<'a> {
+    opts: &'a MergeOptions,
+    progress: &'a dyn Progress,
+    writer: CbzWriter<Cursor<Vec<u8>>>,
+    /// Sum of the encoded pages sizes in the current part
+    size: u64,
+    /// The parts already written, with their bookmarks
+    written: Vec<(Utf8PathBuf, Vec<Bookmark>)>,
+    /// Amount of pages in all the parts, including the cover
+    pages: usize,
+    /// Amount of pages of the merged archives in all the parts, without the cover and the title pages
+    extracted_pages: usize,
+    /// The pages of the merged archives that couldn't be read or encoded, numbered in their archive
+    skipped_pages: Vec<SkippedPage>,
+    /// The chapters starting in the current part, written as `ComicInfo.xml` bookmarks
+    bookmarks: Vec<Bookmark>,
+    /// The sidecars of the archives read so far, kept according to the `SidecarPolicy`
+    sidecars: Vec<(String, Vec<u8>)>,
+    /// The cover, inserted once the first part is full so it's named to be sorted before its pages
+    cover: Option<Page>,
+    /// The preserved name of the first part sorted first
+    first_name: Option<String>,
+}
+
+impl<'a> Parts<'a> {
+    fn new(opts: &'a MergeOptions, progress: &'a dyn Progress) -> Self {
+        Self {
+            opts,
+            progress,
+            writer: Self::writer(opts),
+            size: 0,
+            written: Vec::new(),
+            pages: 0,
+            extracted_pages: 0,
+            skipped_pages: Vec::new(),
+            bookmarks: Vec::new(),
+            sidecars: Vec::new(),
+            cover: None,
+            first_name: None,
+        }
+    }
+
+    fn writer(opts: &MergeOptions) -> CbzWriter<Cursor<Vec<u8>>> {
+        let mut writer = CbzWriter::default().with_compression(opts.compression);
+        if let Some(Split::Pages(max_pages)) = opts.split {
+            writer = writer.with_max_file_number(max_pages);
+        }
+        if opts.manifest {
+            writer = writer.with_manifest();
+        }
+        writer
+    }
+
+    /// An empty part accepts anything, so the pages bigger than the limit are still merged
+    fn fits(&self, pages: usize, size: u64) -> bool {
+        let size_fits = match self.opts.split {
+            Some(Split::Size(max)) => self.size + size <= max.bytes(),
+            // The writer is limited to the amount of pages of the split
+            Some(Split::Pages(_)) | None => true,
+        };
+        self.writer.is_empty() || (self.len() + pages <= self.writer.max_file_number() && size_fits)
+    }
+
+    /// Amount of pages in the current part, including the cover to insert
+    fn len(&self) -> usize {
+        self.writer.len() + usize::from(self.cover.is_some())
+    }
+
+    /// The cover is counted in the first part, but only inserted by `insert_cover`
+    fn reserve_cover(&mut self, cover: Page) {
+        self.size += cover.size();
+        self.pages += 1;
+        self.cover = Some(cover);
+    }
+
+    fn insert_cover(&mut self) -> Result<()> {
+        if let Some(cover) = self.cover.take() {
+            let name = name_before(self.first_name.as_deref(), cover.extension);
+            self.writer.insert_raw_with_name(&cover.bytes, &name)?;
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, page: &Page, name: Option<&str>) -> Result<()> {
+        match name {
+            Some(name) => {
+                if self.cover.is_some()
+                    && self
+                        .first_name
+                        .as_ref()
+                        .map_or(true, |first_name| cmp_paths(name, first_name).is_lt())
+                {
+                    self.first_name = Some(name.to_string());
+                }
+                self.writer.insert_raw_with_name(&page.bytes, name)?;
+            }
+            None => self
+                .writer
+                .insert_raw_with_extension(&page.bytes, page.extension)?,
+        }
+        self.size += page.size();
+        self.pages += 1;
+        Ok(())
+    }
+
+    fn add_sidecars(&mut self, sidecars: Vec<(String, Vec<u8>)>) {
+        for (name, bytes) in sidecars {
+            let existing = self
+                .sidecars
+                .iter()
+                .position(|(existing_name, _)| *existing_name == name);
+            match (self.opts.sidecars, existing) {
+                (SidecarPolicy::KeepFirst, Some(_)) => {
+                    debug!("skipping conflicting {name}");
+                }
+                (SidecarPolicy::KeepLast, Some(index)) => self.sidecars[index] = (name, bytes),
+                _ => self.sidecars.push((name, bytes)),
+            }
+        }
+    }
+
+    /// Bookmarks the next page of the current part as the start of the chapter
+    fn bookmark(&mut self, chapter: &str) {
+        self.bookmarks.push(Bookmark {
+            page: self.len(),
+            title: chapter.to_string(),
+        });
+    }
+
+    /// Writes the current part, and starts a new one.
+    /// The sidecars are added by `finish`, once the ones of all the archives are known.
+    fn next_part(&mut self) -> Result<()> {
+        self.insert_cover()?;
+        let writer = std::mem::replace(&mut self.writer, Self::writer(self.opts));
+        let bookmarks = std::mem::take(&mut self.bookmarks);
+        self.size = 0;
+        let output = self.output(self.written.len() + 1);
+        let _write = Measure::with_progress(Stage::Write, self.progress);
+        writer.write_to_path(&output)?;
+        self.written.push((output, bookmarks));
+        Ok(())
+    }
+
+    /// Writes the last part, and appends the sidecars to the previous ones
+    fn finish(&mut self) -> Result<()> {
+        let has_cover = self.opts.cover_file.is_some();
+        if self.pages <= usize::from(has_cover) {
+            return Err(Error::NoImages);
+        }
+        self.insert_cover()?;
+        let mut writer = std::mem::replace(&mut self.writer, Self::writer(self.opts));
+        let bookmarks = std::mem::take(&mut self.bookmarks);
+        // The output is only suffixed when it's actually split
+        let output = if self.written.is_empty() {
+            self.opts.outdir.join(format!("{}.cbz", self.opts.name))
+        } else {
+            self.output(self.written.len() + 1)
+        };
+        let _write = Measure::with_progress(Stage::Write, self.progress);
+        for (output, bookmarks) in &self.written {
+            let mut writer = CbzWriter::append_to_path(output)?;
+            self.add_metadata(&mut writer, bookmarks.clone())?;
+            writer.finish()?;
+        }
+        self.add_metadata(&mut writer, bookmarks)?;
+        writer.write_to_path(&output)?;
+        self.written.push((output, Vec::new()));
+        Ok(())
+    }
+
+    fn outputs(self) -> Vec<Utf8PathBuf> {
+        self.written.into_iter().map(|(output, _)| output).collect()
+    }
+
+    /// Deletes the parts already written
+    fn discard(self) {
+        for output in self.outputs() {
+            if let Err(err) = fs::remove_file(&output) {
+                warn!("{output} couldn't be deleted: {err}");
+            }
+        }
+    }
+
+    fn output(&self, part: usize) -> Utf8PathBuf {
+        self.opts
+            .outdir
+            .join(format!("{}_part{part}.cbz", self.opts.name))
+    }
+
+    /// The bookmarks replace the pages of the `ComicInfo.xml` carried over, which is created if needed
+    fn add_metadata<W>(&self, writer: &mut CbzWriter<W>, bookmarks: Vec<Bookmark>) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        let mut bookmarks = Some(bookmarks).filter(|bookmarks| !bookmarks.is_empty());
+        for (name, bytes) in &self.sidecars {
+            if name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME) {
+                match ComicInfo::try_from_xml(&String::from_utf8_lossy(bytes)) {
+                    Ok(mut comic_info) => {
+                        // The pages of the archive it comes from don't match the merged ones
+                        comic_info.pages = None;
+                        comic_info.set_bookmarks(bookmarks.take().unwrap_or_default());
+                        writer.set_comic_info(&comic_info)?;
+                        continue;
+                    }
+                    Err(err) => warn!("the chapters couldn't be bookmarked in {name}: {err}"),
+                }
+            }
+            writer.insert_sidecar(name, bytes)?;
+        }
+        if let Some(bookmarks) = bookmarks {
+            let mut comic_info = ComicInfo::default();
+            comic_info.set_bookmarks(bookmarks);
+            writer.set_comic_info(&comic_info)?;
+        }
+        Ok(())
+    }
+}
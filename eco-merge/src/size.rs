@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use crate::{Error, Result};
+
+/// A size in bytes, parsed from strings like `300MB`, `1.5G`, or `4096`, the units are powers of 1024
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Size(u64);
+
+impl Size {
+    #[must_use]
+    pub fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl FromStr for Size {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSize(s.to_string());
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        let value = value.parse::<f64>().map_err(|_| invalid())?;
+        let multiplier: u32 = match unit.trim().to_ascii_uppercase().as_str() {
+            "" | "B" => 1,
+            "K" | "KB" | "KIB" => 1 << 10,
+            "M" | "MB" | "MIB" => 1 << 20,
+            "G" | "GB" | "GIB" => 1 << 30,
+            _ => return Err(invalid()),
+        };
+        let bytes = value * f64::from(multiplier);
+        if !bytes.is_finite() || bytes < 1.0 {
+            return Err(invalid());
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(Self(bytes as u64))
+    }
+}
+
+/// Where the merged archive is split, parsed from sizes like `300MB` or amounts of pages like `500pages`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Split {
+    /// Parts of at most this size
+    Size(Size),
+    /// Parts of at most this amount of pages, the parts bigger than 65535 pages are written in the ZIP64 format
+    Pages(usize),
+}
+
+impl FromStr for Split {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidSplit(s.to_string());
+        let lowercase = s.trim().to_ascii_lowercase();
+        let Some(pages) = ["pages", "page", "p"]
+            .iter()
+            .find_map(|suffix| lowercase.strip_suffix(suffix))
+        else {
+            return s.parse().map(Self::Size).map_err(|_| invalid());
+        };
+        match pages.trim().parse() {
+            Ok(0) | Err(_) => Err(invalid()),
+            Ok(pages) => Ok(Self::Pages(pages)),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use std::{path::Path, process::Command};
+
+use tempfile::{Builder, NamedTempFile};
+use tracing::debug;
+
+use crate::{Error, Result};
+
+/// An external program and its arguments, split like a shell would, so the quoted arguments can contain spaces.
+/// The placeholders of the arguments are replaced with the paths of the temporary files when the command is run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalCommand {
+    program: String,
+    args: Vec<String>,
+}
+
+impl ExternalCommand {
+    #[must_use]
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the command is empty, or if its quotes aren't balanced
+    pub fn parse(command: &str) -> Result<Self> {
+        let mut words = shell_words::split(command)
+            .map_err(|err| Error::Command(format!("{command}: {err}")))?
+            .into_iter();
+        let Some(program) = words.next() else {
+            return Err(Error::Command("the command is empty".to_string()));
+        };
+
+        Ok(Self {
+            program,
+            args: words.collect(),
+        })
+    }
+
+    #[must_use]
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn contains(&self, placeholder: &str) -> bool {
+        self.args.iter().any(|arg| arg.contains(placeholder))
+    }
+
+    /// Runs the command once its placeholders are replaced, and returns its standard output
+    ///
+    /// ## Errors
+    ///
+    /// Fails with an `io::ErrorKind::NotFound` error if the program isn't installed,
+    /// or with `Error::Command` and its standard error if it exits with an error
+    pub fn run(&self, placeholders: &[(&str, &Path)]) -> Result<Vec<u8>> {
+        let args = self
+            .args
+            .iter()
+            .map(|arg| {
+                placeholders
+                    .iter()
+                    .fold(arg.clone(), |arg, (placeholder, path)| {
+                        arg.replace(placeholder, &path.to_string_lossy())
+                    })
+            })
+            .collect::<Vec<_>>();
+        debug!("running {} {}", self.program, shell_words::join(&args));
+        let output = Command::new(&self.program).args(&args).output()?;
+        if !output.status.success() {
+            return Err(Error::Command(format!(
+                "{} exited with {}: {}",
+                self.program,
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+/// An empty temporary file with a unique name, removed once dropped
+///
+/// ## Errors
+///
+/// Fails if the file can't be created
+pub fn temp_file(extension: &str) -> Result<NamedTempFile> {
+    Ok(Builder::new()
+        .prefix("eco-")
+        .suffix(&format!(".{extension}"))
+        .tempfile()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_arguments_are_kept_together() {
+        let command = ExternalCommand::parse(r#"waifu2x -i "{in}" -o '/my dir/{out}'"#).unwrap();
+
+        assert_eq!(
+            command,
+            ExternalCommand::new("waifu2x")
+                .arg("-i")
+                .arg("{in}")
+                .arg("-o")
+                .arg("/my dir/{out}")
+        );
+        assert!(ExternalCommand::parse("waifu2x \"{in}").is_err());
+        assert!(ExternalCommand::parse("  ").is_err());
+    }
+}
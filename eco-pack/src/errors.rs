@@ -3,6 +3,24 @@ pub enum Error {
     #[error("error: {0}")]
     Generic(String),
 
+    #[error("invalid page ranges: {0}, expected something like 1-50,60,70-")]
+    InvalidPageRanges(String),
+
+    #[error("invalid page operations: {0}")]
+    InvalidPageOps(String),
+
+    #[error("cancelled")]
+    Cancelled,
+
+    #[error("no images to pack")]
+    NoImages,
+
+    #[error("external command error: {0}")]
+    Command(String),
+
+    #[error("download error: {0}")]
+    Download(String),
+
     #[error("glob error: {0}")]
     Glob(#[from] glob::GlobError),
 
@@ -12,8 +30,17 @@ pub enum Error {
     #[error("cbz error: {0}")]
     Cbz(#[from] eco_cbz::Error),
 
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
+
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("yaml error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,52 @@
+use std::str::FromStr;
+
+use eco_cbz::image::Image;
+
+use crate::{
+    command::{temp_file, ExternalCommand},
+    Error, Result,
+};
+
+/// An external command transforming each image, like `waifu2x -i {in} -o {out}`.
+/// `{in}` is replaced with the path to the image, and `{out}` with the path the command must write the result to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FilterCommand(ExternalCommand);
+
+impl FilterCommand {
+    /// Runs the command on the image, both the input and the output are temporary png files.
+    /// The result keeps the format of the original image.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the image can't be saved, if the command can't be run or exits with an error,
+    /// or if its output can't be read
+    pub fn apply(&self, img: &Image) -> Result<Image> {
+        let input = temp_file("png")?;
+        let output = temp_file("png")?;
+        img.dynamic().save(input.path())?;
+        self.0
+            .run(&[("{in}", input.path()), ("{out}", output.path())])?;
+
+        let mut filtered = Image::open(output.path())?;
+        if let Some(format) = img.format() {
+            filtered.set_format(format);
+        }
+
+        Ok(filtered)
+    }
+}
+
+impl FromStr for FilterCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let command = ExternalCommand::parse(s)?;
+        if !command.contains("{in}") || !command.contains("{out}") {
+            return Err(Error::Command(format!(
+                "{s} must contain both the {{in}} and {{out}} placeholders"
+            )));
+        }
+
+        Ok(Self(command))
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use eco_cbz::image::{Image, ReadingOrder};
+use tracing::debug;
+
+use crate::{
+    command::{temp_file, ExternalCommand},
+    Error, Result,
+};
+
+/// Set once `jpegtran` is found missing, so it's not looked for again on every page
+static MISSING: AtomicBool = AtomicBool::new(false);
+
+/// Splits the untransformed jpeg in 2 pages without decoding and encoding it again, using `jpegtran`.
+/// The split is moved to the closest MCU boundary, so the halves can differ by a few pixels.
+/// Returns `None` when the image isn't an untransformed jpeg, or when `jpegtran` isn't installed.
+///
+/// ## Errors
+///
+/// Fails if the temporary files can't be written or read, or if `jpegtran` exits with an error
+pub fn split(img: &Image, reading_order: ReadingOrder) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some(bytes) = img.jpeg_bytes() else {
+        return Ok(None);
+    };
+    let (width, height) = (img.dynamic().width(), img.dynamic().height());
+    let Some(mcu_width) = mcu_width(bytes) else {
+        debug!("the jpeg frame header can't be read");
+        return Ok(None);
+    };
+    if width < mcu_width * 2 || MISSING.load(Ordering::Relaxed) {
+        return Ok(None);
+    }
+    let middle = (width / 2 + mcu_width / 2) / mcu_width * mcu_width;
+
+    let input = temp_file("jpg")?;
+    fs::write(input.path(), bytes)?;
+    let Some(left) = crop(input.path(), middle, height, 0)? else {
+        return Ok(None);
+    };
+    let Some(right) = crop(input.path(), width - middle, height, middle)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(match reading_order {
+        ReadingOrder::Ltr => (left, right),
+        ReadingOrder::Rtl => (right, left),
+    }))
+}
+
+/// The jpegs can only be cropped losslessly on a multiple of the MCU width,
+/// which is 8 pixels times the largest horizontal sampling factor of the components (e.g. 16 for 4:2:0, 32 for 4:1:1)
+fn mcu_width(jpeg: &[u8]) -> Option<u32> {
+    // The segments following the start of image marker, until the frame header
+    let mut offset = 2;
+    loop {
+        if *jpeg.get(offset)? != 0xff {
+            return None;
+        }
+        let marker = *jpeg.get(offset + 1)?;
+        // The start of frame markers, except the huffman (c4), extension (c8), and arithmetic coding (cc) ones
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let components = usize::from(*jpeg.get(offset + 9)?);
+            let max_sampling = (0..components)
+                .map(|component| {
+                    jpeg.get(offset + 11 + component * 3)
+                        .map(|sampling| sampling >> 4)
+                })
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .max()?;
+            return Some(8 * u32::from(max_sampling.max(1)));
+        }
+        let length = u16::from_be_bytes([*jpeg.get(offset + 2)?, *jpeg.get(offset + 3)?]);
+        offset += 2 + usize::from(length);
+    }
+}
+
+/// Returns `None` if `jpegtran` isn't installed
+fn crop(input: &Path, width: u32, height: u32, x: u32) -> Result<Option<Vec<u8>>> {
+    let command = ExternalCommand::new("jpegtran")
+        .arg("-copy")
+        .arg("all")
+        .arg("-crop")
+        .arg(format!("{width}x{height}+{x}+0"))
+        .arg("{in}");
+    match command.run(&[("{in}", input)]) {
+        Ok(output) => Ok(Some(output)),
+        Err(Error::Io(err)) if err.kind() == ErrorKind::NotFound => {
+            debug!("jpegtran not found, the jpegs are split after being decoded");
+            MISSING.store(true, Ordering::Relaxed);
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A start of image marker, an empty comment segment, and a frame header with 3 components
+    fn jpeg_header(luma_sampling: u8) -> Vec<u8> {
+        let mut jpeg = vec![0xff, 0xd8, 0xff, 0xfe, 0x00, 0x02];
+        jpeg.extend([0xff, 0xc0, 0x00, 0x11, 0x08, 0x00, 0x10, 0x00, 0x40, 0x03]);
+        jpeg.extend([
+            0x01,
+            luma_sampling,
+            0x00,
+            0x02,
+            0x11,
+            0x01,
+            0x03,
+            0x11,
+            0x01,
+        ]);
+        jpeg
+    }
+
+    #[test]
+    fn mcu_width_follows_the_subsampling() {
+        assert_eq!(mcu_width(&jpeg_header(0x11)), Some(8));
+        assert_eq!(mcu_width(&jpeg_header(0x22)), Some(16));
+        assert_eq!(mcu_width(&jpeg_header(0x41)), Some(32));
+        assert_eq!(mcu_width(&[0xff, 0xd8, 0x00]), None);
+    }
+}
@@ -1,76 +1,241 @@
 #![deny(clippy::all, clippy::pedantic)]
 
-use std::{env, fs::create_dir_all, io::Cursor};
+use std::{
+    env,
+    fs::{self, create_dir_all},
+    io::{Cursor, Seek, Write},
+};
 
 use camino::{Utf8Path, Utf8PathBuf};
 use eco_cbz::{
-    image::{Image, ReadingOrder},
-    CbzWriter,
+    image::Image, CancellationToken, CbzWriter, Event, Failure, Measure, NoProgress, Progress,
+    Report, SkippedPage, Stage,
 };
 use glob::glob;
-use tracing::{debug, error};
+use tracing::{debug, error, error_span};
 
 pub use crate::errors::{Error, Result};
+pub use crate::filter::FilterCommand;
+pub use crate::ocr::{Ocr, OcrCommand, OcrPage, OcrText, OCR_FILE_NAME};
+pub use crate::page_ops::{Crop, PageOp, PageOps};
+pub use crate::page_ranges::PageRanges;
+pub use crate::remote::{download_images, parse_urls, DownloadOptions};
+pub use crate::repack::{repack, repack_with_progress, RepackOptions};
+pub use crate::transform::{Resize, Transform};
+pub use crate::watermark::{Corner, Watermark};
+pub use eco_cbz::text::{TitleBackground, TitlePage};
 
+mod command;
 pub mod errors;
+mod filter;
+mod jpegtran;
+mod ocr;
+mod page_ops;
+mod page_ranges;
+mod remote;
+mod repack;
+mod transform;
+mod watermark;
 
+/// The matched files are sorted alphabetically, only the selected ones (if any) are read, and decoded by `transform`.
+/// When `failures` is provided, the images that can't be read are recorded there and left out.
+///
 /// ## Errors
 ///
 /// Fails when the glob is invalid, the paths are not utf-8, or the image can't be read and decoded
-pub fn get_images_from_glob(glob_expr: impl AsRef<str>) -> Result<Vec<Image>> {
+pub fn get_images_from_glob(
+    glob_expr: impl AsRef<str>,
+    pages: Option<&PageRanges>,
+    transform: &Transform,
+    mut failures: Option<&mut Vec<Failure>>,
+) -> Result<Vec<Image>> {
     let paths = glob(glob_expr.as_ref())?;
     let mut imgs = Vec::new();
 
-    for path in paths {
+    for (index, path) in paths.enumerate() {
+        if pages.is_some_and(|pages| !pages.contains(index + 1)) {
+            continue;
+        }
         let path = path?;
         let Some(path) = Utf8Path::from_path(&path) else {
             error!("{path:?} is not a valid utf-8 path");
             continue;
         };
-        imgs.push(Image::open(path)?);
+        let _span = error_span!("file", operation = "read", file = %path).entered();
+        let img = fs::read(path)
+            .map_err(eco_cbz::Error::from)
+            .and_then(|bytes| transform.decode(bytes));
+        match (img, failures.as_deref_mut()) {
+            (Ok(img), _) => imgs.push(img),
+            (Err(err), Some(failures)) => {
+                error!("{path} couldn't be read: {err}");
+                failures.push(Failure {
+                    input: path.to_string(),
+                    error: err.to_string(),
+                });
+            }
+            (Err(err), None) => return Err(err.into()),
+        }
     }
 
     Ok(imgs)
 }
 
-#[allow(clippy::missing_errors_doc, clippy::missing_panics_doc)]
+/// Transforms the images and inserts them after the pages already in the writer, e.g. a title page
+#[allow(clippy::missing_errors_doc)]
 pub fn pack_imgs_to_cbz(
+    mut cbz_writer: CbzWriter<Cursor<Vec<u8>>>,
     imgs: Vec<Image>,
-    contrast: Option<f32>,
-    brightness: Option<i32>,
-    blur: Option<f32>,
-    autosplit: bool,
-    reading_order: ReadingOrder,
+    transform: &Transform,
+    mut ocr: Option<Ocr>,
+    progress: &dyn Progress,
+    cancel: &CancellationToken,
 ) -> Result<CbzWriter<Cursor<Vec<u8>>>> {
-    let mut cbz_writer = CbzWriter::default();
-    for mut img in imgs {
-        if let Some(contrast) = contrast {
-            img = img.set_contrast(contrast);
-        }
-        if let Some(brightness) = brightness {
-            img = img.set_brightness(brightness);
+    let total = imgs.len();
+    for (index, img) in imgs.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            return Err(Error::Cancelled);
         }
-        if let Some(blur) = blur {
-            img = img.set_blur(blur);
+        let _span = error_span!("page", page = index + 1).entered();
+        insert_img(&mut cbz_writer, img, transform, ocr.as_mut(), progress)?;
+        progress.on_event(Event::PageProcessed {
+            page: index + 1,
+            total: Some(total),
+        });
+    }
+    if let Some(ocr) = ocr {
+        ocr.write_to(&mut cbz_writer)?;
+    }
+
+    Ok(cbz_writer)
+}
+
+/// Transforms a single image and inserts it, landscape images are inserted as 2 pages when `autosplit` is set.
+/// The pages are resized after the split, so each one fits in the `resize` bounds.
+/// The colors are converted to sRGB first when `srgb` is set, the `icc` feature is enabled, and the image embeds an ICC profile.
+/// The text of the inserted pages is recognized when `ocr` is provided, before the watermark is rendered.
+///
+/// ## Errors
+///
+/// Fails if the image can't be encoded or inserted, if its color profile is invalid, or if its text can't be recognized
+pub fn insert_img<W>(
+    cbz_writer: &mut CbzWriter<W>,
+    mut img: Image,
+    transform: &Transform,
+    mut ocr: Option<&mut Ocr>,
+    progress: &dyn Progress,
+) -> Result<()>
+where
+    W: Write + Seek,
+{
+    let Transform {
+        srgb,
+        ref filter,
+        contrast,
+        brightness,
+        blur,
+        denoise,
+        descreen,
+        autosplit,
+        resize,
+        reading_order,
+        ref watermark,
+        ..
+    } = *transform;
+    let measure = Measure::with_progress(Stage::Transform, progress);
+    if srgb {
+        #[cfg(feature = "icc")]
+        {
+            img = img.into_srgb()?;
         }
+        #[cfg(not(feature = "icc"))]
+        tracing::warn!("the colors can't be converted to sRGB without the icc feature");
+    }
+    if let Some(filter) = filter {
+        img = filter.apply(&img)?;
+    }
+    if let Some(contrast) = contrast {
+        img = img.set_contrast(contrast);
+    }
+    if let Some(brightness) = brightness {
+        img = img.set_brightness(brightness);
+    }
+    if let Some(blur) = blur {
+        img = img.set_blur(blur);
+    }
+    if let Some(denoise) = denoise {
+        img = img.denoise(denoise);
+    }
+    if descreen {
+        img = img.descreen();
+    }
 
-        if img.is_landscape() && autosplit {
-            debug!("splitting landscape file");
-            let (img_left, img_right) = img.autosplit(reading_order);
-            cbz_writer.insert(img_left)?;
-            cbz_writer.insert(img_right)?;
-        } else {
-            cbz_writer.insert(img)?;
+    if img.is_landscape() && autosplit {
+        // The untransformed jpegs are split losslessly, unless the pages are encoded again anyway
+        let lossless = transform.splits_losslessly() && cbz_writer.page_format().is_none();
+        if let Some((first, second)) = lossless
+            .then(|| jpegtran::split(&img, reading_order))
+            .transpose()?
+            .flatten()
+        {
+            debug!("splitting landscape jpeg losslessly");
+            drop(measure);
+            for bytes in [first, second] {
+                if let Some(ocr) = ocr.as_deref_mut() {
+                    ocr.recognize(&Image::try_from_bytes(&bytes)?, cbz_writer.len() + 1)?;
+                }
+                let _encode = Measure::with_progress(Stage::Encode, progress);
+                cbz_writer.insert_raw_with_extension(&bytes, "jpg")?;
+            }
+            return Ok(());
         }
     }
+    let pages = if img.is_landscape() && autosplit {
+        debug!("splitting landscape file");
+        let (img_left, img_right) = img.autosplit(reading_order);
+        vec![img_left, img_right]
+    } else {
+        vec![img]
+    };
+    let pages = match resize {
+        Some(resize) => pages
+            .into_iter()
+            .map(|img| img.resize_to_fit(resize.max_width, resize.max_height, resize.resampling))
+            .collect(),
+        None => pages,
+    };
+    drop(measure);
+    for mut img in pages {
+        let page = cbz_writer.len() + 1;
+        if let Some(ocr) = ocr.as_deref_mut() {
+            ocr.recognize(&img, page)?;
+        }
+        if let Some(watermark) = watermark {
+            let _transform = Measure::with_progress(Stage::Transform, progress);
+            img = watermark.apply(img, page);
+        }
+        let _encode = Measure::with_progress(Stage::Encode, progress);
+        cbz_writer.insert(img)?;
+    }
 
-    Ok(cbz_writer)
+    Ok(())
 }
 
+/// Where the packed images come from
 #[derive(Debug)]
-pub struct PackOptions {
+pub enum Source {
     /// A glob that matches all the files to pack
-    pub files_descriptor: String,
+    Glob(String),
+    /// The images are downloaded from these urls
+    Urls {
+        urls: Vec<String>,
+        download: DownloadOptions,
+    },
+}
+
+#[derive(Debug)]
+pub struct PackOptions {
+    pub source: Source,
 
     /// The output directory for the merged archive
     pub outdir: Utf8PathBuf,
@@ -78,24 +243,44 @@ pub struct PackOptions {
     /// The merged archive name
     pub name: String,
 
-    /// Adjust images contrast
-    pub contrast: Option<f32>,
+    /// The transformations applied to the images, and how the pages are encoded
+    pub transform: Transform,
+
+    /// Only packs the selected files
+    pub pages: Option<PageRanges>,
+
+    /// Amount of files dropped at the start, after the selection (e.g. the credits pages)
+    pub skip_first: usize,
 
-    /// Adjust images brightness
-    pub brightness: Option<i32>,
+    /// Amount of files dropped at the end, after the selection (e.g. the ads)
+    pub skip_last: usize,
 
-    /// Blur image (slow with big numbers)
-    pub blur: Option<f32>,
+    /// Path to a yaml or json manifest listing the operations to apply to some pages only, see `PageOps`
+    pub manifest: Option<Utf8PathBuf>,
 
-    /// Automatically split landscape images into 2 pages
-    pub autosplit: bool,
+    /// Stores the text of each page, recognized by this command, in an `eco-ocr.json` sidecar
+    pub ocr: Option<OcrCommand>,
 
-    /// Reading order
-    pub reading_order: ReadingOrder,
+    /// Inserts a generated title page at the start, the archive name is used as the chapter
+    pub title_page: Option<TitlePage>,
+
+    /// The images that can't be read are left out and reported, instead of aborting the packing
+    pub keep_going: bool,
+
+    /// Aborts the packing, nothing is written once it's cancelled
+    pub cancel: CancellationToken,
+}
+
+/// Returns the report of the packing, with the path of the packed archive
+#[allow(clippy::missing_errors_doc)]
+pub fn pack(opts: PackOptions) -> Result<Report> {
+    pack_with_progress(opts, &NoProgress)
 }
 
+/// Same as `pack`, the progress is reported to `progress`, a page is processed once it's transformed and inserted
 #[allow(clippy::missing_errors_doc)]
-pub fn pack(opts: PackOptions) -> Result<()> {
+pub fn pack_with_progress(opts: PackOptions, progress: &dyn Progress) -> Result<Report> {
+    let _span = error_span!("file", operation = "pack", file = %opts.name).entered();
     let Ok(current_dir) = Utf8PathBuf::from_path_buf(env::current_dir()?) else {
         return Err(Error::Generic(
             "current dir is not a valid utf8 path".to_string(),
@@ -105,18 +290,86 @@ pub fn pack(opts: PackOptions) -> Result<()> {
     if !outdir.exists() {
         create_dir_all(&*outdir)?;
     }
-    let imgs = get_images_from_glob(opts.files_descriptor)?;
+    let mut failures = Vec::new();
+    let decode = Measure::with_progress(Stage::Decode, progress);
+    let mut imgs = match &opts.source {
+        Source::Urls { urls, download } => download_images(
+            urls,
+            opts.pages.as_ref(),
+            download,
+            &opts.transform,
+            opts.keep_going.then_some(&mut failures),
+        )?,
+        Source::Glob(files_descriptor) => get_images_from_glob(
+            files_descriptor,
+            opts.pages.as_ref(),
+            &opts.transform,
+            opts.keep_going.then_some(&mut failures),
+        )?,
+    };
+    drop(decode);
+    let extracted_pages = imgs.len();
+    let mut skipped_pages = Vec::new();
+    let mut skip = |pages: Vec<usize>, reason: &str| {
+        skipped_pages.extend(pages.into_iter().map(|page| SkippedPage {
+            page,
+            reason: reason.to_string(),
+        }));
+    };
+    // The pages of the remaining images, so the skipped ones can be reported
+    let mut pages = (1..=imgs.len()).collect::<Vec<_>>();
+    if let Some(manifest) = &opts.manifest {
+        let page_ops = PageOps::try_from_path(manifest)?;
+        let (kept, skipped) = pages
+            .into_iter()
+            .partition(|page| !page_ops.is_skipped(*page));
+        skip(skipped, "skipped by the page operations manifest");
+        pages = kept;
+        imgs = page_ops.apply(imgs);
+    }
+    let kept = pages.len().saturating_sub(opts.skip_last);
+    skip(pages.drain(kept..).collect(), "skipped at the end");
+    imgs.truncate(kept);
+    let skipped = opts.skip_first.min(pages.len());
+    skip(pages.drain(..skipped).collect(), "skipped at the start");
+    imgs.drain(..skipped);
+    skipped_pages.sort_by_key(|skipped_page| skipped_page.page);
+    if imgs.is_empty() {
+        return Err(Error::NoImages);
+    }
+    let mut cbz_writer = opts.transform.configure(CbzWriter::default());
+    // The title page is generated, so it doesn't go through the transformations
+    if let Some(title_page) = &opts.title_page {
+        let (width, height) = TitlePage::size_of(&imgs[0]);
+        cbz_writer.insert(title_page.render(Some(&opts.name), width, height)?)?;
+    }
 
     let cbz_writer = pack_imgs_to_cbz(
+        cbz_writer,
         imgs,
-        opts.contrast,
-        opts.brightness,
-        opts.blur,
-        opts.autosplit,
-        opts.reading_order,
+        &opts.transform,
+        opts.ocr.map(Ocr::new),
+        progress,
+        &opts.cancel,
     )?;
+    // The archive is only written at the end, there is no partial output to delete
+    if opts.cancel.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
 
-    cbz_writer.write_to_path(outdir.join(format!("{}.cbz", opts.name)))?;
+    let written_pages = cbz_writer.len();
+    let output = outdir.join(format!("{}.cbz", opts.name));
+    let write = Measure::with_progress(Stage::Write, progress);
+    cbz_writer.write_to_path(&output)?;
+    drop(write);
+    let size = fs::metadata(&output)?.len();
 
-    Ok(())
+    Ok(Report {
+        outputs: vec![output],
+        extracted_pages,
+        written_pages,
+        skipped_pages,
+        failures,
+        size,
+    })
 }
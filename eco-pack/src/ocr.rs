@@ -0,0 +1,105 @@
+use std::{
+    io::{Seek, Write},
+    str::FromStr,
+};
+
+use eco_cbz::{image::Image, CbzWriter};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    command::{temp_file, ExternalCommand},
+    Error, Result,
+};
+
+/// Name of the entry holding the recognized text
+pub static OCR_FILE_NAME: &str = "eco-ocr.json";
+
+/// An external OCR command like `tesseract {} -`, `{}` is replaced with the path to the page,
+/// which is appended to the arguments when there's no placeholder. The text is read from its standard output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OcrCommand(ExternalCommand);
+
+impl OcrCommand {
+    /// Runs the command on the image, saved as a temporary png file
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the image can't be saved, or if the command can't be run or exits with an error
+    pub fn run(&self, img: &Image) -> Result<String> {
+        let input = temp_file("png")?;
+        img.dynamic().save(input.path())?;
+        let output = self.0.run(&[("{}", input.path())])?;
+
+        Ok(String::from_utf8_lossy(&output).trim().to_string())
+    }
+}
+
+impl FromStr for OcrCommand {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let command = ExternalCommand::parse(s)?;
+        if command.contains("{}") {
+            Ok(Self(command))
+        } else {
+            Ok(Self(command.arg("{}")))
+        }
+    }
+}
+
+/// The text of each page, stored as a json sidecar so the archives can be searched
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrText {
+    pub pages: Vec<OcrPage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OcrPage {
+    /// The page number, starting at 1
+    pub page: usize,
+    pub text: String,
+}
+
+/// Runs the OCR command on the pages as they're inserted, see `insert_img`
+#[derive(Debug)]
+pub struct Ocr {
+    command: OcrCommand,
+    text: OcrText,
+}
+
+impl Ocr {
+    #[must_use]
+    pub fn new(command: OcrCommand) -> Self {
+        Self {
+            command,
+            text: OcrText::default(),
+        }
+    }
+
+    /// ## Errors
+    ///
+    /// Same behavior as `OcrCommand::run`
+    pub fn recognize(&mut self, img: &Image, page: usize) -> Result<()> {
+        debug!("recognizing the text of page {page}");
+        let text = self.command.run(img)?;
+        self.text.pages.push(OcrPage { page, text });
+
+        Ok(())
+    }
+
+    /// Inserts the recognized text in the archive
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the sidecar can't be serialized or inserted
+    pub fn write_to<W>(self, cbz_writer: &mut CbzWriter<W>) -> Result<()>
+    where
+        W: Write + Seek,
+    {
+        let bytes = serde_json::to_vec_pretty(&self.text)?;
+        cbz_writer.insert_sidecar(OCR_FILE_NAME, &bytes)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+
+use camino::Utf8Path;
+use eco_cbz::image::Image;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::{Error, PageRanges, Result};
+
+/// Operations applied to some pages only, on top of the global transformations, e.g. in yaml:
+///
+/// ```yaml
+/// - pages: 12
+///   rotate: 90
+/// - pages: "30-35"
+///   crop: { x: 0, y: 120, width: 1200, height: 1700 }
+/// - pages: "99"
+///   skip: true
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct PageOps(Vec<PageOp>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PageOp {
+    /// The pages the operations apply to, numbered from 1 after the `--pages` selection
+    pub pages: PageRanges,
+    /// Clockwise rotation, in degrees: 90, 180, or 270
+    pub rotate: Option<u16>,
+    pub crop: Option<Crop>,
+    /// The pages are not packed
+    #[serde(default)]
+    pub skip: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PageOps {
+    /// Reads the operations from a yaml or json file
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the file can't be read or parsed, or if an operation is invalid
+    pub fn try_from_path(path: impl AsRef<Utf8Path>) -> Result<Self> {
+        let content = fs::read_to_string(path.as_ref())?;
+        // Json is a subset of yaml
+        let ops: Self = serde_yaml::from_str(&content)?;
+        for op in &ops.0 {
+            if op
+                .rotate
+                .is_some_and(|rotate| ![90, 180, 270].contains(&rotate))
+            {
+                return Err(Error::InvalidPageOps(format!(
+                    "rotation must be 90, 180, or 270, got {}",
+                    op.rotate.unwrap_or_default()
+                )));
+            }
+        }
+
+        Ok(ops)
+    }
+
+    /// Whether the page is dropped by one of the operations, the first image is the page 1
+    #[must_use]
+    pub fn is_skipped(&self, page: usize) -> bool {
+        self.0.iter().any(|op| op.skip && op.pages.contains(page))
+    }
+
+    /// Applies the operations to the images, the first image is the page 1
+    #[must_use]
+    pub fn apply(&self, imgs: Vec<Image>) -> Vec<Image> {
+        imgs.into_iter()
+            .enumerate()
+            .filter_map(|(index, mut img)| {
+                let page = index + 1;
+                for op in self.0.iter().filter(|op| op.pages.contains(page)) {
+                    if op.skip {
+                        debug!("skipping page {page}");
+                        return None;
+                    }
+                    if let Some(crop) = op.crop {
+                        img = img.crop(crop.x, crop.y, crop.width, crop.height);
+                    }
+                    if let Some(rotate) = op.rotate {
+                        img = img.rotate(rotate);
+                    }
+                }
+                Some(img)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,84 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{Error, Result};
+
+/// A selection of pages like `1-50,60,70-`, the page numbers start at 1 and the bounds are inclusive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRanges(Vec<(usize, Option<usize>)>);
+
+impl PageRanges {
+    /// Checks if the page number (starting at 1) is selected
+    #[must_use]
+    pub fn contains(&self, page: usize) -> bool {
+        self.0
+            .iter()
+            .any(|(start, end)| page >= *start && end.map_or(true, |end| page <= end))
+    }
+
+    /// Keeps the selected items only, the first item is the page 1
+    #[must_use]
+    pub fn select<T>(&self, items: Vec<T>) -> Vec<T> {
+        items
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| self.contains(index + 1))
+            .map(|(_, item)| item)
+            .collect()
+    }
+}
+
+impl FromStr for PageRanges {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidPageRanges(s.to_string());
+        let parse_page = |page: &str| match page.trim().parse::<usize>() {
+            Ok(page) if page > 0 => Ok(page),
+            _ => Err(invalid()),
+        };
+
+        let mut ranges = Vec::new();
+        for range in s.split(',') {
+            let range = match range.split_once('-') {
+                Some((start, end)) if end.trim().is_empty() => (parse_page(start)?, None),
+                Some((start, end)) => {
+                    let (start, end) = (parse_page(start)?, parse_page(end)?);
+                    if start > end {
+                        return Err(invalid());
+                    }
+                    (start, Some(end))
+                }
+                None => {
+                    let page = parse_page(range)?;
+                    (page, Some(page))
+                }
+            };
+            ranges.push(range);
+        }
+
+        Ok(Self(ranges))
+    }
+}
+
+impl<'de> Deserialize<'de> for PageRanges {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // A single page can be written as a number
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Page(usize),
+            Ranges(String),
+        }
+
+        let ranges = match Raw::deserialize(deserializer)? {
+            Raw::Page(page) => page.to_string(),
+            Raw::Ranges(ranges) => ranges,
+        };
+        ranges.parse().map_err(serde::de::Error::custom)
+    }
+}
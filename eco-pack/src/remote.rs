@@ -0,0 +1,155 @@
+use std::{sync::Arc, time::Duration};
+
+use eco_cbz::{image::Image, Failure};
+use futures::{stream, StreamExt};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use tokio::{sync::Mutex, time::Instant};
+use tracing::{debug, error, error_span, Instrument};
+
+use crate::{Error, PageRanges, Result, Transform};
+
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Amount of images downloaded at the same time
+    pub concurrency: usize,
+
+    /// Amount of retries of the requests failing with a transient error (timeouts, 5xx, 429...)
+    pub retries: u32,
+
+    /// Maximum amount of requests started per second, unlimited when `None`
+    pub rate_limit: Option<u32>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            retries: 3,
+            rate_limit: None,
+        }
+    }
+}
+
+/// Downloads the images concurrently, they're returned in the order of the urls.
+/// Only the selected ones (if any) are downloaded.
+/// When `failures` is provided, the images that can't be downloaded or decoded are recorded there and left out.
+///
+/// ## Errors
+///
+/// Fails if the async runtime can't be started, or if an image can't be downloaded or decoded
+pub fn download_images(
+    urls: &[String],
+    pages: Option<&PageRanges>,
+    opts: &DownloadOptions,
+    transform: &Transform,
+    mut failures: Option<&mut Vec<Failure>>,
+) -> Result<Vec<Image>> {
+    let urls = urls
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !pages.is_some_and(|pages| !pages.contains(index + 1)))
+        .map(|(_, url)| url.clone())
+        .collect::<Vec<_>>();
+    let client = ClientBuilder::new(reqwest::Client::new())
+        .with(RetryTransientMiddleware::new_with_policy(
+            ExponentialBackoff::builder().build_with_max_retries(opts.retries),
+        ))
+        .build();
+    let interval = opts
+        .rate_limit
+        .filter(|rate_limit| *rate_limit > 0)
+        .map(|rate_limit| Duration::from_secs(1) / rate_limit);
+    // The time at which the next request can be started
+    let next_request = Arc::new(Mutex::new(Instant::now()));
+    let transform = Arc::new(transform.clone());
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let downloads = runtime.block_on(
+        stream::iter(urls)
+            .map(|url| {
+                let client = client.clone();
+                let next_request = Arc::clone(&next_request);
+                let transform = Arc::clone(&transform);
+                let span = error_span!("file", operation = "download", file = %url);
+                async move {
+                    if let Some(interval) = interval {
+                        wait_turn(&next_request, interval).await;
+                    }
+                    let downloaded = download(&client, &url, transform).await;
+                    (url, downloaded)
+                }
+                .instrument(span)
+            })
+            .buffered(opts.concurrency.max(1))
+            .collect::<Vec<_>>(),
+    );
+
+    let mut imgs = Vec::with_capacity(downloads.len());
+    for (url, downloaded) in downloads {
+        match (downloaded, failures.as_deref_mut()) {
+            (Ok(img), _) => imgs.push(img),
+            (Err(err), Some(failures)) => {
+                error!("{url} couldn't be downloaded: {err}");
+                failures.push(Failure {
+                    input: url,
+                    error: err.to_string(),
+                });
+            }
+            (Err(err), None) => return Err(err),
+        }
+    }
+
+    Ok(imgs)
+}
+
+/// Reads the urls listed one per line, the blank lines and the `#` comments are ignored
+#[must_use]
+pub fn parse_urls(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(ToString::to_string)
+        .collect()
+}
+
+async fn wait_turn(next_request: &Mutex<Instant>, interval: Duration) {
+    let at = {
+        let mut next_request = next_request.lock().await;
+        let at = (*next_request).max(Instant::now());
+        *next_request = at + interval;
+        at
+    };
+    tokio::time::sleep_until(at).await;
+}
+
+async fn download(
+    client: &ClientWithMiddleware,
+    url: &str,
+    transform: Arc<Transform>,
+) -> Result<Image> {
+    debug!("downloading {url}");
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|err| Error::Download(err.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(Error::Download(format!("{url} responded with {status}")));
+    }
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|err| Error::Download(err.to_string()))?;
+
+    // Decoding is cpu bound, it would hold the other downloads
+    let img = tokio::task::spawn_blocking(move || transform.decode(bytes.to_vec()))
+        .await
+        .map_err(|err| Error::Generic(format!("decoding {url} panicked: {err}")))??;
+
+    Ok(img)
+}
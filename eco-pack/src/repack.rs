@@ -0,0 +1,159 @@
+use std::fs::{self, create_dir_all};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{
+    comic_info::COMIC_INFO_FILE_NAME, manifest::MANIFEST_FILE_NAME, Bookmark, CancellationToken,
+    CbzReader, CbzWriter, ComicInfo, Event, Failure, Measure, NoProgress, Progress, Report, Stage,
+};
+use tracing::{debug, error, error_span};
+
+use crate::{insert_img, Error, Result, Transform, OCR_FILE_NAME};
+
+#[derive(Debug)]
+pub struct RepackOptions {
+    /// The archives to repack
+    pub archives: Vec<Utf8PathBuf>,
+
+    /// The output directory, each archive is written next to its source with a `-repacked` suffix when `None`
+    pub outdir: Option<Utf8PathBuf>,
+
+    /// The transformations applied to the pages, and how they're encoded
+    pub transform: Transform,
+
+    /// The archives that can't be repacked are left out and reported, instead of aborting
+    pub keep_going: bool,
+
+    /// Aborts the repacking, the archive being repacked is not written once it's cancelled
+    pub cancel: CancellationToken,
+}
+
+/// Returns the report of each repacked archive, and the archives left out in keep going mode
+#[allow(clippy::missing_errors_doc)]
+pub fn repack(opts: &RepackOptions) -> Result<(Vec<Report>, Vec<Failure>)> {
+    repack_with_progress(opts, &NoProgress)
+}
+
+/// Same as `repack`, the progress is reported to `progress`, a page is processed once it's transformed and inserted
+#[allow(clippy::missing_errors_doc)]
+pub fn repack_with_progress(
+    opts: &RepackOptions,
+    progress: &dyn Progress,
+) -> Result<(Vec<Report>, Vec<Failure>)> {
+    if let Some(outdir) = &opts.outdir {
+        if !outdir.exists() {
+            create_dir_all(outdir)?;
+        }
+    }
+    let mut reports = Vec::with_capacity(opts.archives.len());
+    let mut failures = Vec::new();
+    for path in &opts.archives {
+        let _span = error_span!("file", operation = "repack", file = %path).entered();
+        let output = output_path(path, opts.outdir.as_deref());
+        match repack_file(path, &output, opts, progress) {
+            Ok(report) => reports.push(report),
+            Err(Error::Cancelled) => return Err(Error::Cancelled),
+            Err(err) if opts.keep_going => {
+                error!("{path} couldn't be repacked: {err}");
+                failures.push(Failure {
+                    input: path.to_string(),
+                    error: err.to_string(),
+                });
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok((reports, failures))
+}
+
+/// The pages go through the same transformations as the packed images, the metadata files and the comment are kept.
+/// The bookmarks follow their pages when the landscape ones are split,
+/// the manifest and the recognized text no longer match the pages so they're dropped.
+fn repack_file(
+    path: &Utf8Path,
+    output: &Utf8Path,
+    opts: &RepackOptions,
+    progress: &dyn Progress,
+) -> Result<Report> {
+    let mut reader = CbzReader::try_from_path(path)?;
+    let mut cbz_writer = opts.transform.configure(CbzWriter::default());
+    let comment = reader.archive().comment().to_vec();
+    if !comment.is_empty() {
+        cbz_writer
+            .raw_archive_mut()
+            .set_comment(String::from_utf8_lossy(&comment));
+    }
+
+    let names = reader.file_names();
+    let total = names.len();
+    // The index of the first written page of each source page
+    let mut first_pages = Vec::with_capacity(total);
+    for (index, name) in names.iter().enumerate() {
+        if opts.cancel.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+        let _span = error_span!("page", page = index + 1).entered();
+        let decode = Measure::with_progress(Stage::Decode, progress);
+        let img = opts.transform.decode(reader.read_bytes_by_name(name)?)?;
+        drop(decode);
+        first_pages.push(cbz_writer.len());
+        insert_img(&mut cbz_writer, img, &opts.transform, None, progress)?;
+        progress.on_event(Event::PageProcessed {
+            page: index + 1,
+            total: Some(total),
+        });
+    }
+
+    let written_pages = cbz_writer.len();
+    for name in reader.sidecars() {
+        let file_name = Utf8Path::new(&name).file_name().unwrap_or(name.as_str());
+        if file_name == MANIFEST_FILE_NAME || file_name == OCR_FILE_NAME {
+            debug!("dropping {name}, it doesn't match the repacked pages");
+            continue;
+        }
+        let bytes = reader.read_bytes_by_name(&name)?;
+        if file_name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME) && written_pages != total {
+            let mut comic_info = ComicInfo::try_from_xml(&String::from_utf8_lossy(&bytes))?;
+            let bookmarks = comic_info
+                .bookmarks()
+                .into_iter()
+                .filter_map(|bookmark| {
+                    Some(Bookmark {
+                        page: *first_pages.get(bookmark.page)?,
+                        title: bookmark.title,
+                    })
+                })
+                .collect::<Vec<_>>();
+            comic_info.set_bookmarks(bookmarks);
+            cbz_writer.set_comic_info(&comic_info)?;
+            continue;
+        }
+        cbz_writer.insert_sidecar(file_name, &bytes)?;
+    }
+    if opts.cancel.is_cancelled() {
+        return Err(Error::Cancelled);
+    }
+
+    let write = Measure::with_progress(Stage::Write, progress);
+    cbz_writer.write_to_path(output)?;
+    drop(write);
+    let size = fs::metadata(output)?.len();
+
+    Ok(Report {
+        outputs: vec![output.to_path_buf()],
+        extracted_pages: total,
+        written_pages,
+        skipped_pages: Vec::new(),
+        failures: Vec::new(),
+        size,
+    })
+}
+
+/// `<name>.cbz` in the output directory, or `<name>-repacked.cbz` next to the source
+fn output_path(path: &Utf8Path, outdir: Option<&Utf8Path>) -> Utf8PathBuf {
+    let stem = path.file_stem().unwrap_or("archive");
+    match outdir {
+        Some(outdir) => outdir.join(format!("{stem}.cbz")),
+        None => path.with_file_name(format!("{stem}-repacked.cbz")),
+    }
+}
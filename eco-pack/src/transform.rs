@@ -0,0 +1,124 @@
+use std::io::{Seek, Write};
+
+use eco_cbz::{
+    image::{Image, ReadingOrder, Resampling},
+    CbzWriter, Compression, PageFormat,
+};
+
+use crate::{FilterCommand, Watermark};
+
+/// The largest size of the pages, they're shrunk to fit in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resize {
+    pub max_width: u32,
+    pub max_height: u32,
+    pub resampling: Resampling,
+}
+
+/// The transformations applied to each page, and how the pages are encoded, shared by convert, pack, and repack
+#[derive(Debug, Clone)]
+pub struct Transform {
+    /// Converts the colors of the images embedding an ICC profile (e.g. Adobe RGB scans) to sRGB
+    pub srgb: bool,
+
+    /// Transform each image with this external command, before the other transformations
+    pub filter: Option<FilterCommand>,
+
+    /// Adjust images contrast
+    pub contrast: Option<f32>,
+
+    /// Adjust images brightness
+    pub brightness: Option<i32>,
+
+    /// Blur image (slow with big numbers)
+    pub blur: Option<f32>,
+
+    /// Remove the noise of the scanned pages with a median filter of this radius (slow with big numbers)
+    pub denoise: Option<u32>,
+
+    /// Remove the halftone patterns of the scanned pages
+    pub descreen: bool,
+
+    /// Automatically split landscape images into 2 pages
+    pub autosplit: bool,
+
+    /// Shrinks the pages bigger than this size, once the landscape images are split
+    pub resize: Option<Resize>,
+
+    /// Reading order
+    pub reading_order: ReadingOrder,
+
+    /// Renders a text, like the page number, on each page
+    pub watermark: Option<Watermark>,
+
+    /// Encodes the pages in this format, instead of the format of each image
+    pub page_format: Option<PageFormat>,
+
+    /// Quality of the pages, between 1 and 100, when they're encoded in a lossy `page_format`
+    pub quality: Option<u8>,
+
+    /// How the pages are compressed in the archive
+    pub compression: Compression,
+}
+
+impl Transform {
+    /// Sets how the pages are encoded and compressed by the writer
+    #[must_use]
+    pub fn configure<W>(&self, mut cbz_writer: CbzWriter<W>) -> CbzWriter<W>
+    where
+        W: Write + Seek,
+    {
+        cbz_writer = cbz_writer.with_compression(self.compression);
+        if let Some(page_format) = self.page_format {
+            cbz_writer = cbz_writer.with_page_format(page_format);
+        }
+        if let Some(quality) = self.quality {
+            cbz_writer = cbz_writer.with_quality(quality);
+        }
+        cbz_writer
+    }
+
+    /// The landscape jpegs are split without being encoded again, unless the halves are transformed or encoded anyway
+    #[must_use]
+    pub fn splits_losslessly(&self) -> bool {
+        self.autosplit
+            && self.watermark.is_none()
+            && self.resize.is_none()
+            && self.page_format.is_none()
+    }
+
+    /// Decodes the image, the encoded jpegs are only kept when they can be split losslessly
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the image format can't be guessed or the image can't be decoded
+    pub fn decode(&self, bytes: Vec<u8>) -> eco_cbz::Result<Image> {
+        if self.splits_losslessly() {
+            Image::try_from_bytes_keeping_jpeg(bytes)
+        } else {
+            Image::try_from_bytes(&bytes)
+        }
+    }
+}
+
+/// No transformation, the pages are inserted as is
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            srgb: false,
+            filter: None,
+            contrast: None,
+            brightness: None,
+            blur: None,
+            denoise: None,
+            descreen: false,
+            autosplit: false,
+            resize: None,
+            reading_order: ReadingOrder::Rtl,
+            watermark: None,
+            page_format: None,
+            quality: None,
+            compression: Compression::default(),
+        }
+    }
+}
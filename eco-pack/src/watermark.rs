@@ -0,0 +1,123 @@
+use eco_cbz::{image::Image, text::font};
+use image::{DynamicImage, ImageBuffer, Pixel, Rgb, Rgba};
+use imageproc::drawing::{draw_text_mut, text_size};
+use rusttype::Scale;
+
+/// The text height is a fraction of the page height, so it looks the same whatever the resolution
+const TEXT_HEIGHT_RATIO: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// A text rendered in a corner of each page, `{page}` is replaced with the page number
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub text: String,
+    pub corner: Corner,
+    /// Between 0 (invisible) and 1 (opaque)
+    pub opacity: f32,
+}
+
+impl Watermark {
+    /// Renders the page numbers only
+    #[must_use]
+    pub fn page_numbers(corner: Corner, opacity: f32) -> Self {
+        Self {
+            text: "{page}".to_string(),
+            corner,
+            opacity,
+        }
+    }
+
+    /// The text is black on a white box, so it's readable on any page.
+    /// The transparency of the images is kept, the box is as opaque as the watermark.
+    #[must_use]
+    pub fn apply(&self, img: Image, page: usize) -> Image {
+        let text = self.text.replace("{page}", &page.to_string());
+        let dynamic_image = if img.dynamic().color().has_alpha() {
+            let mut canvas = img.dynamic().to_rgba8();
+            if !self.stamp(&mut canvas, &text, Rgba([255; 4]), Rgba([0, 0, 0, 255])) {
+                return img;
+            }
+            DynamicImage::ImageRgba8(canvas)
+        } else {
+            let mut canvas = img.dynamic().to_rgb8();
+            if !self.stamp(&mut canvas, &text, Rgb([255; 3]), Rgb([0; 3])) {
+                return img;
+            }
+            DynamicImage::ImageRgb8(canvas)
+        };
+
+        let format = img.format();
+        let mut watermarked = Image::from(dynamic_image);
+        if let Some(format) = format {
+            watermarked.set_format(format);
+        }
+        watermarked
+    }
+
+    /// Blends the label in the corner of the canvas, returns `false` if the canvas is too small for it
+    fn stamp<P>(
+        &self,
+        canvas: &mut ImageBuffer<P, Vec<u8>>,
+        text: &str,
+        background: P,
+        color: P,
+    ) -> bool
+    where
+        P: Pixel<Subpixel = u8> + 'static,
+    {
+        let font = font();
+        let (width, height) = canvas.dimensions();
+
+        #[allow(clippy::cast_precision_loss)]
+        let scale = Scale::uniform((height as f32 * TEXT_HEIGHT_RATIO).max(8.0));
+        let (text_width, text_height) = text_size(scale, font, text);
+        let padding = text_height / 2;
+        let box_width = u32::try_from(text_width + 2 * padding).unwrap_or_default();
+        let box_height = u32::try_from(text_height + 2 * padding).unwrap_or_default();
+        if box_width == 0 || box_height == 0 || box_width > width || box_height > height {
+            return false;
+        }
+
+        let margin = box_height / 2;
+        let x = match self.corner {
+            Corner::TopLeft | Corner::BottomLeft => margin.min(width - box_width),
+            Corner::TopRight | Corner::BottomRight => width.saturating_sub(box_width + margin),
+        };
+        let y = match self.corner {
+            Corner::TopLeft | Corner::TopRight => margin.min(height - box_height),
+            Corner::BottomLeft | Corner::BottomRight => height.saturating_sub(box_height + margin),
+        };
+
+        let mut label = ImageBuffer::from_pixel(box_width, box_height, background);
+        draw_text_mut(&mut label, color, padding, padding, scale, font, text);
+
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        for (label_x, label_y, label_pixel) in label.enumerate_pixels() {
+            let pixel = canvas.get_pixel_mut(x + label_x, y + label_y);
+            for (value, label_value) in pixel.channels_mut().iter_mut().zip(label_pixel.channels())
+            {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let blended = (f32::from(*value) * (1.0 - opacity)
+                    + f32::from(*label_value) * opacity)
+                    .round() as u8;
+                *value = blended;
+            }
+        }
+
+        true
+    }
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self::page_numbers(Corner::default(), 0.8)
+    }
+}
@@ -0,0 +1,37 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error {0}")]
+    IO(#[from] std::io::Error),
+
+    #[error("cbz error {0}")]
+    Cbz(#[from] eco_cbz::Error),
+
+    #[error("epub doc error {0}")]
+    EpubDoc(#[from] epub::doc::DocError),
+
+    #[error("glob error {0}")]
+    Glob(#[from] glob::GlobError),
+
+    #[error("glob pattern error {0}")]
+    GlobPattern(#[from] glob::PatternError),
+
+    #[error("server error {0}")]
+    Server(Box<dyn std::error::Error + Send + Sync>),
+
+    #[error("invalid non utf8 path provided")]
+    InvalidNonUtf8Path,
+
+    #[error("book not found {0}")]
+    BookNotFound(usize),
+
+    #[error("page not found {0}")]
+    PageNotFound(usize),
+
+    #[error("file not found {0}")]
+    FileNotFound(String),
+
+    #[error("route not found {0}")]
+    RouteNotFound(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,51 @@
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::{sync::Arc, thread};
+
+use camino::Utf8PathBuf;
+use tiny_http::Server;
+use tracing::{error, info};
+
+pub use crate::errors::{Error, Result};
+use crate::library::Library;
+
+mod encoding;
+pub mod errors;
+mod library;
+mod render;
+mod routes;
+
+#[derive(Debug)]
+pub struct ServeOptions {
+    /// The directory containing the cbz and epub files to serve
+    pub dir: Utf8PathBuf,
+
+    /// The address to listen on, e.g. `127.0.0.1:8080`
+    pub address: String,
+}
+
+/// Starts a web server serving a browser based reader over the books found in the directory.
+/// Only the requested pages are read from the books.
+///
+/// ## Errors
+///
+/// Fails if the directory can't be read or the server can't listen on the address
+#[allow(clippy::needless_pass_by_value)]
+pub fn serve(opts: ServeOptions) -> Result<()> {
+    let library = Arc::new(Library::try_scan(&opts.dir)?);
+    info!("found {} books in {}", library.len(), opts.dir);
+
+    let server = Server::http(&opts.address).map_err(Error::Server)?;
+    info!("serving on http://{}", opts.address);
+
+    for request in server.incoming_requests() {
+        let library = Arc::clone(&library);
+        thread::spawn(move || {
+            if let Err(err) = routes::handle(&library, request) {
+                error!("response couldn't be sent: {err}");
+            }
+        });
+    }
+
+    Ok(())
+}
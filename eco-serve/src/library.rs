@@ -0,0 +1,166 @@
+use std::io::Read;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::CbzReader;
+use epub::doc::EpubDoc;
+use glob::{glob, Pattern};
+use tracing::{debug, error};
+
+use crate::errors::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookKind {
+    Cbz,
+    EPub,
+}
+
+impl BookKind {
+    fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "cbz" => Some(Self::Cbz),
+            "epub" => Some(Self::EPub),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Book {
+    pub path: Utf8PathBuf,
+    pub kind: BookKind,
+}
+
+impl Book {
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.path.file_stem().unwrap_or(self.path.as_str())
+    }
+
+    /// The paths of the pages inside the book, in reading order
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the book can't be opened
+    pub fn page_paths(&self) -> Result<Vec<String>> {
+        match self.kind {
+            BookKind::Cbz => Ok(CbzReader::try_from_path(&self.path)?.file_names()),
+            BookKind::EPub => {
+                let mut doc = EpubDoc::new(&self.path)?;
+                let mut paths = Vec::with_capacity(doc.get_num_pages());
+                for page in 0..doc.get_num_pages() {
+                    doc.set_current_page(page);
+                    let Some(path) = doc.get_current_path() else {
+                        continue;
+                    };
+                    // Epub paths are always separated by slashes, whatever the platform
+                    paths.push(path.to_string_lossy().replace('\\', "/"));
+                }
+                Ok(paths)
+            }
+        }
+    }
+
+    /// Reads a single file from the book, only this file is loaded in memory.
+    /// Returns the content of the file and its mime type.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the book can't be opened or if the file doesn't exist
+    pub fn read_file(&self, path: &str) -> Result<(Vec<u8>, String)> {
+        match self.kind {
+            BookKind::Cbz => {
+                let mut reader = CbzReader::try_from_path(&self.path)?;
+                let mut file = reader.raw_read_by_name(path)?;
+                let mut bytes =
+                    Vec::with_capacity(usize::try_from(file.size()).unwrap_or_default());
+                file.read_to_end(&mut bytes)?;
+                Ok((bytes, mime_from_path(path).to_string()))
+            }
+            BookKind::EPub => {
+                let mut doc = EpubDoc::new(&self.path)?;
+                let mime = doc
+                    .get_resource_mime_by_path(path)
+                    .unwrap_or_else(|| mime_from_path(path).to_string());
+                let Some(bytes) = doc.get_resource_by_path(path) else {
+                    return Err(Error::FileNotFound(path.to_string()));
+                };
+                Ok((bytes, mime))
+            }
+        }
+    }
+}
+
+/// All the books found in the served directory
+#[derive(Debug, Default)]
+pub struct Library {
+    books: Vec<Book>,
+}
+
+impl Library {
+    /// Looks for cbz and epub files in the directory and its sub directories
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the directory can't be read
+    pub fn try_scan(dir: &Utf8Path) -> Result<Self> {
+        let mut books = Vec::new();
+        // The directory name may contain glob special characters, like `[` or `*`
+        let dir = Pattern::escape(dir.as_str());
+        for path in glob(Utf8Path::new(&dir).join("**").join("*").as_str())? {
+            let path = path?;
+            let Ok(path) = Utf8PathBuf::from_path_buf(path) else {
+                error!("skipping non utf-8 path");
+                continue;
+            };
+            let Some(kind) = path.extension().and_then(BookKind::from_extension) else {
+                continue;
+            };
+            debug!("found book {path}");
+            books.push(Book { path, kind });
+        }
+        books.sort_by(|book, other| book.path.cmp(&other.path));
+
+        Ok(Self { books })
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if there is no book with this id
+    pub fn get(&self, id: usize) -> Result<&Book> {
+        self.books.get(id).ok_or(Error::BookNotFound(id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Book> {
+        self.books.iter()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.books.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.books.is_empty()
+    }
+}
+
+fn mime_from_path(path: &str) -> &'static str {
+    let ext = Utf8Path::new(path)
+        .extension()
+        .map(str::to_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "css" => "text/css",
+        "html" | "htm" => "text/html",
+        "xhtml" => "application/xhtml+xml",
+        _ => "application/octet-stream",
+    }
+}
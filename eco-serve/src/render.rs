@@ -0,0 +1,99 @@
+use std::fmt::Write;
+
+use eco_cbz::markup::escape_markup;
+
+use crate::encoding::encode_path;
+use crate::errors::{Error, Result};
+use crate::library::{BookKind, Library};
+
+static STYLES: &str = "\
+body { margin: 0; font-family: sans-serif; background: #1f1f1f; color: #e5e5e5; }
+a { color: inherit; }
+ul { list-style: none; padding: 0 1rem; }
+li { padding: 0.5rem 0; font-size: 1.25rem; }
+.reader { display: flex; flex-direction: column; height: 100vh; }
+.page { flex: 1; min-height: 0; display: flex; justify-content: center; }
+.page img { max-width: 100%; max-height: 100%; object-fit: contain; }
+.page iframe { width: 100%; height: 100%; border: none; background: #ffffff; }
+.nav { display: flex; justify-content: space-between; align-items: center; padding: 0.5rem 1rem; }
+.nav a { padding: 0.5rem 1rem; border: 1px solid; border-radius: 0.5rem; text-decoration: none; }
+";
+
+/// Turns the pages with the arrow keys, and with taps on the left and right thirds of the screen
+static NAVIGATION_SCRIPT: &str = "\
+const go = (id) => { const link = document.getElementById(id); if (link) { location.href = link.href; } };
+document.addEventListener('keyup', (evt) => {
+  if (evt.key === 'ArrowLeft') { go('prev'); }
+  if (evt.key === 'ArrowRight') { go('next'); }
+});
+document.querySelector('.page').addEventListener('click', (evt) => {
+  if (evt.clientX < window.innerWidth / 3) { go('prev'); }
+  if (evt.clientX > (window.innerWidth * 2) / 3) { go('next'); }
+});
+";
+
+fn layout(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+        <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+        <title>{}</title><style>{STYLES}</style></head><body>{body}</body></html>",
+        escape_markup(title)
+    )
+}
+
+/// Lists all the books of the library
+pub fn library(library: &Library) -> String {
+    let mut body = String::from("<ul>");
+    for (id, book) in library.iter().enumerate() {
+        let _ = write!(
+            body,
+            "<li><a href=\"/books/{id}\">{}</a></li>",
+            escape_markup(book.name())
+        );
+    }
+    body.push_str("</ul>");
+    layout("Eco Library", &body)
+}
+
+/// The reader, displaying a single page of the book at a time
+///
+/// ## Errors
+///
+/// Fails if the book can't be read or the page doesn't exist
+pub fn reader(library: &Library, id: usize, page: usize) -> Result<String> {
+    let book = library.get(id)?;
+    let page_paths = book.page_paths()?;
+    let max_page = page_paths.len();
+    let Some(page_path) = page.checked_sub(1).and_then(|index| page_paths.get(index)) else {
+        return Err(Error::PageNotFound(page));
+    };
+    let src = format!("/books/{id}/files/{}", encode_path(page_path));
+    let content = match book.kind {
+        BookKind::Cbz => format!("<img src=\"{src}\" alt=\"Page {page}\">"),
+        // Epub pages are served from their own path, so their relative links keep working
+        BookKind::EPub => format!("<iframe src=\"{src}\"></iframe>"),
+    };
+    let prev = if page > 1 {
+        format!(
+            "<a id=\"prev\" href=\"/books/{id}?page={}\">Prev</a>",
+            page - 1
+        )
+    } else {
+        String::from("<span></span>")
+    };
+    let next = if page < max_page {
+        format!(
+            "<a id=\"next\" href=\"/books/{id}?page={}\">Next</a>",
+            page + 1
+        )
+    } else {
+        String::from("<span></span>")
+    };
+    let body = format!(
+        "<div class=\"reader\"><div class=\"page\">{content}</div>\
+        <div class=\"nav\">{prev}<a href=\"/\">Library</a><span>{page} / {max_page}</span>{next}</div></div>\
+        <script>{NAVIGATION_SCRIPT}</script>"
+    );
+
+    Ok(layout(book.name(), &body))
+}
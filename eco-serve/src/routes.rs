@@ -0,0 +1,81 @@
+use std::io::{self, Cursor};
+
+use tiny_http::{Header, Method, Request, Response};
+use tracing::{debug, error};
+
+use crate::encoding::decode_path;
+use crate::errors::{Error, Result};
+use crate::library::Library;
+use crate::render;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+/// Responds to the request, errors are turned into the matching http status
+///
+/// ## Errors
+///
+/// Fails if the response can't be sent
+#[allow(clippy::needless_pass_by_value)]
+pub fn handle(library: &Library, request: Request) -> io::Result<()> {
+    if *request.method() != Method::Get {
+        return request.respond(Response::from_string("method not allowed").with_status_code(405));
+    }
+    let url = request.url().to_string();
+    debug!("GET {url}");
+    let response = route(library, &url).unwrap_or_else(|err| {
+        let status_code = match err {
+            Error::BookNotFound(_)
+            | Error::PageNotFound(_)
+            | Error::FileNotFound(_)
+            | Error::RouteNotFound(_) => 404,
+            _ => {
+                error!("{url} couldn't be served: {err}");
+                500
+            }
+        };
+        Response::from_string(err.to_string()).with_status_code(status_code)
+    });
+    request.respond(response)
+}
+
+fn route(library: &Library, url: &str) -> Result<HttpResponse> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments = path
+        .trim_start_matches('/')
+        .splitn(4, '/')
+        .collect::<Vec<_>>();
+    match segments.as_slice() {
+        [""] => Ok(html(render::library(library))),
+        ["books", id] => {
+            let id = parse_id(id, url)?;
+            let page = query
+                .split('&')
+                .find_map(|param| param.strip_prefix("page="))
+                .and_then(|page| page.parse().ok())
+                .unwrap_or(1);
+            Ok(html(render::reader(library, id, page)?))
+        }
+        ["books", id, "files", file_path] => {
+            let book = library.get(parse_id(id, url)?)?;
+            let (bytes, mime) = book.read_file(&decode_path(file_path))?;
+            Ok(with_content_type(Response::from_data(bytes), &mime))
+        }
+        _ => Err(Error::RouteNotFound(url.to_string())),
+    }
+}
+
+fn parse_id(id: &str, url: &str) -> Result<usize> {
+    id.parse()
+        .map_err(|_| Error::RouteNotFound(url.to_string()))
+}
+
+fn html(content: String) -> HttpResponse {
+    with_content_type(Response::from_string(content), "text/html; charset=utf-8")
+}
+
+fn with_content_type(response: HttpResponse, mime: &str) -> HttpResponse {
+    match Header::from_bytes(&b"Content-Type"[..], mime.as_bytes()) {
+        Ok(header) => response.with_header(header),
+        Err(()) => response,
+    }
+}
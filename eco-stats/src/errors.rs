@@ -0,0 +1,13 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("glob error {0}")]
+    Glob(#[from] glob::GlobError),
+
+    #[error("glob pattern error {0}")]
+    GlobPattern(#[from] glob::PatternError),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,176 @@
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::collections::BTreeMap;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use cli_table::{print_stdout, Cell, Style, Table};
+use eco_cbz::{comic_info::COMIC_INFO_FILE_NAME, CbzReader};
+use glob::{glob, Pattern};
+use serde::Serialize;
+use tracing::{debug, error};
+
+pub use crate::errors::{Error, Result};
+
+pub mod errors;
+
+/// The upper bounds, in MiB, of the archive size buckets, the last bucket has no upper bound
+static SIZE_BUCKETS: [u64; 4] = [10, 50, 100, 500];
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Debug)]
+pub struct StatsOptions {
+    /// The directory containing the archives, its sub directories are walked too
+    pub dir: Utf8PathBuf,
+
+    /// Reads all the entries to detect the corrupted ones, instead of the archives index only
+    pub deep: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Stats {
+    pub archives: usize,
+    pub pages: usize,
+    /// Total size of the archives in bytes
+    pub size: u64,
+    /// Amount of archives per size range
+    pub sizes: Vec<SizeBucket>,
+    /// Amount of pages per image format (i.e. file extension)
+    pub formats: BTreeMap<String, usize>,
+    /// The archives with neither a `ComicInfo.xml` entry nor a `ComicBookInfo` comment
+    pub without_metadata: Vec<Utf8PathBuf>,
+    /// The archives that can't be read
+    pub corrupted: Vec<Utf8PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeBucket {
+    pub label: String,
+    pub count: usize,
+}
+
+/// ## Errors
+///
+/// Fails if the directory can't be walked
+pub fn stats(opts: &StatsOptions) -> Result<Stats> {
+    let mut stats = Stats {
+        sizes: size_labels()
+            .into_iter()
+            .map(|label| SizeBucket { label, count: 0 })
+            .collect(),
+        ..Stats::default()
+    };
+
+    // The directory name may contain glob special characters, like `[` or `*`
+    let dir = Pattern::escape(opts.dir.as_str());
+    for path in glob(Utf8Path::new(&dir).join("**").join("*.cbz").as_str())? {
+        let path = path?;
+        let Ok(path) = Utf8PathBuf::from_path_buf(path) else {
+            error!("skipping non utf-8 path");
+            continue;
+        };
+        debug!("reading {path}");
+        let size = path.metadata()?.len();
+        stats.archives += 1;
+        stats.size += size;
+        let bucket = SIZE_BUCKETS
+            .iter()
+            .position(|bound| size < bound * MIB)
+            .unwrap_or(SIZE_BUCKETS.len());
+        stats.sizes[bucket].count += 1;
+
+        if let Err(err) = add_archive(&mut stats, &path, opts.deep) {
+            error!("{path} is corrupted: {err}");
+            stats.corrupted.push(path);
+        }
+    }
+
+    Ok(stats)
+}
+
+fn add_archive(stats: &mut Stats, path: &Utf8Path, deep: bool) -> eco_cbz::Result<()> {
+    let mut reader = CbzReader::try_from_path(path)?;
+    let names = reader.file_names();
+    if deep {
+        // The checksums are verified while reading
+        for name in &names {
+            reader.read_bytes_by_name(name)?;
+        }
+    }
+
+    stats.pages += names.len();
+    for name in &names {
+        let format = Utf8Path::new(name)
+            .extension()
+            .map_or_else(|| "none".to_string(), str::to_lowercase);
+        *stats.formats.entry(format).or_default() += 1;
+    }
+    let has_metadata = !reader.archive().comment().is_empty()
+        || reader
+            .sidecars()
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME));
+    if !has_metadata {
+        stats.without_metadata.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+fn size_labels() -> Vec<String> {
+    let mut labels = Vec::with_capacity(SIZE_BUCKETS.len() + 1);
+    let mut lower = 0;
+    for bound in SIZE_BUCKETS {
+        labels.push(format!("{lower}-{bound} MiB"));
+        lower = bound;
+    }
+    labels.push(format!(">= {lower} MiB"));
+    labels
+}
+
+/// Prints the stats as tables on the standard output
+///
+/// ## Errors
+///
+/// Fails if the tables can't be written
+pub fn print_tables(stats: &Stats) -> Result<()> {
+    let mut rows = vec![
+        vec!["archives".cell(), stats.archives.cell()],
+        vec!["pages".cell(), stats.pages.cell()],
+        vec!["size (MiB)".cell(), (stats.size / MIB).cell()],
+        vec![
+            "without metadata".cell(),
+            stats.without_metadata.len().cell(),
+        ],
+        vec!["corrupted".cell(), stats.corrupted.len().cell()],
+    ];
+    rows.extend(
+        stats
+            .sizes
+            .iter()
+            .map(|bucket| vec![bucket.label.as_str().cell(), bucket.count.cell()]),
+    );
+    rows.extend(
+        stats
+            .formats
+            .iter()
+            .map(|(format, count)| vec![format!("{format} pages").cell(), count.cell()]),
+    );
+    print_stdout(
+        rows.table()
+            .title(vec!["Stat".cell().bold(true), "Value".cell().bold(true)]),
+    )?;
+
+    for (title, paths) in [
+        ("Without metadata", &stats.without_metadata),
+        ("Corrupted", &stats.corrupted),
+    ] {
+        if paths.is_empty() {
+            continue;
+        }
+        let rows = paths.iter().map(|path| vec![path.as_str().cell()]);
+        print_stdout(rows.table().title(vec![title.cell().bold(true)]))?;
+    }
+
+    Ok(())
+}
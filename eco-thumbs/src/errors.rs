@@ -0,0 +1,22 @@
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cbz error {0}")]
+    Cbz(#[from] eco_cbz::Error),
+
+    #[error("image error {0}")]
+    Image(#[from] image::ImageError),
+
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("glob error {0}")]
+    Glob(#[from] glob::GlobError),
+
+    #[error("glob pattern error {0}")]
+    GlobPattern(#[from] glob::PatternError),
+
+    #[error("invalid thumbnail size {0}, expected at least 1 pixel")]
+    InvalidSize(u32),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
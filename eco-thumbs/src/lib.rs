@@ -0,0 +1,129 @@
+#![deny(clippy::all, clippy::pedantic)]
+
+use std::fs;
+
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{manifest::sha256, CbzReader, Resampling};
+use glob::glob;
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use tracing::{debug, error};
+
+pub use crate::errors::{Error, Result};
+
+pub mod errors;
+
+#[derive(Debug)]
+pub struct ThumbsOptions {
+    /// A glob that matches all the archives
+    pub archives_glob: String,
+
+    /// The output directory for the thumbnails
+    pub outdir: Utf8PathBuf,
+
+    /// Amount of pages to extract from each archive, starting with the cover
+    pub pages: usize,
+
+    /// Maximum width and height of the thumbnails, the aspect ratio is preserved
+    pub size: u32,
+
+    /// The filter used to resize the pages, a fast approximation is used by default
+    pub resampling: Option<Resampling>,
+
+    /// Writes a single contact sheet per archive instead of one file per page
+    pub contact_sheet: bool,
+
+    /// Amount of thumbnails per row in the contact sheets
+    pub columns: u32,
+}
+
+/// Returns the paths of the written images, the archives that can't be read are skipped
+#[allow(clippy::missing_errors_doc, clippy::needless_pass_by_value)]
+pub fn thumbs(opts: ThumbsOptions) -> Result<Vec<Utf8PathBuf>> {
+    if opts.size == 0 {
+        return Err(Error::InvalidSize(opts.size));
+    }
+    fs::create_dir_all(&opts.outdir)?;
+    let mut outputs = Vec::new();
+
+    for path in glob(&opts.archives_glob)? {
+        let path = path?;
+        let Some(path) = Utf8Path::from_path(&path) else {
+            error!("{path:?} is not a valid utf-8 path");
+            continue;
+        };
+        let thumbs = match archive_thumbs(path, opts.pages, opts.size, opts.resampling) {
+            Ok(thumbs) => thumbs,
+            Err(err) => {
+                error!("thumbnails of {path} couldn't be generated: {err}");
+                continue;
+            }
+        };
+        let stem = output_stem(path);
+
+        if opts.contact_sheet {
+            let output = opts.outdir.join(format!("{stem}.png"));
+            contact_sheet(&thumbs, opts.size, opts.columns).save(&output)?;
+            outputs.push(output);
+        } else {
+            for (index, thumb) in thumbs.iter().enumerate() {
+                let output = opts.outdir.join(format!("{stem}-{:03}.png", index + 1));
+                thumb.save(&output)?;
+                outputs.push(output);
+            }
+        }
+    }
+
+    Ok(outputs)
+}
+
+/// The archive name, followed by a hash of its path so the archives with the same name in different directories don't collide
+fn output_stem(path: &Utf8Path) -> String {
+    let name = path.file_stem().unwrap_or("archive");
+    let path = path
+        .canonicalize_utf8()
+        .unwrap_or_else(|_| path.to_path_buf());
+    let hash = sha256(path.as_str().as_bytes());
+
+    format!("{name}-{}", &hash[..8])
+}
+
+/// Reads the first pages of the archive, and resizes them
+fn archive_thumbs(
+    path: &Utf8Path,
+    pages: usize,
+    size: u32,
+    resampling: Option<Resampling>,
+) -> Result<Vec<DynamicImage>> {
+    let mut reader = CbzReader::try_from_path(path)?;
+    let pages = pages.min(reader.len());
+    debug!("generating {pages} thumbnails for {path}");
+    let mut thumbs = Vec::with_capacity(pages);
+    for index in 0..pages {
+        let page = reader.read_by_index(index)?;
+        thumbs.push(match resampling {
+            Some(resampling) => page.dynamic().resize(size, size, resampling.into()),
+            None => page.dynamic().thumbnail(size, size),
+        });
+    }
+
+    Ok(thumbs)
+}
+
+/// Lays the thumbnails out in a grid, each one is centered in its cell
+fn contact_sheet(thumbs: &[DynamicImage], size: u32, columns: u32) -> RgbaImage {
+    let columns = columns.max(1);
+    let count = u32::try_from(thumbs.len()).unwrap_or(u32::MAX);
+    let rows = count.div_ceil(columns).max(1);
+    let mut sheet = RgbaImage::from_pixel(
+        columns.min(count.max(1)) * size,
+        rows * size,
+        Rgba([255, 255, 255, 255]),
+    );
+    for (index, thumb) in (0..).zip(thumbs) {
+        let x = (index % columns) * size + (size - thumb.width()) / 2;
+        let y = (index / columns) * size + (size - thumb.height()) / 2;
+        imageops::overlay(&mut sheet, thumb, i64::from(x), i64::from(y));
+    }
+
+    sheet
+}
@@ -0,0 +1,186 @@
+#![deny(clippy::all, clippy::pedantic)]
+// Necessary for Dioxus
+#![allow(non_snake_case, clippy::ignored_unit_patterns)]
+
+use std::io::Cursor;
+
+use base64::Engine;
+use dioxus::{html::input_data::keyboard_types::Key, prelude::*};
+use eco_cbz::{CbzReader, Result};
+
+/// The stylesheet of the desktop viewer, so both viewers look the same
+static STYLES: &str = include_str!("../../eco-view/assets/styles.css");
+
+fn main() {
+    dioxus_web::launch(App);
+}
+
+/// An archive read in the browser memory, the pages are read when they're displayed
+struct OpenedArchive {
+    name: String,
+    reader: CbzReader<Cursor<Vec<u8>>>,
+    max_page: usize,
+}
+
+impl OpenedArchive {
+    fn try_open(name: String, bytes: Vec<u8>) -> Result<Self> {
+        let reader = CbzReader::try_from_bytes(bytes)?;
+        let max_page = reader.len();
+
+        Ok(Self {
+            name,
+            reader,
+            max_page,
+        })
+    }
+
+    /// The page as a data url, the pages are displayed as is without being decoded
+    fn page_url(&mut self, page: usize) -> Result<String> {
+        let Some(name) = page
+            .checked_sub(1)
+            .and_then(|index| self.reader.entries().get(index).cloned())
+        else {
+            return Err(eco_cbz::Error::CbzNotFound(page.saturating_sub(1)));
+        };
+        let bytes = self.reader.read_bytes_by_name(&name)?;
+
+        Ok(format!(
+            "data:{};base64,{}",
+            mime_type(&name),
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ))
+    }
+}
+
+fn mime_type(name: &str) -> &'static str {
+    let extension = name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jxl" => "image/jxl",
+        "bmp" => "image/bmp",
+        _ => "application/octet-stream",
+    }
+}
+
+fn App(cx: Scope) -> Element {
+    let archive = use_ref(cx, || None::<OpenedArchive>);
+    let current_page = use_state(cx, || 1_usize);
+    let error = use_state(cx, || None::<String>);
+    // Bumped each time an archive is opened, so its first page is read even if the page number didn't change
+    let generation = use_state(cx, || 0_usize);
+    let page_url = use_memo(
+        cx,
+        (*current_page.get(), *generation.get()),
+        |(page, _generation)| {
+            archive.with_mut(|archive| {
+                archive
+                    .as_mut()
+                    .map(|archive| archive.page_url(page).map_err(|err| err.to_string()))
+            })
+        },
+    );
+    let (name, max_page) = archive.with(|archive| {
+        archive.as_ref().map_or((String::new(), 0), |archive| {
+            (archive.name.clone(), archive.max_page)
+        })
+    });
+    let page = *current_page.get();
+
+    let open_file = move |evt: FormEvent| {
+        let Some(file_engine) = evt.files.clone() else {
+            return;
+        };
+        to_owned![archive, current_page, error, generation];
+        cx.spawn(async move {
+            let Some(name) = file_engine.files().into_iter().next() else {
+                return;
+            };
+            let Some(bytes) = file_engine.read_file(&name).await else {
+                error.set(Some(format!("{name} couldn't be read")));
+                return;
+            };
+            match OpenedArchive::try_open(name.clone(), bytes) {
+                Ok(opened_archive) => {
+                    archive.set(Some(opened_archive));
+                    current_page.set(1);
+                    generation.modify(|generation| generation.wrapping_add(1));
+                    error.set(None);
+                }
+                Err(err) => error.set(Some(format!("{name} couldn't be opened: {err}"))),
+            }
+        });
+    };
+    let go_to_page = move |page: usize| {
+        if page > 0 && page <= max_page {
+            current_page.set(page);
+        }
+    };
+
+    cx.render(rsx! {
+        style { "{STYLES}" }
+        div {
+            class: "w-full h-screen flex flex-col gap-1 items-center outline-none",
+            autofocus: true,
+            tabindex: -1,
+            onkeyup: move |evt| match evt.key() {
+                Key::ArrowLeft | Key::ArrowUp => go_to_page(page.saturating_sub(1)),
+                Key::ArrowRight | Key::ArrowDown => go_to_page(page + 1),
+                Key::Home => go_to_page(1),
+                Key::End => go_to_page(max_page),
+                _ => {}
+            },
+            div {
+                class: "flex flex-row items-center justify-center gap-1 h-8 mt-2",
+                input {
+                    r#type: "file",
+                    accept: ".cbz,application/vnd.comicbook+zip",
+                    onchange: open_file,
+                }
+                span { class: "text-sm", "{name}" }
+            }
+            if let Some(error) = error.get() {
+                rsx!(span { "{error}" })
+            }
+            div {
+                class: "flex flex-col h-full w-full items-center justify-center",
+                match page_url {
+                    Some(Ok(page_url)) => rsx!(div {
+                        class: "h-px grow w-full flex overflow-auto",
+                        img {
+                            class: "m-auto h-full w-full object-contain",
+                            src: "{page_url}",
+                        }
+                    }),
+                    Some(Err(err)) => rsx!(span { "Page {page} couldn't be read: {err}" }),
+                    None => rsx!(span { "Open a cbz archive to read it" }),
+                }
+            }
+            if max_page > 0 {
+                rsx!(div {
+                    class: "flex flex-row items-center justify-center gap-1 h-8 mb-2",
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        onclick: move |_evt| go_to_page(page.saturating_sub(1)),
+                        "Prev"
+                    }
+                    span {
+                        class: "flex flex-row items-center justify-center h-8 px-2 rounded-sm border",
+                        "{page} / {max_page}"
+                    }
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        onclick: move |_evt| go_to_page(page + 1),
+                        "Next"
+                    }
+                })
+            }
+        }
+    })
+}
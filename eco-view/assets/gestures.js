@@ -0,0 +1,109 @@
+// Eco Viewer touch and trackpad gestures
+//
+// Swipe left or right to turn the pages, pinch to zoom in the page, and use two fingers to pan
+// around a zoomed page, press `0` to reset the zoom.
+// Page turns are forwarded to the viewer as arrow key presses.
+(() => {
+  const MIN_ZOOM = 1;
+  const MAX_ZOOM = 5;
+  // Minimum horizontal distance, in pixels, for a touch to be considered a swipe
+  const SWIPE_DISTANCE = 60;
+  // Trackpad pinches are reported as wheel events with the control key pressed
+  const WHEEL_ZOOM_SPEED = 0.01;
+
+  let zoom = MIN_ZOOM;
+  let swipe = null;
+  let pinch = null;
+
+  const getImage = () => document.getElementById("page-image");
+
+  const setZoom = (value) => {
+    zoom = Math.min(MAX_ZOOM, Math.max(MIN_ZOOM, value));
+    document.documentElement.style.setProperty("--page-zoom", `${zoom}`);
+  };
+
+  const turnPage = (key) => {
+    const viewer = document.getElementById("viewer");
+    if (viewer !== null) {
+      viewer.dispatchEvent(new KeyboardEvent("keyup", { key, bubbles: true }));
+    }
+  };
+
+  const distance = (touches) =>
+    Math.hypot(touches[0].clientX - touches[1].clientX, touches[0].clientY - touches[1].clientY);
+
+  const middle = (touches) => ({
+    x: (touches[0].clientX + touches[1].clientX) / 2,
+    y: (touches[0].clientY + touches[1].clientY) / 2,
+  });
+
+  document.addEventListener(
+    "touchstart",
+    (event) => {
+      if (getImage() === null) {
+        return;
+      }
+      if (event.touches.length === 1) {
+        swipe = { x: event.touches[0].clientX, y: event.touches[0].clientY };
+        pinch = null;
+      } else if (event.touches.length === 2) {
+        swipe = null;
+        pinch = { distance: distance(event.touches), middle: middle(event.touches), zoom };
+      }
+    },
+    { passive: true },
+  );
+  document.addEventListener(
+    "touchmove",
+    (event) => {
+      const image = getImage();
+      if (pinch === null || image === null || event.touches.length !== 2) {
+        return;
+      }
+      // Prevents the whole webview from being zoomed
+      event.preventDefault();
+      setZoom((pinch.zoom * distance(event.touches)) / pinch.distance);
+      const current = middle(event.touches);
+      image.parentElement.scrollBy(pinch.middle.x - current.x, pinch.middle.y - current.y);
+      pinch.middle = current;
+    },
+    { passive: false },
+  );
+  document.addEventListener("touchend", (event) => {
+    if (swipe === null || event.changedTouches.length !== 1) {
+      return;
+    }
+    const dx = event.changedTouches[0].clientX - swipe.x;
+    const dy = event.changedTouches[0].clientY - swipe.y;
+    swipe = null;
+    // A zoomed page is panned with one finger, not turned
+    if (zoom > MIN_ZOOM || Math.abs(dx) < SWIPE_DISTANCE || Math.abs(dx) < Math.abs(dy) * 1.5) {
+      return;
+    }
+    turnPage(dx < 0 ? "ArrowRight" : "ArrowLeft");
+  });
+
+  // Registered on the capture phase so the viewer doesn't turn the page on zoom and pan
+  window.addEventListener(
+    "wheel",
+    (event) => {
+      if (getImage() === null) {
+        return;
+      }
+      if (event.ctrlKey) {
+        event.preventDefault();
+        event.stopPropagation();
+        setZoom(zoom * (1 - event.deltaY * WHEEL_ZOOM_SPEED));
+      } else if (zoom > MIN_ZOOM) {
+        // The default behavior scrolls the zoomed page
+        event.stopPropagation();
+      }
+    },
+    { capture: true, passive: false },
+  );
+  document.addEventListener("keyup", (event) => {
+    if (event.key === "0") {
+      setZoom(MIN_ZOOM);
+    }
+  });
+})();
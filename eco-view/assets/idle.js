@@ -0,0 +1,26 @@
+// Eco Viewer idle detection
+//
+// In focus mode, the controls are hidden once the mouse and the keyboard have been idle for a while.
+// Implemented client side, so the mouse moves don't re-render the viewer.
+(() => {
+  // Delay, in milliseconds, after which the controls are hidden
+  const IDLE_DELAY = 2000;
+
+  let timeout = null;
+
+  const setIdle = (idle) => {
+    document.documentElement.classList.toggle("idle", idle);
+  };
+
+  const reset = () => {
+    setIdle(false);
+    clearTimeout(timeout);
+    timeout = setTimeout(() => setIdle(true), IDLE_DELAY);
+  };
+
+  // Turning the focus mode on, with a key or a click, restarts the delay as well
+  for (const event of ["mousemove", "mousedown", "keydown", "wheel", "touchstart"]) {
+    document.addEventListener(event, reset, { passive: true });
+  }
+  reset();
+})();
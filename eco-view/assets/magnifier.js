@@ -0,0 +1,95 @@
+// Eco Viewer magnifier lens
+//
+// Hold `m` or the middle mouse button to display a zoomed circle under the cursor.
+// Only the displayed image is magnified, the page zoom level is left untouched.
+(() => {
+  const ZOOM = 2.5;
+  const SIZE = 200;
+
+  let lens = null;
+  let active = false;
+  let cursor = { x: 0, y: 0 };
+
+  const getLens = () => {
+    if (lens === null) {
+      lens = document.createElement("div");
+      lens.style.cssText = [
+        "position: fixed",
+        "pointer-events: none",
+        "border-radius: 9999px",
+        "border: 2px solid rgba(255, 255, 255, 0.8)",
+        "box-shadow: 0 0 8px rgba(0, 0, 0, 0.6)",
+        "background-repeat: no-repeat",
+        "display: none",
+        "z-index: 50",
+        `width: ${SIZE}px`,
+        `height: ${SIZE}px`,
+      ].join(";");
+      document.body.appendChild(lens);
+    }
+    return lens;
+  };
+
+  const update = () => {
+    const lens = getLens();
+    const image = document.getElementById("page-image");
+    if (!active || image === null) {
+      lens.style.display = "none";
+      return;
+    }
+    // The page may be rotated around its center, the cursor is moved back to the unrotated image
+    const rect = image.getBoundingClientRect();
+    const width = image.offsetWidth;
+    const height = image.offsetHeight;
+    const transform = getComputedStyle(image).transform;
+    const matrix = transform === "none" ? new DOMMatrix() : new DOMMatrix(transform);
+    const point = matrix.inverse().transformPoint(
+      new DOMPoint(cursor.x - rect.left - rect.width / 2, cursor.y - rect.top - rect.height / 2),
+    );
+    const x = point.x + width / 2;
+    const y = point.y + height / 2;
+    if (x < 0 || y < 0 || x > width || y > height) {
+      lens.style.display = "none";
+      return;
+    }
+    lens.style.display = "block";
+    lens.style.left = `${cursor.x - SIZE / 2}px`;
+    lens.style.top = `${cursor.y - SIZE / 2}px`;
+    lens.style.backgroundImage = `url("${image.src}")`;
+    lens.style.backgroundSize = `${width * ZOOM}px ${height * ZOOM}px`;
+    lens.style.backgroundPosition = `${SIZE / 2 - x * ZOOM}px ${SIZE / 2 - y * ZOOM}px`;
+    // Rotated like the page, so the magnified part is displayed the same way
+    lens.style.transform = transform;
+  };
+
+  const setActive = (value) => {
+    active = value;
+    update();
+  };
+
+  document.addEventListener("mousemove", (event) => {
+    cursor = { x: event.clientX, y: event.clientY };
+    update();
+  });
+  document.addEventListener("mousedown", (event) => {
+    if (event.button === 1) {
+      event.preventDefault();
+      setActive(true);
+    }
+  });
+  document.addEventListener("mouseup", (event) => {
+    if (event.button === 1) {
+      setActive(false);
+    }
+  });
+  document.addEventListener("keydown", (event) => {
+    if (event.key === "m" && !event.repeat) {
+      setActive(true);
+    }
+  });
+  document.addEventListener("keyup", (event) => {
+    if (event.key === "m") {
+      setActive(false);
+    }
+  });
+})();
@@ -0,0 +1,61 @@
+use std::collections::{HashMap, VecDeque};
+
+/// Maximum amount of pages kept in memory
+pub static CACHE_SIZE: usize = 32;
+
+/// Keeps the content of the recently loaded pages,
+/// the least recently used pages are evicted first when the cache is full.
+#[derive(Debug)]
+pub struct PageCache {
+    capacity: usize,
+    pages: HashMap<usize, String>,
+    recently_used: VecDeque<usize>,
+}
+
+impl PageCache {
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            pages: HashMap::with_capacity(capacity),
+            recently_used: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    #[must_use]
+    pub fn get(&self, page: usize) -> Option<&String> {
+        self.pages.get(&page)
+    }
+
+    #[must_use]
+    pub fn contains(&self, page: usize) -> bool {
+        self.pages.contains_key(&page)
+    }
+
+    /// Marks the page as recently used
+    pub fn touch(&mut self, page: usize) {
+        if let Some(position) = self.recently_used.iter().position(|p| *p == page) {
+            self.recently_used.remove(position);
+            self.recently_used.push_back(page);
+        }
+    }
+
+    pub fn insert(&mut self, page: usize, content: String) {
+        if self.pages.insert(page, content).is_some() {
+            self.touch(page);
+            return;
+        }
+        self.recently_used.push_back(page);
+        while self.recently_used.len() > self.capacity {
+            if let Some(evicted_page) = self.recently_used.pop_front() {
+                self.pages.remove(&evicted_page);
+            }
+        }
+    }
+}
+
+impl Default for PageCache {
+    fn default() -> Self {
+        Self::new(CACHE_SIZE)
+    }
+}
@@ -0,0 +1,73 @@
+use dioxus::prelude::*;
+
+use crate::transform::ColorAdjustments;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props, PartialEq)]
+pub struct AdjustmentsPanelProps {
+    adjustments: UseState<ColorAdjustments>,
+}
+
+/// Sliders adjusting the pages colors live
+pub fn AdjustmentsPanel(cx: Scope<AdjustmentsPanelProps>) -> Element {
+    let adjustments = &cx.props.adjustments;
+    let ColorAdjustments {
+        contrast,
+        brightness,
+        invert,
+    } = *adjustments.get();
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-row flex-wrap items-center justify-center gap-1 px-2 text-sm",
+            label {
+                class: "flex flex-row items-center gap-1",
+                "Contrast {contrast}",
+                input {
+                    r#type: "range",
+                    min: "-100",
+                    max: "100",
+                    value: "{contrast}",
+                    oninput: move |evt| {
+                        let Ok(contrast) = evt.value.parse::<f32>() else {
+                            return;
+                        };
+                        adjustments.modify(|adjustments| ColorAdjustments { contrast, ..*adjustments });
+                    },
+                },
+            },
+            label {
+                class: "flex flex-row items-center gap-1",
+                "Brightness {brightness}",
+                input {
+                    r#type: "range",
+                    min: "-255",
+                    max: "255",
+                    value: "{brightness}",
+                    oninput: move |evt| {
+                        let Ok(brightness) = evt.value.parse::<i32>() else {
+                            return;
+                        };
+                        adjustments.modify(|adjustments| ColorAdjustments { brightness, ..*adjustments });
+                    },
+                },
+            },
+            label {
+                class: "flex flex-row items-center gap-1",
+                input {
+                    r#type: "checkbox",
+                    checked: "{invert}",
+                    oninput: move |_evt| {
+                        adjustments.modify(|adjustments| ColorAdjustments { invert: !adjustments.invert, ..*adjustments });
+                    },
+                },
+                "Invert",
+            },
+            button {
+                class: "btn btn-outline-primary btn-sm",
+                onclick: move |_evt| adjustments.set(ColorAdjustments::default()),
+                "Reset"
+            },
+        }
+    })
+}
@@ -0,0 +1,38 @@
+use dioxus::prelude::*;
+use eco_cbz::Bookmark;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct ChaptersPanelProps<'a> {
+    chapters: &'a [Bookmark],
+    current_page: usize,
+    /// Called with the first page of the selected chapter, starting at 1
+    on_select: EventHandler<'a, usize>,
+}
+
+/// Lists the chapters bookmarked in the document, the chapter of the current page is highlighted
+pub fn ChaptersPanel<'a>(cx: Scope<'a, ChaptersPanelProps<'a>>) -> Element<'a> {
+    let current_index = cx
+        .props
+        .chapters
+        .iter()
+        .rposition(|chapter| chapter.page < cx.props.current_page);
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col w-full px-2 overflow-auto max-h-40 text-sm",
+            cx.props.chapters.iter().enumerate().map(|(index, chapter)| {
+                let page = chapter.page + 1;
+                let class = if Some(index) == current_index { "font-bold" } else { "" };
+                rsx! {
+                    button {
+                        key: "{index}",
+                        class: "text-left px-2 rounded-sm {class}",
+                        onclick: move |_evt| cx.props.on_select.call(page),
+                        "{chapter.title} (page {page})"
+                    }
+                }
+            })
+        }
+    })
+}
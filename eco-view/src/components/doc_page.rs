@@ -1,28 +1,63 @@
+use base64::Engine;
 use dioxus::prelude::*;
+use eco_cbz::markup::inject_in_head;
 
-use crate::doc::{Doc, SharedDoc};
+use crate::{
+    doc::ContentType,
+    search::highlight_script,
+    settings::{FitMode, NightMode},
+    transform::{ColorAdjustments, PageTransform},
+};
 
 #[allow(clippy::module_name_repetitions)]
 #[derive(Props)]
 pub struct DocPageProps<'a> {
-    doc: SharedDoc,
+    content_type: ContentType,
     content: &'a str,
+    fit_mode: FitMode,
+    transform: PageTransform,
+    adjustments: ColorAdjustments,
+    night_mode: NightMode,
+    /// User style injected in html pages
+    epub_css: &'a str,
+    /// Term highlighted in html pages
+    highlight: Option<&'a str>,
 }
 
 pub fn DocPage<'a, 'b: 'a>(cx: Scope<'a, DocPageProps<'b>>) -> Element<'a> {
     let content = cx.props.content;
+    let img_class = cx.props.fit_mode.img_class();
+    let transform_style = cx.props.transform.style();
+    let adjustments_style = cx.props.adjustments.style(cx.props.night_mode);
 
-    match *cx.props.doc.lock().unwrap() {
-        Doc::Cbz { .. } => cx.render(rsx!(img {
-            class: "h-px grow",
-            src: "data:image/png;base64,{content}"
-        })),
-        Doc::Epub { .. } => cx.render(rsx!(div {
-            class: "h-px grow spect-[12/16]",
-            iframe {
-                class: "h-full w-full",
-                src: "data:text/html;charset=utf-8,{content}"
+    match cx.props.content_type {
+        ContentType::Image => cx.render(rsx!(div {
+            class: "h-px grow w-full flex overflow-auto",
+            img {
+                // Used by the magnifier script
+                id: "page-image",
+                class: "m-auto {img_class}",
+                // The zoom is set by the gestures script
+                style: "zoom: var(--page-zoom, 1); {transform_style} {adjustments_style}",
+                src: "data:image/png;base64,{content}"
             }
         })),
+        ContentType::Html => {
+            let mut head = format!("<style>{}</style>", cx.props.epub_css);
+            if let Some(highlight) = cx.props.highlight {
+                head.push_str(&highlight_script(highlight));
+            }
+            // Encoded so the characters that are meaningful in urls (`#`, `%`) are preserved
+            let content =
+                base64::engine::general_purpose::STANDARD.encode(inject_in_head(content, &head));
+            cx.render(rsx!(div {
+                class: "h-px grow w-full",
+                iframe {
+                    class: "h-full w-full",
+                    style: "{transform_style} {adjustments_style}",
+                    src: "data:text/html;charset=utf-8;base64,{content}"
+                }
+            }))
+        }
     }
 }
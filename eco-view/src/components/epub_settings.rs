@@ -0,0 +1,107 @@
+use dioxus::prelude::*;
+
+use crate::{
+    settings::{EpubStyle, Settings},
+    update_settings,
+};
+
+static FONT_FAMILIES: [(&str, &str); 4] = [
+    ("", "Book font"),
+    ("serif", "Serif"),
+    ("sans-serif", "Sans serif"),
+    ("monospace", "Monospace"),
+];
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct EpubSettingsPanelProps<'a> {
+    settings: &'a UseRef<Settings>,
+}
+
+/// Controls the user style injected in the epub pages
+pub fn EpubSettingsPanel<'a>(cx: Scope<'a, EpubSettingsPanelProps<'a>>) -> Element<'a> {
+    let settings = cx.props.settings;
+    let EpubStyle {
+        font_size,
+        line_height,
+        font_family,
+        margin,
+    } = settings.read().epub_style.clone();
+    let font_family = font_family.unwrap_or_default();
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-row flex-wrap items-center justify-center gap-1 px-2 text-sm",
+            button {
+                class: "btn btn-outline-primary btn-sm",
+                title: "Decrease font size",
+                onclick: move |_evt| {
+                    update_settings(settings, |settings| {
+                        settings.epub_style.font_size = settings.epub_style.font_size.saturating_sub(10).max(EpubStyle::MIN_FONT_SIZE);
+                    });
+                },
+                "A-"
+            },
+            span { "{font_size}%" },
+            button {
+                class: "btn btn-outline-primary btn-sm",
+                title: "Increase font size",
+                onclick: move |_evt| {
+                    update_settings(settings, |settings| {
+                        settings.epub_style.font_size = (settings.epub_style.font_size + 10).min(EpubStyle::MAX_FONT_SIZE);
+                    });
+                },
+                "A+"
+            },
+            label {
+                class: "flex flex-row items-center gap-1",
+                "Line height",
+                input {
+                    class: "h-8 w-16 px-2 rounded-sm border",
+                    r#type: "number",
+                    min: "1",
+                    max: "3",
+                    step: "0.1",
+                    value: "{line_height}",
+                    oninput: move |evt| {
+                        let Ok(line_height) = evt.value.parse::<f32>() else {
+                            return;
+                        };
+                        update_settings(settings, |settings| settings.epub_style.line_height = line_height);
+                    },
+                },
+            },
+            label {
+                class: "flex flex-row items-center gap-1",
+                "Margins",
+                input {
+                    class: "h-8 w-16 px-2 rounded-sm border",
+                    r#type: "number",
+                    min: "0",
+                    max: "10",
+                    value: "{margin}",
+                    oninput: move |evt| {
+                        let Ok(margin) = evt.value.parse::<u16>() else {
+                            return;
+                        };
+                        update_settings(settings, |settings| settings.epub_style.margin = margin);
+                    },
+                },
+            },
+            select {
+                class: "h-8 px-2 rounded-sm border",
+                value: "{font_family}",
+                onchange: move |evt| {
+                    let font_family = (!evt.value.is_empty()).then(|| evt.value.clone());
+                    update_settings(settings, |settings| settings.epub_style.font_family = font_family);
+                },
+                FONT_FAMILIES.iter().map(|(value, label)| rsx!(option {
+                    key: "{value}",
+                    value: "{value}",
+                    selected: *value == font_family,
+                    "{label}"
+                }))
+            },
+        }
+    })
+}
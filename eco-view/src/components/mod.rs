@@ -1 +1,9 @@
+pub mod adjustments;
+pub mod chapters;
 pub mod doc_page;
+pub mod epub_settings;
+pub mod notes;
+pub mod search;
+pub mod stats;
+pub mod tab_bar;
+pub mod thumbnail_grid;
@@ -0,0 +1,74 @@
+use dioxus::prelude::*;
+use tracing::error;
+
+use crate::tabs::Tabs;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct NotesPanelProps<'a> {
+    tabs: &'a UseRef<Tabs>,
+    /// Called with the page of the selected note
+    on_select: EventHandler<'a, usize>,
+}
+
+/// Edits the note of the current page, and lists the notes of the document
+pub fn NotesPanel<'a>(cx: Scope<'a, NotesPanelProps<'a>>) -> Element<'a> {
+    let tabs = cx.props.tabs;
+    let (current_page, note, notes) = tabs.with(|tabs| {
+        let tab = tabs.active();
+        (
+            tab.current_page,
+            tab.notes
+                .get(tab.current_page)
+                .unwrap_or_default()
+                .to_string(),
+            tab.notes.pages.clone(),
+        )
+    });
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col items-center gap-1 px-2 w-full text-sm",
+            textarea {
+                class: "w-full max-w-xl px-2 rounded-sm border",
+                rows: "2",
+                placeholder: "Note for the page {current_page}",
+                value: "{note}",
+                oninput: move |evt| {
+                    tabs.with_mut(|tabs| {
+                        let tab = tabs.active_mut();
+                        tab.notes.set(tab.current_page, evt.value.clone());
+                    });
+                },
+                // Written once the edition is done rather than on each key stroke
+                onchange: move |_evt| {
+                    tabs.with(|tabs| {
+                        let tab = tabs.active();
+                        if let Err(err) = tab.notes.save(&tab.doc.path) {
+                            error!("notes couldn't be saved: {err}");
+                        }
+                    });
+                },
+                // Prevents the page navigation while typing
+                onkeyup: move |evt| evt.stop_propagation(),
+            }
+            div {
+                class: "flex flex-col w-full overflow-auto max-h-40",
+                if notes.is_empty() {
+                    rsx!(span { "No notes" })
+                }
+                notes.into_iter().map(|(page, note)| {
+                    let class = if page == current_page { "font-bold" } else { "" };
+                    rsx! {
+                        button {
+                            key: "{page}",
+                            class: "text-left px-2 rounded-sm {class}",
+                            onclick: move |_evt| cx.props.on_select.call(page),
+                            "Page {page}: {note}"
+                        }
+                    }
+                })
+            }
+        }
+    })
+}
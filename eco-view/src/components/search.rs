@@ -0,0 +1,96 @@
+use camino::Utf8PathBuf;
+use dioxus::prelude::*;
+use tracing::error;
+
+use crate::search::{search_epub, SearchMatch};
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct SearchPanelProps<'a> {
+    path: Utf8PathBuf,
+    /// Called with the page and the searched term when a match is selected
+    on_select: EventHandler<'a, (usize, String)>,
+}
+
+/// Searches the text of the epub and lists the matches
+pub fn SearchPanel<'a>(cx: Scope<'a, SearchPanelProps<'a>>) -> Element<'a> {
+    let query = use_state(cx, String::new);
+    let searched_query = use_state(cx, String::new);
+    let matches = use_state(cx, || None::<Vec<SearchMatch>>);
+    let searching = use_state(cx, || false);
+
+    let search = move || {
+        if *searching.get() {
+            return;
+        }
+        searching.set(true);
+        searched_query.set(query.get().clone());
+        let path = cx.props.path.clone();
+        to_owned![query, matches, searching];
+        cx.spawn(async move {
+            let query = query.current().as_ref().clone();
+            match tokio::task::spawn_blocking(move || search_epub(&path, &query)).await {
+                Ok(Ok(found_matches)) => matches.set(Some(found_matches)),
+                Ok(Err(err)) => error!("search failed: {err}"),
+                Err(err) => error!("search task failed: {err}"),
+            }
+            searching.set(false);
+        });
+    };
+
+    let search_label = if *searching.get() {
+        "Searching..."
+    } else {
+        "Search"
+    };
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col items-center gap-1 px-2 w-full text-sm",
+            div {
+                class: "flex flex-row items-center justify-center gap-1",
+                input {
+                    class: "h-8 px-2 rounded-sm border",
+                    r#type: "search",
+                    placeholder: "Search",
+                    value: "{query}",
+                    oninput: move |evt| query.set(evt.value.clone()),
+                    // Prevents the page navigation while typing
+                    onkeyup: move |evt| evt.stop_propagation(),
+                    onkeydown: move |evt| {
+                        if evt.key() == dioxus::html::input_data::keyboard_types::Key::Enter {
+                            search();
+                        }
+                    },
+                },
+                button {
+                    class: "btn btn-outline-primary btn-sm",
+                    disabled: *searching.get(),
+                    onclick: move |_evt| search(),
+                    "{search_label}"
+                },
+            }
+            if let Some(matches) = matches.get() {
+                rsx! {
+                    div {
+                        class: "flex flex-col w-full overflow-auto max-h-40",
+                        if matches.is_empty() {
+                            rsx!(span { "No matches" })
+                        }
+                        matches.iter().enumerate().map(|(index, search_match)| {
+                            let page = search_match.page;
+                            rsx! {
+                                button {
+                                    key: "{index}",
+                                    class: "text-left px-2 rounded-sm",
+                                    onclick: move |_evt| cx.props.on_select.call((page, searched_query.get().clone())),
+                                    "Page {page}: {search_match.excerpt}"
+                                }
+                            }
+                        })
+                    }
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,78 @@
+use dioxus::prelude::*;
+use eco_cbz::sort::cmp_paths;
+
+use crate::{
+    stats::{format_duration, ReadingStats},
+    tabs::Tabs,
+};
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct StatsPanelProps<'a> {
+    tabs: &'a UseRef<Tabs>,
+}
+
+/// Reading statistics of the current document, and of the documents read in the same folder
+pub fn StatsPanel<'a>(cx: Scope<'a, StatsPanelProps<'a>>) -> Element<'a> {
+    // Loaded once when the panel is shown, the current session is added on top
+    let stats = use_ref(cx, ReadingStats::load);
+    let (path, max_page, session) = cx.props.tabs.with(|tabs| {
+        let tab = tabs.active();
+        (
+            tab.doc.path.clone(),
+            tab.doc.max_page,
+            tab.session.session(),
+        )
+    });
+    let session_duration = format_duration(session.duration);
+    let session_pages_read = session.pages_read;
+    let mut files = stats.read().files.clone();
+    files
+        .entry(path.clone())
+        .or_default()
+        .push(max_page, session);
+    let file_stats = files[&path].clone();
+    let sessions = file_stats.session_count();
+    let duration = format_duration(file_stats.duration());
+    let pages_read = file_stats.pages_read();
+    let furthest_page = file_stats.furthest_page;
+    let progress = file_stats.progress();
+
+    let mut folder = files
+        .into_iter()
+        .filter(|(file_path, _)| file_path.parent() == path.parent())
+        .collect::<Vec<_>>();
+    folder.sort_by(|(a, _), (b, _)| cmp_paths(a.as_str(), b.as_str()));
+    let finished = folder
+        .iter()
+        .filter(|(_, file_stats)| file_stats.is_finished())
+        .count();
+    let count = folder.len();
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-col items-center gap-1 px-2 text-sm",
+            span { "This session: {session_pages_read} pages in {session_duration}" }
+            span {
+                "This document: page {furthest_page} / {max_page} reached ({progress}%), \
+                {pages_read} pages read in {duration} over {sessions} sessions"
+            }
+            span { "This folder: {finished} / {count} documents finished" }
+            div {
+                class: "flex flex-row flex-wrap items-center justify-center gap-1",
+                folder.into_iter().map(|(file_path, file_stats)| {
+                    let name = file_path.file_name().unwrap_or(file_path.as_str()).to_string();
+                    let progress = file_stats.progress();
+                    let class = if file_path == path { "font-bold" } else { "" };
+                    rsx!(
+                        span {
+                            key: "{file_path}",
+                            class: "px-2 rounded-sm border {class}",
+                            "{name} {progress}%"
+                        }
+                    )
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,50 @@
+use dioxus::prelude::*;
+
+use crate::tabs::Tabs;
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct TabBarProps<'a> {
+    tabs: &'a UseRef<Tabs>,
+}
+
+/// Lists the opened documents, the active one is highlighted
+pub fn TabBar<'a>(cx: Scope<'a, TabBarProps<'a>>) -> Element<'a> {
+    let tabs = cx.props.tabs;
+    let (names, active, closable) = tabs.with(|tabs| {
+        let names = tabs.iter().map(|tab| tab.doc.name()).collect::<Vec<_>>();
+        (names, tabs.active_index(), tabs.count() > 1)
+    });
+
+    cx.render(rsx! {
+        div {
+            class: "flex flex-row items-center gap-1 px-2 w-full shrink-0 overflow-x-auto text-sm",
+            names.into_iter().enumerate().map(|(index, name)| {
+                let class = if index == active {
+                    "btn btn-primary btn-sm"
+                } else {
+                    "btn btn-outline-primary btn-sm"
+                };
+                rsx!(
+                    div {
+                        key: "{index}",
+                        class: "flex flex-row items-center gap-1",
+                        button {
+                            class: class,
+                            onclick: move |_evt| tabs.with_mut(|tabs| tabs.select(index)),
+                            "{name}"
+                        },
+                        if closable {
+                            rsx!(button {
+                                class: "btn btn-outline-primary btn-sm",
+                                title: "Close tab",
+                                onclick: move |_evt| tabs.with_mut(|tabs| tabs.close(index)),
+                                "x"
+                            })
+                        }
+                    }
+                )
+            })
+        }
+    })
+}
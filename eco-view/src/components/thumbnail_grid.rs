@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use dioxus::prelude::*;
+
+use crate::{doc::THUMBNAIL_SIZE, tabs::Tabs};
+
+/// Renders the grid again, set while the grid is shown so the loaded thumbnails don't render the whole viewer
+pub type ThumbnailsListener = Option<Arc<dyn Fn() + Send + Sync>>;
+
+/// Unregisters the grid once it's closed
+struct ListenerGuard(UseRef<ThumbnailsListener>);
+
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        *self.0.write_silent() = None;
+    }
+}
+
+#[allow(clippy::module_name_repetitions)]
+#[derive(Props)]
+pub struct ThumbnailGridProps<'a> {
+    tabs: &'a UseRef<Tabs>,
+    listener: &'a UseRef<ThumbnailsListener>,
+    background_color: &'a str,
+    /// Called with the page when a thumbnail is selected
+    on_select: EventHandler<'a, usize>,
+    on_close: EventHandler<'a, ()>,
+}
+
+/// Covers the window with the thumbnails of all the pages, to jump to any page at once.
+/// The thumbnails are loaded when the grid is first shown, and kept in the tab afterward.
+pub fn ThumbnailGrid<'a>(cx: Scope<'a, ThumbnailGridProps<'a>>) -> Element<'a> {
+    let tabs = cx.props.tabs;
+    let (doc_id, current_page, thumbnails) = tabs.with(|tabs| {
+        let tab = tabs.active();
+        let thumbnails = (1..=tab.doc.max_page)
+            .map(|page| (page, tab.thumbnails.get(&page).cloned()))
+            .collect::<Vec<_>>();
+        (tab.doc.id, tab.current_page, thumbnails)
+    });
+
+    cx.use_hook(|| {
+        *cx.props.listener.write_silent() = Some(cx.schedule_update());
+        ListenerGuard(cx.props.listener.clone())
+    });
+
+    // Requested again when another tab is selected
+    use_effect(cx, (&doc_id,), |_| {
+        to_owned![tabs];
+        async move { tabs.read().active().request_thumbnails() }
+    });
+
+    cx.render(rsx! {
+        div {
+            class: "fixed inset-0 z-40 flex flex-col gap-1 p-2",
+            style: "background-color: {cx.props.background_color};",
+            div {
+                class: "flex flex-row items-center justify-center gap-1 shrink-0",
+                span { "Go to page" }
+                button {
+                    class: "btn btn-outline-primary btn-sm",
+                    title: "Close the thumbnails (Escape)",
+                    onclick: move |_evt| cx.props.on_close.call(()),
+                    "Close"
+                },
+            }
+            div {
+                class: "grid gap-1 overflow-auto",
+                style: "grid-template-columns: repeat(auto-fill, minmax({THUMBNAIL_SIZE}px, 1fr));",
+                thumbnails.into_iter().map(|(page, thumbnail)| {
+                    let class = if page == current_page { "btn-primary" } else { "" };
+                    rsx!(
+                        button {
+                            key: "{page}",
+                            class: "flex flex-col items-center gap-1 p-1 rounded-sm border {class}",
+                            onclick: move |_evt| cx.props.on_select.call(page),
+                            if let Some(thumbnail) = thumbnail {
+                                rsx!(img {
+                                    class: "h-40 w-auto object-contain",
+                                    src: "data:image/png;base64,{thumbnail}",
+                                })
+                            } else {
+                                rsx!(div {
+                                    class: "h-40 flex items-center justify-center",
+                                    div { class: "spinner-simple" }
+                                })
+                            }
+                            span { class: "text-sm", "{page}" }
+                        }
+                    )
+                })
+            }
+        }
+    })
+}
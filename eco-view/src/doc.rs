@@ -1,22 +1,29 @@
 use std::{
-    fs::File,
+    fs::{self, File},
     io::BufReader,
     str::FromStr,
-    sync::{Arc, Mutex},
 };
 
 use base64::Engine;
-use camino::Utf8Path;
-use eco_cbz::CbzReader;
+use camino::{Utf8Path, Utf8PathBuf};
+use eco_cbz::{cbz::is_page_file, sort::cmp_paths, Bookmark, CbzReader, Image};
 use tl::{HTMLTag, ParserOptions, VDom};
 use tracing::debug;
+use unrar::Archive as RarArchive;
 
 use crate::errors::{Error, Result};
 
+/// Maximum width and height of the thumbnails, in pixels
+pub static THUMBNAIL_SIZE: u32 = 160;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     Cbz,
+    Cbr,
     EPub,
+    Mobi,
+    Azw3,
+    Pdf,
 }
 
 impl FromStr for FileType {
@@ -25,72 +32,127 @@ impl FromStr for FileType {
     fn from_str(s: &str) -> Result<Self> {
         match s.to_lowercase().as_str() {
             "cbz" => Ok(FileType::Cbz),
+            "cbr" => Ok(FileType::Cbr),
             "epub" => Ok(FileType::EPub),
+            "mobi" => Ok(FileType::Mobi),
+            "azw3" => Ok(FileType::Azw3),
+            "pdf" => Ok(FileType::Pdf),
             _ => Err(Error::InvalidFileType(s.to_string())),
         }
     }
 }
 
+/// The document following the provided one in its directory, by natural order.
+/// Only the files the viewer can open are considered.
+#[must_use]
+pub fn next_doc_path(path: &Utf8Path) -> Option<Utf8PathBuf> {
+    let entries = path.parent()?.read_dir_utf8().ok()?;
+    entries
+        .filter_map(std::result::Result::ok)
+        .map(camino::Utf8DirEntry::into_path)
+        .filter(|entry| {
+            entry.is_file()
+                && entry
+                    .extension()
+                    .is_some_and(|ext| ext.parse::<FileType>().is_ok())
+        })
+        .filter(|entry| cmp_paths(entry.as_str(), path.as_str()).is_gt())
+        .min_by(|a, b| cmp_paths(a.as_str(), b.as_str()))
+}
+
+/// How the content of a page must be displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentType {
+    /// Base64 encoded image
+    Image,
+    Html,
+}
+
 pub enum Doc {
     Cbz {
         archive: CbzReader<File>,
         max_page: usize,
-        file_names: Vec<String>,
-        pages: Vec<String>,
+    },
+    /// Documents that can't be read lazily, all their images are extracted in memory when loaded.
+    /// Rar archives are often solid and can't be read randomly,
+    /// while mobi, azw3, and pdf files need to be converted first.
+    Images {
+        images: Vec<Vec<u8>>,
+        max_page: usize,
     },
     Epub {
         doc: epub::doc::EpubDoc<BufReader<File>>,
         max_page: usize,
-        pages: Vec<String>,
     },
 }
 
 impl Doc {
+    /// The password is only used by the cbz and cbr archives
+    ///
     /// ## Errors
-    pub fn try_load_from_path(type_: FileType, path: &Utf8Path) -> Result<Doc> {
+    pub fn try_load_from_path(
+        type_: FileType,
+        path: &Utf8Path,
+        password: Option<&str>,
+    ) -> Result<Doc> {
         match type_ {
             FileType::Cbz => {
-                let archive = CbzReader::try_from_path(path)?;
-                let file_names = archive.file_names();
-                let max_page = file_names.len();
-                Ok(Doc::Cbz {
-                    archive,
-                    file_names,
-                    max_page,
-                    pages: Vec::with_capacity(max_page),
-                })
+                let mut archive = CbzReader::try_from_path(path)?;
+                if let Some(password) = password {
+                    archive = archive.with_password(password);
+                }
+                let max_page = archive.len();
+                Ok(Doc::Cbz { archive, max_page })
+            }
+            FileType::Cbr => {
+                let images = read_rar_images(path, password)?;
+                let max_page = images.len();
+                Ok(Doc::Images { images, max_page })
+            }
+            FileType::Mobi | FileType::Azw3 | FileType::Pdf => {
+                let images = if type_ == FileType::Pdf {
+                    eco_convert::pdf_to_imgs(path, &eco_convert::PdfOptions::default())?
+                } else {
+                    eco_convert::mobi_to_imgs(path)?
+                };
+                let images = images
+                    .into_iter()
+                    .map(Image::try_into_bytes)
+                    .collect::<Result<Vec<_>, _>>()?;
+                let max_page = images.len();
+                Ok(Doc::Images { images, max_page })
             }
             FileType::EPub => {
                 let doc = epub::doc::EpubDoc::new(path)?;
                 let max_page = doc.get_num_pages();
-                Ok(Doc::Epub {
-                    doc,
-                    max_page,
-                    pages: Vec::with_capacity(max_page),
-                })
+                Ok(Doc::Epub { doc, max_page })
             }
         }
     }
 
+    /// Loads the page content, pages start at 1.
+    /// The uniform margins of the images are trimmed if `crop_margins` is set.
+    ///
     /// ## Errors
-    pub fn load_page(&mut self, page: usize) -> Result<()> {
+    pub fn load_page(&mut self, page: usize, crop_margins: bool) -> Result<String> {
         match self {
-            Self::Cbz {
-                archive,
-                file_names,
-                pages,
-                ..
-            } => {
-                let Some(file_name) = file_names.get(page - 1) else {
+            Self::Cbz { archive, max_page } => {
+                if page == 0 || page > *max_page {
                     return Err(Error::PageNotFound(page));
-                };
-                let mut image = archive.raw_read_by_name(file_name.as_str())?;
+                }
+                let mut image = archive.raw_read_by_index(page - 1)?;
                 #[allow(clippy::cast_possible_truncation)]
                 let mut bytes = Vec::with_capacity(image.size() as usize);
                 std::io::copy(&mut image, &mut bytes)?;
-                pages.push(base64::engine::general_purpose::STANDARD.encode(bytes));
+                encode_image(&bytes, crop_margins)
             }
-            Self::Epub { doc, pages, .. } => {
+            Self::Images { images, .. } => {
+                let Some(bytes) = images.get(page - 1) else {
+                    return Err(Error::PageNotFound(page));
+                };
+                encode_image(bytes, crop_margins)
+            }
+            Self::Epub { doc, .. } => {
                 doc.set_current_page(page - 1);
                 let Some(content) = doc.get_current_with_epub_uris().ok() else {
                     return Err(Error::PageNotFound(page));
@@ -112,25 +174,179 @@ impl Doc {
                     }
                     Ok(())
                 })?;
-                pages.push(dom.outer_html());
+                Ok(dom.outer_html())
             }
         }
-        Ok(())
+    }
+
+    /// Loads a small version of the page image, encoded in base64.
+    /// The uniform margins are trimmed if `crop_margins` is set, like the displayed page.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the page can't be read or is an html page
+    pub fn load_thumbnail(&mut self, page: usize, crop_margins: bool) -> Result<String> {
+        let image = match self {
+            Self::Cbz { archive, max_page } => {
+                if page == 0 || page > *max_page {
+                    return Err(Error::PageNotFound(page));
+                }
+                archive.read_by_index(page - 1)?
+            }
+            Self::Images { images, .. } => {
+                let Some(bytes) = images.get(page - 1) else {
+                    return Err(Error::PageNotFound(page));
+                };
+                Image::try_from_bytes(bytes)?
+            }
+            Self::Epub { .. } => return Err(Error::NotAnImage(page)),
+        };
+        let image = if crop_margins {
+            image.trim_margins()
+        } else {
+            image
+        };
+        let thumbnail = Image::from(image.dynamic().thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+
+        Ok(base64::engine::general_purpose::STANDARD.encode(thumbnail.try_into_bytes()?))
+    }
+
+    /// Writes the original bytes of the page image to the path,
+    /// the extension matching the image format is added if the path has none.
+    /// Returns the path the page was written to.
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the page can't be read or written, or is an html page
+    pub fn export_page(&mut self, page: usize, path: &Utf8Path) -> Result<Utf8PathBuf> {
+        let (bytes, extension) = match self {
+            Self::Cbz { archive, max_page } => {
+                if page == 0 || page > *max_page {
+                    return Err(Error::PageNotFound(page));
+                }
+                let mut image = archive.raw_read_by_index(page - 1)?;
+                let extension = Utf8Path::new(image.name())
+                    .extension()
+                    .map(ToString::to_string);
+                #[allow(clippy::cast_possible_truncation)]
+                let mut bytes = Vec::with_capacity(image.size() as usize);
+                std::io::copy(&mut image, &mut bytes)?;
+                (bytes, extension)
+            }
+            Self::Images { images, .. } => {
+                let Some(bytes) = images.get(page - 1) else {
+                    return Err(Error::PageNotFound(page));
+                };
+                // The images extracted from rar archives, mobi, azw3, and pdf files have no name
+                let extension = Image::try_from_bytes(bytes)?
+                    .format()
+                    .and_then(|format| format.extensions_str().first())
+                    .map(ToString::to_string);
+                (bytes.clone(), extension)
+            }
+            Self::Epub { .. } => return Err(Error::NotAnImage(page)),
+        };
+        let path = match (path.extension(), extension) {
+            (None, Some(extension)) => path.with_extension(extension),
+            _ => path.to_path_buf(),
+        };
+        fs::write(&path, bytes)?;
+
+        Ok(path)
     }
 
     #[must_use]
-    pub fn content_for_page(&self, page: usize) -> Option<String> {
+    pub fn content_type(&self) -> ContentType {
         match self {
-            Self::Cbz { pages, .. } | Self::Epub { pages, .. } => pages.get(page - 1).cloned(),
+            Self::Cbz { .. } | Self::Images { .. } => ContentType::Image,
+            Self::Epub { .. } => ContentType::Html,
         }
     }
 
     #[must_use]
     pub fn max_page(&self) -> usize {
         match self {
-            Self::Cbz { max_page, .. } | Self::Epub { max_page, .. } => *max_page,
+            Self::Cbz { max_page, .. }
+            | Self::Images { max_page, .. }
+            | Self::Epub { max_page, .. } => *max_page,
+        }
+    }
+
+    /// The chapters bookmarked in the `ComicInfo.xml` of the cbz archives
+    pub fn bookmarks(&mut self) -> Vec<Bookmark> {
+        let Self::Cbz { archive, .. } = self else {
+            return Vec::new();
+        };
+        archive.bookmarks().unwrap_or_else(|err| {
+            debug!("couldn't read the bookmarks: {err}");
+            Vec::new()
+        })
+    }
+
+    /// The width and height of the pages, in reading order.
+    /// Only known for the cbz archives embedding a manifest, the pages aren't read to get them.
+    pub fn page_dimensions(&mut self) -> Vec<(u32, u32)> {
+        let Self::Cbz { archive, max_page } = self else {
+            return Vec::new();
+        };
+        let mut pages = match archive.manifest() {
+            Ok(Some(manifest)) => manifest.pages,
+            Ok(None) => return Vec::new(),
+            Err(err) => {
+                debug!("couldn't read the manifest: {err}");
+                return Vec::new();
+            }
+        };
+        if pages.len() != *max_page {
+            debug!("the manifest doesn't list all the pages, the dimensions are ignored");
+            return Vec::new();
         }
+        pages.sort_by(|page, other| cmp_paths(&page.name, &other.name));
+
+        pages
+            .into_iter()
+            .map(|page| (page.width, page.height))
+            .collect()
+    }
+}
+
+/// Encodes the image in base64, once its margins are trimmed if requested.
+/// The image is only decoded when its margins are trimmed.
+fn encode_image(bytes: &[u8], crop_margins: bool) -> Result<String> {
+    if crop_margins {
+        let bytes = Image::try_from_bytes(bytes)?
+            .trim_margins()
+            .try_into_bytes()?;
+        return Ok(base64::engine::general_purpose::STANDARD.encode(bytes));
+    }
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// Extracts all the images contained in the rar archive, sorted like the pages of a cbz.
+/// Directories, hidden files, and sidecars (like `ComicInfo.xml`) are ignored.
+fn read_rar_images(path: &Utf8Path, password: Option<&str>) -> Result<Vec<Vec<u8>>> {
+    let mut images = Vec::new();
+    let archive = match password {
+        Some(password) => RarArchive::with_password(path.as_str(), password),
+        None => RarArchive::new(path.as_str()),
+    };
+    let mut archive = archive.open_for_processing()?;
+    while let Some(header) = archive.read_header()? {
+        let entry = header.entry();
+        let file_name = entry.filename.to_string_lossy().replace('\\', "/");
+        archive = if entry.is_file() && is_page_file(&file_name) {
+            let (bytes, archive) = header.read()?;
+            images.push((file_name, bytes));
+            archive
+        } else {
+            debug!("skipping rar entry {:?}", entry.filename);
+            header.skip()?
+        };
     }
+    images.sort_by(|(name, _), (other, _)| cmp_paths(name, other));
+
+    Ok(images.into_iter().map(|(_, bytes)| bytes).collect())
 }
 
 fn try_for_each_tag_mut<F>(dom: &mut VDom, selector: &str, mut f: F) -> Result<()>
@@ -155,16 +371,3 @@ where
 
     Ok(())
 }
-
-#[allow(clippy::module_name_repetitions)]
-pub type SharedDoc = Arc<Mutex<Doc>>;
-
-/// ## Errors
-pub fn try_load_shared_doc_from_path(
-    type_: FileType,
-    path: &Utf8Path,
-) -> Result<(usize, SharedDoc)> {
-    let doc = Doc::try_load_from_path(type_, path)?;
-
-    Ok((doc.max_page(), Arc::new(Mutex::new(doc))))
-}
@@ -6,6 +6,9 @@ pub enum Error {
     #[error("cbz error: {0}")]
     Cbz(#[from] eco_cbz::Error),
 
+    #[error("convert error: {0}")]
+    Convert(#[from] eco_convert::Error),
+
     #[error("epub doc error: {0}")]
     EpubDoc(#[from] epub::doc::DocError),
 
@@ -15,20 +18,53 @@ pub enum Error {
     #[error("html bytes conversion error: {0}")]
     HtmlBytesConversion(#[from] tl::errors::SetBytesError),
 
+    #[error("rar error: {0}")]
+    Rar(#[from] unrar::error::UnrarError),
+
     #[error("zip error: {0}")]
     Zip(#[from] zip::result::ZipError),
 
+    #[error("settings error: {0}")]
+    Settings(#[from] serde_json::Error),
+
+    #[error("reading statistics error: {0}")]
+    Stats(serde_json::Error),
+
+    #[error("notes error: {0}")]
+    Notes(serde_json::Error),
+
+    #[error("saved session error: {0}")]
+    Session(serde_json::Error),
+
+    #[error("remote control error: {0}")]
+    Remote(serde_json::Error),
+
+    #[error("remote request with an invalid token")]
+    RemoteUnauthorized,
+
+    #[error("home directory not found")]
+    HomeDirNotFound,
+
     #[error("invalid file type: {0}")]
     InvalidFileType(String),
 
     #[error("page not found: {0}")]
     PageNotFound(usize),
 
+    #[error("page is not an image: {0}")]
+    NotAnImage(usize),
+
     #[error("invalid non utf8 path provided")]
     InvalidNonUtf8Path,
 
     #[error("unknown file type provided")]
     UnknownFileType,
+
+    #[error("no document to view")]
+    NoDocument,
+
+    #[error("comparison needs exactly two documents, got {0}")]
+    InvalidComparison(usize),
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -2,154 +2,755 @@
 // Necessary for Dioxus
 #![allow(non_snake_case, clippy::ignored_unit_patterns)]
 
-use std::{cell::Cell, thread};
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use camino::Utf8PathBuf;
 use dioxus::{
-    html::{geometry::WheelDelta, input_data::keyboard_types::Key},
+    html::{
+        geometry::WheelDelta,
+        input_data::keyboard_types::{Key, Modifiers},
+    },
     prelude::*,
 };
-use dioxus_desktop::{Config, WindowBuilder};
-use doc::try_load_shared_doc_from_path;
-use futures::{channel::mpsc, executor::block_on, SinkExt, StreamExt};
-use tracing::{debug, error};
+use dioxus_desktop::{
+    tao::event::{Event, WindowEvent},
+    use_window, use_wry_event_handler,
+    wry::webview::FileDropEvent,
+    Config, WindowBuilder,
+};
+use eco_cbz::Bookmark;
+use futures::{
+    channel::mpsc::{self, UnboundedSender},
+    StreamExt,
+};
+use tracing::{debug, error, warn};
 
-use crate::components::doc_page::DocPage;
+use crate::components::{
+    adjustments::AdjustmentsPanel,
+    chapters::ChaptersPanel,
+    doc_page::DocPage,
+    epub_settings::EpubSettingsPanel,
+    notes::NotesPanel,
+    search::SearchPanel,
+    stats::StatsPanel,
+    tab_bar::TabBar,
+    thumbnail_grid::{ThumbnailGrid, ThumbnailsListener},
+};
 pub use crate::doc::FileType;
-use crate::doc::SharedDoc;
+use crate::doc::{next_doc_path, ContentType, Doc};
 pub use crate::errors::{Error, Result};
+use crate::loader::{LoadedPage, Loader};
+use crate::remote::RemoteRequest;
+use crate::restore::{SavedDoc, SavedSession, SessionSaver};
+use crate::settings::Settings;
+use crate::tabs::{Tab, Tabs};
+use crate::transform::ColorAdjustments;
+
+/// Amount of pages skipped with `PageUp` and `PageDown`
+static LARGE_JUMP: usize = 10;
+
+/// Amount of pages skipped with the arrows while `Shift` is pressed
+static SMALL_JUMP: usize = 5;
 
+/// Bundled stylesheet, so the viewer doesn't depend on any CDN
+static STYLES: &str = include_str!("../assets/styles.css");
+
+/// Magnifier lens, implemented client side as it only follows the cursor
+static MAGNIFIER_SCRIPT: &str = include_str!("../assets/magnifier.js");
+
+/// Touch and trackpad gestures, implemented client side as dioxus doesn't expose the touch positions
+static GESTURES_SCRIPT: &str = include_str!("../assets/gestures.js");
+
+/// Hides the controls in distraction free mode once idle, implemented client side so the mouse moves don't re-render the viewer
+static IDLE_SCRIPT: &str = include_str!("../assets/idle.js");
+
+mod cache;
 mod components;
 mod doc;
 pub mod errors;
-mod measure;
-
-fn load_pages<F>(
-    doc: SharedDoc,
-    max_page: usize,
-    mut page_loaded_sender: UnboundedSender<()>,
-    done: F,
-) where
-    F: 'static + Send + FnOnce(),
-{
-    thread::spawn(move || {
-        for page in 1..=max_page {
-            let mut doc = doc.lock().unwrap();
-            if let Err(err) = doc.load_page(page) {
-                error!("page load failed: {err}");
-            }
-            drop(doc);
-            // Gives some breath to the UI
-            std::thread::sleep(std::time::Duration::from_millis(1000 / 60));
-            if let Err(err) = block_on(page_loaded_sender.send(())) {
-                error!("page loaded channel error: {err}");
-            }
-        }
-        done();
-    });
-}
+mod loader;
+mod notes;
+mod remote;
+mod restore;
+mod search;
+mod settings;
+mod stats;
+mod tabs;
+mod transform;
 
 #[derive(Debug)]
 pub struct ViewOptions {
-    /// The path to the e-book file to view
-    pub path: Utf8PathBuf,
+    /// The paths to the e-book files to view, each one is opened in its own tab.
+    /// The documents of the previous session are opened when empty
+    pub paths: Vec<Utf8PathBuf>,
 
     /// Type of the file
     pub type_: Option<FileType>,
+
+    /// Password of the protected archives
+    pub password: Option<String>,
+
+    /// The page the first document is opened at, starting at 1
+    pub page: Option<usize>,
+
+    /// Displays the two documents side by side, moving them to the same pages
+    pub compare: bool,
+
+    /// Forwards the documents to the viewer already running if any, instead of starting a new one.
+    /// Otherwise the new viewer listens to the next `eco view` processes
+    pub single_instance: bool,
+}
+
+/// A document opened in the viewer
+struct OpenedDoc {
+    id: usize,
+    path: Utf8PathBuf,
+    loader: Loader,
+    content_type: ContentType,
+    max_page: usize,
+    /// Width and height of the pages, empty when the document doesn't record them
+    page_dimensions: Vec<(u32, u32)>,
+    /// The bookmarked chapters, in page order
+    chapters: Vec<Bookmark>,
+    /// The password the document was opened with, also used to open the next document of its folder
+    password: Option<String>,
+}
+
+impl OpenedDoc {
+    /// Loads the document and starts loading its first pages in the background
+    fn try_open(
+        id: usize,
+        path: impl AsRef<Path>,
+        type_: Option<FileType>,
+        password: Option<&str>,
+        page_loaded_sender: UnboundedSender<LoadedPage>,
+        crop_margins: Arc<AtomicBool>,
+    ) -> Result<Self> {
+        let Ok(path) = Utf8PathBuf::try_from(dunce::canonicalize(path)?) else {
+            return Err(Error::InvalidNonUtf8Path);
+        };
+        let Some(file_type) = type_.or_else(|| path.extension().and_then(|ext| ext.parse().ok()))
+        else {
+            return Err(Error::UnknownFileType);
+        };
+
+        let mut doc = Doc::try_load_from_path(file_type, &path, password)?;
+        let max_page = doc.max_page();
+        let content_type = doc.content_type();
+        let page_dimensions = doc.page_dimensions();
+        let chapters = doc.bookmarks();
+        let loader = Loader::spawn(id, doc, page_loaded_sender, crop_margins);
+        loader.request(loader::window(1, max_page));
+
+        Ok(Self {
+            id,
+            path,
+            loader,
+            content_type,
+            max_page,
+            page_dimensions,
+            chapters,
+            password: password.map(ToString::to_string),
+        })
+    }
+
+    fn title(&self) -> String {
+        format!("Eco Viewer - {}", self.path)
+    }
+
+    /// Short name displayed in the tab bar
+    fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map_or_else(|| self.path.to_string(), ToString::to_string)
+    }
 }
 
 /// Starts a new window with the viewer inside
 ///
 /// ## Errors
 ///
-/// Fails on file read error
+/// Fails on file read error, or if no paths are provided
 ///
 /// ## Panics
 pub fn view(opts: ViewOptions) -> Result<()> {
-    let Ok(path) = Utf8PathBuf::try_from(dunce::canonicalize(opts.path)?) else {
-        return Err(Error::InvalidNonUtf8Path);
+    let (page_loaded_sender, page_loaded_receiver) = mpsc::unbounded::<LoadedPage>();
+    let (file_dropped_sender, file_dropped_receiver) = mpsc::unbounded::<PathBuf>();
+    let (remote_sender, remote_receiver) = mpsc::unbounded::<RemoteRequest>();
+    if opts.single_instance {
+        let request = RemoteRequest {
+            paths: opts
+                .paths
+                .iter()
+                .map(|path| {
+                    dunce::canonicalize(path)
+                        .ok()
+                        .and_then(|path| Utf8PathBuf::try_from(path).ok())
+                        .unwrap_or_else(|| path.clone())
+                })
+                .collect(),
+            page: opts.page,
+        };
+        if remote::send(&request)? {
+            debug!("documents sent to the running viewer");
+            return Ok(());
+        }
+        if let Err(err) = remote::listen(remote_sender) {
+            warn!("the remote control endpoint couldn't be started: {err}");
+        }
+    }
+    let settings = Settings::load();
+    let crop_margins = Arc::new(AtomicBool::new(settings.crop_margins));
+    if opts.compare && opts.paths.len() != 2 {
+        return Err(Error::InvalidComparison(opts.paths.len()));
+    }
+    let saved_session = SavedSession::load();
+    let mut ids = 0..;
+    let restore = opts.paths.is_empty();
+    let opened_tabs = if restore {
+        let Some(saved_session) = &saved_session else {
+            return Err(Error::NoDocument);
+        };
+        open_saved_docs(
+            &saved_session.docs,
+            || ids.next().unwrap_or_default(),
+            &page_loaded_sender,
+            &crop_margins,
+        )
+    } else {
+        opts.paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| {
+                // Only the first document is opened at the requested page
+                let page = opts.page.filter(|_| index == 0).unwrap_or(1);
+                OpenedDoc::try_open(
+                    ids.next().unwrap_or_default(),
+                    path,
+                    opts.type_,
+                    opts.password.as_deref(),
+                    page_loaded_sender.clone(),
+                    crop_margins.clone(),
+                )
+                .map(|opened_doc| Tab::new(opened_doc, page))
+            })
+            .collect::<Result<Vec<_>>>()?
     };
-    let Some(file_type) = opts
-        .type_
-        .or_else(|| path.extension().and_then(|ext| ext.parse().ok()))
-    else {
-        return Err(Error::UnknownFileType);
+    if opened_tabs.is_empty() {
+        return Err(Error::NoDocument);
+    }
+    let mut tabs = Tabs::new(opened_tabs);
+    if opts.compare {
+        let compared_id = tabs.iter().nth(1).map(|tab| tab.doc.id);
+        if let Some(compared_id) = compared_id {
+            tabs.compare(compared_id);
+        }
+    }
+    // When documents are provided, restoring the other documents of the previous session is only offered
+    let restorable = if restore {
+        if let Some(index) = saved_session
+            .and_then(|saved_session| saved_session.active)
+            .and_then(|active| tabs.position_by_path(&active))
+        {
+            tabs.select(index);
+        }
+        None
+    } else {
+        saved_session
+            .map(|mut saved_session| {
+                saved_session
+                    .docs
+                    .retain(|saved_doc| tabs.position_by_path(&saved_doc.path).is_none());
+                saved_session
+            })
+            .filter(|saved_session| !saved_session.docs.is_empty())
     };
-
-    let path = path.as_ref();
-    let (max_page, doc) = try_load_shared_doc_from_path(file_type, path)?;
-    let (page_loaded_sender, page_loaded_receiver) = mpsc::unbounded::<()>();
-    let measure =
-        crate::measure::Measure::new("total document loading time", crate::measure::Precision::Ms);
-
-    load_pages(doc.clone(), max_page, page_loaded_sender, move || {
-        drop(measure);
-    });
+    let title = tabs.active().doc.title();
 
     dioxus_desktop::launch_with_props(
         App,
         AppProps {
-            doc,
-            max_page,
+            tabs: Cell::new(Some(tabs)),
+            restorable: Cell::new(restorable),
+            page_loaded_sender,
             page_loaded_receiver: Cell::new(Some(page_loaded_receiver)),
+            crop_margins,
+            file_dropped_receiver: Cell::new(Some(file_dropped_receiver)),
+            remote_receiver: Cell::new(Some(remote_receiver)),
         },
         Config::default()
-            .with_custom_head(
-                r#"
-                    <link
-                        rel="stylesheet"
-                        href="https://cdn.jsdelivr.net/npm/rippleui@1.12.1/dist/css/styles.css"
-                    />
-                    <script src="https://cdn.tailwindcss.com"></script>
-                "#
-                .to_string(),
-            )
-            .with_window(WindowBuilder::default().with_title(format!("Eco Viewer - {path}"))),
+            .with_custom_head(format!(
+                "<style>{STYLES}</style><script>{MAGNIFIER_SCRIPT}</script><script>{GESTURES_SCRIPT}</script><script>{IDLE_SCRIPT}</script>"
+            ))
+            .with_file_drop_handler(move |_window, event| {
+                let FileDropEvent::Dropped { paths, .. } = event else {
+                    return false;
+                };
+                // Each dropped document is opened in a new tab
+                for path in paths {
+                    if let Err(err) = file_dropped_sender.unbounded_send(path) {
+                        error!("file dropped channel error: {err}");
+                    }
+                }
+                true
+            })
+            .with_window(
+                settings
+                    .window
+                    .apply(WindowBuilder::default().with_title(title)),
+            ),
     );
 
     Ok(())
 }
 
+/// Opens the saved documents at their saved page, the ones that can't be opened anymore are skipped
+fn open_saved_docs(
+    saved_docs: &[SavedDoc],
+    mut next_id: impl FnMut() -> usize,
+    page_loaded_sender: &UnboundedSender<LoadedPage>,
+    crop_margins: &Arc<AtomicBool>,
+) -> Vec<Tab> {
+    saved_docs
+        .iter()
+        .filter_map(|saved_doc| {
+            match OpenedDoc::try_open(
+                next_id(),
+                &saved_doc.path,
+                None,
+                None,
+                page_loaded_sender.clone(),
+                crop_margins.clone(),
+            ) {
+                Ok(opened_doc) => Some(Tab::new(opened_doc, saved_doc.page)),
+                Err(err) => {
+                    warn!(
+                        "saved document {} couldn't be opened: {err}",
+                        saved_doc.path
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Applies the update to the settings and persists them
+fn update_settings<F>(settings: &UseRef<Settings>, f: F)
+where
+    F: FnOnce(&mut Settings),
+{
+    settings.with_mut(|settings| {
+        f(settings);
+        if let Err(err) = settings.save() {
+            error!("settings couldn't be saved: {err}");
+        }
+    });
+}
+
 pub struct AppProps {
-    doc: SharedDoc,
-    max_page: usize,
-    // Wrapped in an `Option` so it can be moved out from the struct
-    page_loaded_receiver: Cell<Option<mpsc::UnboundedReceiver<()>>>,
+    // Wrapped in `Option`s so they can be moved out from the struct
+    tabs: Cell<Option<Tabs>>,
+    /// The documents of the previous session that can be restored
+    restorable: Cell<Option<SavedSession>>,
+    page_loaded_sender: UnboundedSender<LoadedPage>,
+    page_loaded_receiver: Cell<Option<mpsc::UnboundedReceiver<LoadedPage>>>,
+    /// Shared with the loaders, so it can be toggled while they are running
+    crop_margins: Arc<AtomicBool>,
+    file_dropped_receiver: Cell<Option<mpsc::UnboundedReceiver<PathBuf>>>,
+    /// The requests of the other `eco view` processes, in single instance mode
+    remote_receiver: Cell<Option<mpsc::UnboundedReceiver<RemoteRequest>>>,
 }
 
 #[allow(clippy::ignored_unit_patterns, clippy::too_many_lines)]
 fn App(cx: Scope<AppProps>) -> Element {
     let page_loaded_receiver = cx.props.page_loaded_receiver.replace(None);
-    // Forces reactivity on page loaded
-    let nb_loaded_pages = use_state(cx, || 0);
-    let current_page = use_state(cx, || 1_usize);
-    #[allow(clippy::cast_precision_loss)]
-    let progress = use_memo(cx, (nb_loaded_pages,), |(nb_loaded_pages,)| {
-        1.0 / (cx.props.max_page as f32) * (*nb_loaded_pages.get() as f32) * 100.0
+    let file_dropped_receiver = cx.props.file_dropped_receiver.replace(None);
+    let remote_receiver = cx.props.remote_receiver.replace(None);
+    let window = use_window(cx);
+    let tabs = use_ref(cx, || {
+        cx.props
+            .tabs
+            .replace(None)
+            .expect("tabs to be accessed once")
+    });
+    let restorable = use_state(cx, || cx.props.restorable.take());
+    let active_tab = tabs.read();
+    let saved_session = SavedSession::from_tabs(&active_tab);
+    let tab = active_tab.active();
+    let doc_id = tab.doc.id;
+    let path = tab.doc.path.clone();
+    let title = tab.doc.title();
+    let max_page = tab.doc.max_page;
+    let content_type = tab.doc.content_type;
+    let current_page = tab.current_page;
+    let current_dimensions = current_page
+        .checked_sub(1)
+        .and_then(|index| tab.doc.page_dimensions.get(index).copied());
+    let current_content = tab.current_content().cloned();
+    let current_transform = tab.current_transform();
+    let highlight = tab.highlight.clone();
+    let chapters = tab.doc.chapters.clone();
+    let name = tab.doc.name();
+    // The compared page is only shown when the compared document has the same page
+    let compared = active_tab.compared().map(|compared_tab| {
+        let has_page = compared_tab.current_page == current_page;
+        (
+            compared_tab.doc.name(),
+            compared_tab.doc.content_type,
+            has_page,
+            has_page
+                .then(|| compared_tab.current_content().cloned())
+                .flatten(),
+            compared_tab.current_transform(),
+        )
+    });
+    let page_width_class = if compared.is_some() {
+        "w-1/2"
+    } else {
+        "w-full"
+    };
+    drop(active_tab);
+    let settings = use_ref(cx, Settings::load);
+    let slideshow = use_state(cx, || false);
+    let adjustments = use_state(cx, ColorAdjustments::default);
+    let show_adjustments = use_state(cx, || false);
+    let show_stats = use_state(cx, || false);
+    let show_notes = use_state(cx, || false);
+    let show_chapters = use_state(cx, || false);
+    let show_thumbnails = use_state(cx, || false);
+    let thumbnails_listener = use_ref(cx, || ThumbnailsListener::None);
+    let distraction_free = use_state(cx, || false);
+    let current_settings = settings.read().clone();
+    let theme = current_settings.theme;
+    let background_color = current_settings.background_color();
+    let fit_mode = current_settings.fit_mode;
+    // The settings file may have been edited by hand, a zero interval would turn the pages in a busy loop
+    let slideshow_interval = current_settings.slideshow_interval.max(1);
+    let epub_css = current_settings.epub_style.css();
+    let theme_name = theme.as_str();
+    let fit_mode_name = fit_mode.as_str();
+    let night_mode = current_settings.night_mode;
+    let night_mode_name = night_mode.as_str();
+    let auto_open_next = current_settings.auto_open_next;
+    let auto_open_next_label = if auto_open_next {
+        "Auto next: on"
+    } else {
+        "Auto next: off"
+    };
+    // The folder is only listed once per document
+    let next_doc = use_memo(cx, (path.clone(),), |(path,)| next_doc_path(&path));
+    // Only shown on the last page, where the next document can be opened
+    let next_doc_name = next_doc
+        .as_ref()
+        .filter(|_| current_page == max_page)
+        .and_then(|next_path| next_path.file_name().map(ToString::to_string));
+    let crop_margins_label = if current_settings.crop_margins {
+        "Uncrop margins"
+    } else {
+        "Crop margins"
+    };
+    let slideshow_label = if *slideshow.get() {
+        "Stop slideshow"
+    } else {
+        "Slideshow"
+    };
+    // Shown when hovering the page number, when the document records it
+    let dimensions_title = current_dimensions
+        .map(|(width, height)| format!("{width}x{height} pixels"))
+        .unwrap_or_default();
+    let distraction_free_label = if *distraction_free.get() {
+        "Exit focus"
+    } else {
+        "Focus"
+    };
+    let restorable_count = restorable
+        .get()
+        .as_ref()
+        .map(|saved_session| saved_session.docs.len());
+    let foreground_color = theme.foreground_color();
+    let svg_filter = adjustments.get().svg_filter();
+    // Hidden by the idle script
+    let controls_class = if *distraction_free.get() {
+        "idle-hidden"
+    } else {
+        ""
+    };
+
+    // Saved on each change rather than on exit, so the session survives a crash or a reboot
+    let session_saver = cx.use_hook(SessionSaver::spawn).clone();
+    use_effect(cx, (saved_session,), |(saved_session,)| async move {
+        session_saver.save(saved_session);
+    });
+
+    use_effect(cx, (title,), |(title,)| {
+        to_owned![window];
+        async move { window.set_title(&title) }
+    });
+
+    use_wry_event_handler(cx, {
+        to_owned![settings, tabs, window];
+        move |event, _target| {
+            if let Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } = event
+            {
+                update_settings(&settings, |settings| settings.window.update(&window));
+                tabs.read().iter().for_each(Tab::end_session);
+                // The latest changes may still be waiting to be saved in the background
+                if let Err(err) = SavedSession::from_tabs(&tabs.read()).save() {
+                    error!("session couldn't be saved: {err}");
+                }
+            }
+        }
     });
-    let current_content = use_memo(
-        cx,
-        (current_page, nb_loaded_pages),
-        |(current_page, _nb_loaded_pages)| {
-            let doc = cx.props.doc.lock().unwrap();
-            doc.content_for_page(*current_page.get())
-        },
-    );
 
     use_future!(cx, || {
-        to_owned![nb_loaded_pages];
+        to_owned![tabs, thumbnails_listener];
+        let crop_margins = cx.props.crop_margins.clone();
         async move {
             let mut page_loaded_receiver =
                 page_loaded_receiver.expect("page loaded receiver to be accessed once");
-            while page_loaded_receiver.next().await.is_some() {
-                nb_loaded_pages.modify(|nb_loaded_pages| *nb_loaded_pages + 1);
+            while let Some(loaded_page) = page_loaded_receiver.next().await {
+                if loaded_page.crop_margins != crop_margins.load(Ordering::Relaxed) {
+                    debug!("discarding page loaded before the crop margins option changed");
+                    continue;
+                }
+                let mut opened_tabs = tabs.write_silent();
+                let is_active = opened_tabs.active().doc.id == loaded_page.doc_id
+                    || opened_tabs
+                        .compared()
+                        .is_some_and(|tab| tab.doc.id == loaded_page.doc_id);
+                let Some(tab) = opened_tabs.find_by_doc_id_mut(loaded_page.doc_id) else {
+                    debug!("discarding page from a closed document");
+                    continue;
+                };
+                if loaded_page.thumbnail {
+                    tab.thumbnails.insert(loaded_page.page, loaded_page.content);
+                    drop(opened_tabs);
+                    // Only the grid shows the thumbnails
+                    if is_active {
+                        if let Some(update) = thumbnails_listener.read().as_ref() {
+                            update();
+                        }
+                    }
+                    continue;
+                }
+                tab.cache.insert(loaded_page.page, loaded_page.content);
+                drop(opened_tabs);
+                // Pages loaded in the background tabs don't need a new render
+                if is_active {
+                    tabs.needs_update();
+                }
             }
         }
     });
 
+    use_future!(cx, || {
+        to_owned![tabs];
+        let page_loaded_sender = cx.props.page_loaded_sender.clone();
+        let crop_margins = cx.props.crop_margins.clone();
+        async move {
+            let mut file_dropped_receiver =
+                file_dropped_receiver.expect("file dropped receiver to be accessed once");
+            while let Some(path) = file_dropped_receiver.next().await {
+                let id = tabs.write_silent().next_id();
+                match OpenedDoc::try_open(
+                    id,
+                    &path,
+                    None,
+                    None,
+                    page_loaded_sender.clone(),
+                    crop_margins.clone(),
+                ) {
+                    Ok(opened_doc) => tabs.with_mut(|tabs| tabs.open(Tab::new(opened_doc, 1))),
+                    Err(err) => error!("dropped file {path:?} couldn't be opened: {err}"),
+                }
+            }
+        }
+    });
+
+    use_future!(cx, || {
+        to_owned![tabs, window];
+        let page_loaded_sender = cx.props.page_loaded_sender.clone();
+        let crop_margins = cx.props.crop_margins.clone();
+        async move {
+            let mut remote_receiver = remote_receiver.expect("remote receiver to be accessed once");
+            while let Some(request) = remote_receiver.next().await {
+                let mut first_index = None;
+                for path in &request.paths {
+                    let index = tabs.read().position_by_path(path);
+                    let index = match index {
+                        Some(index) => index,
+                        None => {
+                            let id = tabs.write_silent().next_id();
+                            match OpenedDoc::try_open(
+                                id,
+                                path,
+                                None,
+                                None,
+                                page_loaded_sender.clone(),
+                                crop_margins.clone(),
+                            ) {
+                                Ok(opened_doc) => tabs.with_mut(|tabs| {
+                                    // The first document is directly opened at the requested page
+                                    let page = request.page.filter(|_| first_index.is_none());
+                                    tabs.open(Tab::new(opened_doc, page.unwrap_or(1)));
+                                    tabs.active_index()
+                                }),
+                                Err(err) => {
+                                    error!("remote file {path} couldn't be opened: {err}");
+                                    continue;
+                                }
+                            }
+                        }
+                    };
+                    first_index.get_or_insert(index);
+                }
+                if let Some(index) = first_index {
+                    tabs.with_mut(|tabs| {
+                        tabs.select(index);
+                        if let Some(page) = request.page {
+                            tabs.go_to_page(page);
+                        }
+                    });
+                }
+                window.set_minimized(false);
+                window.set_focus();
+            }
+        }
+    });
+
+    use_future(
+        cx,
+        (*slideshow.get(), slideshow_interval),
+        |(enabled, slideshow_interval)| {
+            to_owned![tabs, slideshow];
+            async move {
+                if !enabled {
+                    return;
+                }
+                loop {
+                    tokio::time::sleep(Duration::from_secs(slideshow_interval)).await;
+                    if !tabs.with_mut(|tabs| {
+                        let next_page = tabs.active().current_page + 1;
+                        tabs.go_to_page(next_page)
+                    }) {
+                        slideshow.set(false);
+                        return;
+                    }
+                }
+            }
+        },
+    );
+
+    let go_to_page = move |page: usize| {
+        if page == 0 || page > max_page {
+            return;
+        }
+        debug!("reading index {}", page - 1);
+        tabs.with_mut(|tabs| tabs.go_to_page(page));
+    };
+    let toggle_crop_margins = move || {
+        update_settings(settings, |settings| {
+            settings.crop_margins = !settings.crop_margins
+        });
+        cx.props
+            .crop_margins
+            .store(settings.read().crop_margins, Ordering::Relaxed);
+        // The pages already loaded must be loaded again with the new option
+        tabs.with_mut(|tabs| tabs.iter_mut().for_each(Tab::reload));
+    };
+    let save_current_page = move || {
+        let (doc_id, page, file_name) = tabs.with(|tabs| {
+            let tab = tabs.active();
+            let stem = tab.doc.path.file_stem().unwrap_or("page");
+            (
+                tab.doc.id,
+                tab.current_page,
+                format!("{stem} - page {}", tab.current_page),
+            )
+        });
+        let tabs = tabs.clone();
+        cx.spawn(async move {
+            let Some(file) = rfd::AsyncFileDialog::new()
+                .set_file_name(&file_name)
+                .save_file()
+                .await
+            else {
+                return;
+            };
+            let Ok(path) = Utf8PathBuf::from_path_buf(file.path().to_path_buf()) else {
+                error!("invalid non utf8 path selected: {:?}", file.path());
+                return;
+            };
+            // The document is owned by its loader thread, which writes the page
+            if let Some(tab) = tabs.write_silent().find_by_doc_id_mut(doc_id) {
+                tab.doc.loader.export(page, path);
+            }
+        });
+    };
+    let rotate_current_page = move || tabs.with_mut(|tabs| tabs.active_mut().rotate_current_page());
+    let go_to_prev_page = move || go_to_page(current_page - 1);
+    // Jumps stop on the first and last pages instead of being ignored
+    let jump_backward = move |pages: usize| go_to_page(current_page.saturating_sub(pages).max(1));
+    let jump_forward = move |pages: usize| go_to_page((current_page + pages).min(max_page));
+    let open_next_doc = move || {
+        let Some(next_path) = next_doc else {
+            debug!("no document after {}", tabs.read().active().doc.path);
+            return;
+        };
+        // The documents of a folder are usually protected by the same password
+        let password = tabs.read().active().doc.password.clone();
+        let id = tabs.write_silent().next_id();
+        match OpenedDoc::try_open(
+            id,
+            next_path,
+            None,
+            password.as_deref(),
+            cx.props.page_loaded_sender.clone(),
+            cx.props.crop_margins.clone(),
+        ) {
+            Ok(opened_doc) => tabs.with_mut(|tabs| tabs.replace_active(Tab::new(opened_doc, 1))),
+            Err(err) => error!("next document {next_path} couldn't be opened: {err}"),
+        }
+    };
+    let restore_session = move || {
+        let Some(saved_session) = restorable.get().clone() else {
+            return;
+        };
+        restorable.set(None);
+        let restored_tabs = open_saved_docs(
+            &saved_session.docs,
+            || tabs.write_silent().next_id(),
+            &cx.props.page_loaded_sender,
+            &cx.props.crop_margins,
+        );
+        tabs.with_mut(|tabs| restored_tabs.into_iter().for_each(|tab| tabs.open(tab)));
+    };
+    let go_to_next_page = move || {
+        if current_page == max_page && auto_open_next {
+            open_next_doc();
+        } else {
+            go_to_page(current_page + 1);
+        }
+    };
+
     cx.render(rsx! {
         div {
+            // Used by the gestures script
+            id: "viewer",
             class: "w-full h-screen flex flex-col gap-1 items-center outline-none",
+            style: "background-color: {background_color}; color: {foreground_color};",
             autofocus: true,
             tabindex: -1,
             onwheel: move |evt| {
@@ -159,94 +760,332 @@ fn App(cx: Scope<AppProps>) -> Element {
                     WheelDelta::Pages(pages) => pages.y,
                 };
                 if delta < 0.0 {
-                    let page = *current_page.get();
-                    if page == 1 {
-                        return;
-                    }
-
-                    current_page.set(page - 1);
-                    debug!("reading index {}", page - 2);
+                    go_to_prev_page();
                 } else {
-                    let page = *current_page.get();
-                    if page == *nb_loaded_pages.get() {
-                        return;
-                    }
-
-                    current_page.set(page + 1);
-                    debug!("reading index {}", page);
+                    go_to_next_page();
                 }
             },
             onkeyup: move |evt| {
+                let shift = evt.modifiers().contains(Modifiers::SHIFT);
                 match evt.key() {
-                    Key::ArrowLeft | Key::ArrowUp => {
-                        let page = *current_page.get();
-                        if page == 1 {
-                            return;
-                        }
-
-                        current_page.set(page - 1);
-                        debug!("reading index {}", page - 2);
+                    Key::ArrowLeft | Key::ArrowUp if shift => jump_backward(SMALL_JUMP),
+                    Key::ArrowRight | Key::ArrowDown if shift => jump_forward(SMALL_JUMP),
+                    Key::PageUp => jump_backward(LARGE_JUMP),
+                    Key::PageDown => jump_forward(LARGE_JUMP),
+                    Key::Home => go_to_page(1),
+                    Key::End => go_to_page(max_page),
+                    Key::ArrowLeft | Key::ArrowUp => go_to_prev_page(),
+                    Key::ArrowRight | Key::ArrowDown => go_to_next_page(),
+                    Key::Character(c) if c == "t" => {
+                        update_settings(settings, |settings| settings.theme = settings.theme.next());
                     },
-                    Key::ArrowRight | Key::ArrowDown => {
-                        let page = *current_page.get();
-                        if page == *nb_loaded_pages.get() {
-                            return;
-                        }
-
-                        current_page.set(page + 1);
-                        debug!("reading index {}", page);
+                    Key::Character(c) if c == "r" => rotate_current_page(),
+                    Key::Character(c) if c == "e" && content_type == ContentType::Image => save_current_page(),
+                    Key::Character(c) if c == "c" => toggle_crop_margins(),
+                    Key::Character(c) if c == "n" => {
+                        update_settings(settings, |settings| settings.night_mode = settings.night_mode.next());
+                    },
+                    Key::Character(c) if c == "s" => slideshow.modify(|enabled| !enabled),
+                    Key::Character(c) if c == "d" => distraction_free.modify(|enabled| !enabled),
+                    Key::Character(c) if c == "g" && content_type == ContentType::Image => {
+                        show_thumbnails.modify(|show| !show);
+                    },
+                    Key::Escape => show_thumbnails.set(false),
+                    Key::Character(c) if c == "f" => {
+                        update_settings(settings, |settings| settings.fit_mode = settings.fit_mode.next());
                     },
                     _ => {}
                 }
             },
             div {
-                class: "relative h-2 w-full shrink-0 px-2 mt-1",
-                if *nb_loaded_pages.get() < cx.props.max_page  {
-                    rsx!(progress {
-                        class: "progress progress-flat-primary absolute h-2 w-[calc(100%-1rem)]",
-                        value: "{progress}",
-                        max: "100"
-                    })
-                }
+                dangerous_inner_html: "{svg_filter}",
+            }
+            if *show_thumbnails.get() && content_type == ContentType::Image {
+                rsx!(ThumbnailGrid {
+                    tabs: tabs,
+                    listener: thumbnails_listener,
+                    background_color: background_color,
+                    on_select: move |page| {
+                        show_thumbnails.set(false);
+                        go_to_page(page);
+                    },
+                    on_close: move |()| show_thumbnails.set(false),
+                })
             }
             div {
-                class: "flex flex-col h-full w-full items-center justify-center",
-                if let Some(current_content) = current_content {
-                    rsx!(DocPage { doc: cx.props.doc.clone(), content: current_content })
-                }
+                class: "w-full shrink-0 {controls_class}",
+                TabBar { tabs: tabs }
+            }
+            if let Some(restorable_count) = restorable_count {
+                rsx!(div {
+                    class: "flex flex-row items-center justify-center gap-2 w-full shrink-0 text-sm {controls_class}",
+                    span { "{restorable_count} other document(s) were opened in the previous session" }
+                    button {
+                        class: "btn btn-primary btn-sm",
+                        onclick: move |_evt| restore_session(),
+                        "Restore"
+                    }
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        onclick: move |_evt| restorable.set(None),
+                        "Dismiss"
+                    }
+                })
             }
             div {
-                class: "flex flex-row items-center justify-center gap-1 h-8 mb-2",
-                button {
-                    class: "btn btn-outline-primary btn-sm",
-                    onclick: move |_evt| {
-                        let page = *current_page.get();
-                        if page == 1 {
-                            return;
+                class: "flex flex-row h-full w-full",
+                div {
+                    class: "flex flex-col h-full {page_width_class} items-center justify-center",
+                    if compared.is_some() {
+                        rsx!(span { class: "text-sm", "{name}" })
+                    }
+                    if let Some(current_content) = current_content {
+                        rsx!(DocPage {
+                            content_type: content_type,
+                            content: current_content,
+                            fit_mode: fit_mode,
+                            transform: current_transform,
+                            adjustments: *adjustments.get(),
+                            night_mode: night_mode,
+                            epub_css: &epub_css,
+                            highlight: highlight.as_deref(),
+                        })
+                    } else {
+                        // The page isn't loaded yet, it has been requested on navigation and is loaded first
+                        rsx!(
+                            div { class: "spinner-simple" }
+                            span { "Loading page {current_page}..." }
+                            if let Some((width, height)) = current_dimensions {
+                                rsx!(span { class: "text-sm", "{width}x{height}" })
+                            }
+                        )
+                    }
+                }
+                if let Some((compared_name, compared_content_type, has_page, compared_content, compared_transform)) = compared {
+                    rsx!(div {
+                        class: "flex flex-col h-full w-1/2 items-center justify-center border-l",
+                        span { class: "text-sm", "{compared_name}" }
+                        if !has_page {
+                            rsx!(span { "No page {current_page}" })
+                        } else if let Some(compared_content) = compared_content {
+                            rsx!(DocPage {
+                                content_type: compared_content_type,
+                                content: compared_content,
+                                fit_mode: fit_mode,
+                                transform: compared_transform,
+                                adjustments: *adjustments.get(),
+                                night_mode: night_mode,
+                                epub_css: &epub_css,
+                                highlight: None,
+                            })
+                        } else {
+                            rsx!(
+                                div { class: "spinner-simple" }
+                                span { "Loading page {current_page}..." }
+                            )
                         }
-
-                        current_page.set(page - 1);
-                        debug!("reading index {}", page - 2);
-                    },
-                    "Prev"
-                },
-                span {
-                    class: "flex flex-row items-center justify-center bg-backgroundSecondary h-8 px-2 rounded-sm",
-                     "{current_page} / {nb_loaded_pages}"
-                },
-                button {
-                    class: "btn btn-outline-primary btn-sm",
-                    onclick: move |_evt| {
-                        let page = *current_page.get();
-                        if page == *nb_loaded_pages.get() {
-                            return;
+                    })
+                }
+            }
+            div {
+                // Hidden rather than removed so the panels keep their state
+                class: "flex flex-col items-center gap-1 w-full shrink-0 {controls_class}",
+                div {
+                    class: "flex flex-row items-center w-full px-2 shrink-0",
+                    input {
+                        class: "w-full cursor-pointer",
+                        r#type: "range",
+                        title: "Go to page",
+                        min: "1",
+                        max: "{max_page}",
+                        value: "{current_page}",
+                        oninput: move |evt| {
+                            let Ok(page) = evt.value.parse::<usize>() else {
+                                return;
+                            };
+                            go_to_page(page);
+                        },
+                    },
+                }
+                if *show_adjustments.get() {
+                    rsx!(AdjustmentsPanel { adjustments: adjustments.clone() })
+                }
+                if *show_chapters.get() && !chapters.is_empty() {
+                    rsx!(ChaptersPanel {
+                        chapters: &chapters,
+                        current_page: current_page,
+                        on_select: move |page| go_to_page(page),
+                    })
+                }
+                if *show_notes.get() {
+                    rsx!(NotesPanel {
+                        tabs: tabs,
+                        on_select: move |page| go_to_page(page),
+                    })
+                }
+                if *show_stats.get() {
+                    rsx!(StatsPanel { tabs: tabs })
+                }
+                if content_type == ContentType::Html {
+                    rsx!(
+                        SearchPanel {
+                            // Each tab has its own search
+                            key: "{doc_id}",
+                            path: path,
+                            on_select: move |(page, term)| {
+                                tabs.with_mut(|tabs| tabs.active_mut().highlight = Some(term));
+                                go_to_page(page);
+                            },
                         }
-
-                        current_page.set(page + 1);
-                        debug!("reading index {}", page);
+                        EpubSettingsPanel { settings: settings }
+                    )
+                }
+                div {
+                    class: "flex flex-row items-center justify-center gap-1 h-8 mb-2",
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        onclick: move |_evt| go_to_prev_page(),
+                        "Prev"
+                    },
+                    span {
+                        class: "flex flex-row items-center justify-center h-8 px-2 rounded-sm border",
+                        title: "{dimensions_title}",
+                         "{current_page} / {max_page}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        onclick: move |_evt| go_to_next_page(),
+                        "Next"
+                    },
+                    if let Some(next_doc_name) = next_doc_name {
+                        rsx!(button {
+                            class: "btn btn-primary btn-sm",
+                            title: "Open the next document of the folder",
+                            onclick: move |_evt| open_next_doc(),
+                            "Open {next_doc_name}"
+                        })
+                    }
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Rotate page (r)",
+                        onclick: move |_evt| rotate_current_page(),
+                        "Rotate"
+                    },
+                    if content_type == ContentType::Image {
+                        rsx!(button {
+                            class: "btn btn-outline-primary btn-sm",
+                            title: "Show the thumbnails of the pages (g)",
+                            onclick: move |_evt| show_thumbnails.modify(|show| !show),
+                            "Pages"
+                        })
+                    }
+                    if content_type == ContentType::Image {
+                        rsx!(button {
+                            class: "btn btn-outline-primary btn-sm",
+                            title: "Save the page as an image file (e)",
+                            onclick: move |_evt| save_current_page(),
+                            "Save page"
+                        })
+                    }
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Adjust contrast and brightness",
+                        onclick: move |_evt| show_adjustments.modify(|show| !show),
+                        "Adjust"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Show the reading statistics",
+                        onclick: move |_evt| show_stats.modify(|show| !show),
+                        "Stats"
+                    },
+                    if !chapters.is_empty() {
+                        rsx!(button {
+                            class: "btn btn-outline-primary btn-sm",
+                            title: "Show the bookmarked chapters",
+                            onclick: move |_evt| show_chapters.modify(|show| !show),
+                            "Chapters"
+                        })
+                    }
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Show the notes attached to the pages",
+                        onclick: move |_evt| show_notes.modify(|show| !show),
+                        "Notes"
                     },
-                    "Next"
-                },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Change theme (t)",
+                        onclick: move |_evt| {
+                            update_settings(settings, |settings| settings.theme = settings.theme.next());
+                        },
+                        "Theme: {theme_name}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Change fit mode (f)",
+                        onclick: move |_evt| {
+                            update_settings(settings, |settings| settings.fit_mode = settings.fit_mode.next());
+                        },
+                        "{fit_mode_name}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Trim the uniform page margins (c)",
+                        onclick: move |_evt| toggle_crop_margins(),
+                        "{crop_margins_label}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Change night mode (n)",
+                        onclick: move |_evt| {
+                            update_settings(settings, |settings| settings.night_mode = settings.night_mode.next());
+                        },
+                        "{night_mode_name}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Toggle slideshow (s)",
+                        onclick: move |_evt| slideshow.modify(|enabled| !enabled),
+                        "{slideshow_label}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Hide the controls while the mouse doesn't move (d)",
+                        onclick: move |_evt| distraction_free.modify(|enabled| !enabled),
+                        "{distraction_free_label}"
+                    },
+                    button {
+                        class: "btn btn-outline-primary btn-sm",
+                        title: "Open the next document of the folder when moving past the last page",
+                        onclick: move |_evt| {
+                            update_settings(settings, |settings| settings.auto_open_next = !settings.auto_open_next);
+                        },
+                        "{auto_open_next_label}"
+                    },
+                    input {
+                        class: "h-8 w-16 px-2 rounded-sm border",
+                        r#type: "number",
+                        min: "1",
+                        title: "Slideshow interval in seconds",
+                        value: "{slideshow_interval}",
+                        oninput: move |evt| {
+                            let Ok(slideshow_interval) = evt.value.parse::<u64>() else {
+                                return;
+                            };
+                            update_settings(settings, |settings| settings.slideshow_interval = slideshow_interval.max(1));
+                        },
+                    },
+                    input {
+                        class: "h-8 w-8 cursor-pointer",
+                        r#type: "color",
+                        title: "Page background color",
+                        value: "{background_color}",
+                        oninput: move |evt| {
+                            update_settings(settings, |settings| settings.background_color = Some(evt.value.clone()));
+                        },
+                    },
+                }
             }
         }
     })
@@ -0,0 +1,202 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+};
+
+use camino::Utf8PathBuf;
+use eco_cbz::{Measure, Stage};
+use futures::channel::mpsc::UnboundedSender;
+use tracing::{debug, error, info};
+
+use crate::doc::Doc;
+
+/// Amount of pages loaded before and after the current page
+pub static WINDOW_SIZE: usize = 4;
+
+/// A page loaded by the loader thread
+#[derive(Debug)]
+pub struct LoadedPage {
+    /// Id of the document the page belongs to,
+    /// used to discard the pages of a document that has been replaced
+    pub doc_id: usize,
+    pub page: usize,
+    pub content: String,
+    /// The content is a thumbnail of the page, displayed in the thumbnails grid
+    pub thumbnail: bool,
+    /// Whether the margins were trimmed, the pages loaded before the option changed are discarded
+    pub crop_margins: bool,
+}
+
+enum Request {
+    Pages(Vec<usize>),
+    Thumbnails(Vec<usize>),
+    Export { page: usize, path: Utf8PathBuf },
+}
+
+enum Task {
+    Page(usize),
+    Thumbnail(usize),
+    Export { page: usize, path: Utf8PathBuf },
+}
+
+/// The requested pages, thumbnails, and exports that are not processed yet.
+/// A new pages or thumbnails request replaces the pending request of the same kind.
+#[derive(Default)]
+struct Pending {
+    pages: VecDeque<usize>,
+    thumbnails: VecDeque<usize>,
+    exports: VecDeque<(usize, Utf8PathBuf)>,
+}
+
+impl Pending {
+    fn push(&mut self, request: Request) {
+        match request {
+            Request::Pages(pages) => {
+                if !self.pages.is_empty() {
+                    debug!("pages request superseded");
+                }
+                self.pages = pages.into();
+            }
+            Request::Thumbnails(pages) => self.thumbnails = pages.into(),
+            Request::Export { page, path } => self.exports.push_back((page, path)),
+        }
+    }
+
+    /// The exports, explicitly asked by the user, come first,
+    /// then the pages are always loaded before the thumbnails
+    fn pop(&mut self) -> Option<Task> {
+        if let Some((page, path)) = self.exports.pop_front() {
+            return Some(Task::Export { page, path });
+        }
+        self.pages
+            .pop_front()
+            .map(Task::Page)
+            .or_else(|| self.thumbnails.pop_front().map(Task::Thumbnail))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pages.is_empty() && self.thumbnails.is_empty() && self.exports.is_empty()
+    }
+}
+
+/// Loads the pages in a dedicated thread that owns the document,
+/// the loaded pages are sent back to the UI through the provided channel.
+/// The `crop_margins` option is shared by all the loaders, and can be changed at any time.
+pub struct Loader {
+    requests: mpsc::Sender<Request>,
+}
+
+impl Loader {
+    pub fn spawn(
+        doc_id: usize,
+        mut doc: Doc,
+        page_loaded_sender: UnboundedSender<LoadedPage>,
+        crop_margins: Arc<AtomicBool>,
+    ) -> Self {
+        let (requests, requests_receiver) = mpsc::channel::<Request>();
+
+        thread::spawn(move || {
+            let mut pending = Pending::default();
+            loop {
+                if pending.is_empty() {
+                    let Ok(request) = requests_receiver.recv() else {
+                        // The UI is gone, there is nothing left to load
+                        break;
+                    };
+                    pending.push(request);
+                }
+                // The user moved to another page, the newest requests take precedence
+                for request in requests_receiver.try_iter() {
+                    pending.push(request);
+                }
+                let crop_margins = crop_margins.load(Ordering::Relaxed);
+                let (page, thumbnail, content) = match pending.pop() {
+                    Some(Task::Page(page)) => {
+                        let _measure = Measure::new(Stage::Load);
+                        (page, false, doc.load_page(page, crop_margins))
+                    }
+                    Some(Task::Thumbnail(page)) => {
+                        (page, true, doc.load_thumbnail(page, crop_margins))
+                    }
+                    Some(Task::Export { page, path }) => {
+                        match doc.export_page(page, &path) {
+                            Ok(path) => info!("page {page} saved to {path}"),
+                            Err(err) => error!("page {page} couldn't be saved to {path}: {err}"),
+                        }
+                        continue;
+                    }
+                    None => continue,
+                };
+                let content = match content {
+                    Ok(content) => content,
+                    Err(err) => {
+                        error!("page {page} load failed: {err}");
+                        continue;
+                    }
+                };
+                let loaded_page = LoadedPage {
+                    doc_id,
+                    page,
+                    content,
+                    thumbnail,
+                    crop_margins,
+                };
+                if let Err(err) = page_loaded_sender.unbounded_send(loaded_page) {
+                    error!("page loaded channel error: {err}");
+                    return;
+                }
+            }
+        });
+
+        Self { requests }
+    }
+
+    /// Asks the loader thread to load the provided pages, in order.
+    /// Any previous request that is still being processed is abandoned.
+    pub fn request(&self, pages: Vec<usize>) {
+        if pages.is_empty() {
+            return;
+        }
+        if let Err(err) = self.requests.send(Request::Pages(pages)) {
+            error!("pages request channel error: {err}");
+        }
+    }
+
+    /// Asks the loader thread to write the original page image to the path, before loading any page
+    pub fn export(&self, page: usize, path: Utf8PathBuf) {
+        if let Err(err) = self.requests.send(Request::Export { page, path }) {
+            error!("export request channel error: {err}");
+        }
+    }
+
+    /// Asks the loader thread to load the thumbnails of the provided pages, in order,
+    /// once the requested pages are loaded.
+    /// Any previous thumbnails request that is still being processed is abandoned.
+    pub fn request_thumbnails(&self, pages: Vec<usize>) {
+        if pages.is_empty() {
+            return;
+        }
+        if let Err(err) = self.requests.send(Request::Thumbnails(pages)) {
+            error!("thumbnails request channel error: {err}");
+        }
+    }
+}
+
+/// Returns the pages around `page`, closest pages first
+#[must_use]
+pub fn window(page: usize, max_page: usize) -> Vec<usize> {
+    let mut pages = vec![page];
+    for offset in 1..=WINDOW_SIZE {
+        if page + offset <= max_page {
+            pages.push(page + offset);
+        }
+        if page > offset {
+            pages.push(page - offset);
+        }
+    }
+    pages
+}
@@ -0,0 +1,76 @@
+use std::{collections::BTreeMap, fs};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::errors::{Error, Result};
+
+/// The notes attached to the pages of a document, stored in a sidecar file next to it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notes {
+    /// The notes by page number, starting at 1
+    pub pages: BTreeMap<usize, String>,
+}
+
+impl Notes {
+    /// Loads the notes of the document, or starts with no notes
+    /// if they don't exist yet or can't be read.
+    #[must_use]
+    pub fn load(doc_path: &Utf8Path) -> Self {
+        let path = notes_path(doc_path);
+        let Ok(content) = fs::read_to_string(&path) else {
+            debug!("no notes found at {path}");
+            return Self::default();
+        };
+        match serde_json::from_str(&content) {
+            Ok(notes) => notes,
+            Err(err) => {
+                error!("invalid notes file {path}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the notes next to the document, the sidecar file is removed once the last note is deleted
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the notes can't be written
+    pub fn save(&self, doc_path: &Utf8Path) -> Result<()> {
+        let path = notes_path(doc_path);
+        if self.pages.is_empty() {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+            return Ok(());
+        }
+        fs::write(
+            path,
+            serde_json::to_string_pretty(self).map_err(Error::Notes)?,
+        )?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get(&self, page: usize) -> Option<&str> {
+        self.pages.get(&page).map(String::as_str)
+    }
+
+    /// Attaches the note to the page, a blank note deletes the page note
+    pub fn set(&mut self, page: usize, note: String) {
+        if note.trim().is_empty() {
+            self.pages.remove(&page);
+        } else {
+            self.pages.insert(page, note);
+        }
+    }
+}
+
+/// The sidecar file of the document, e.g. `volume_1.cbz.notes.json`
+fn notes_path(doc_path: &Utf8Path) -> Utf8PathBuf {
+    let file_name = doc_path.file_name().unwrap_or("document");
+    doc_path.with_file_name(format!("{file_name}.notes.json"))
+}
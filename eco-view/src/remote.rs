@@ -0,0 +1,187 @@
+use std::{
+    collections::hash_map::RandomState,
+    fs::{self, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::{BufRead, BufReader, Write},
+    net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+    process, thread,
+    time::Duration,
+};
+
+use camino::Utf8PathBuf;
+use futures::channel::mpsc::UnboundedSender;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::errors::{Error, Result};
+
+/// Delay after which the endpoint is considered unreachable, or the client idle
+static TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Answer of the viewer once the request is accepted
+static ACK: &str = "ok";
+
+/// Sent by a new `eco view` process to the running viewer, as a single json line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteRequest {
+    /// The documents to open, the ones already opened are only selected.
+    /// The paths are absolute, as both processes may not share the same working directory
+    pub paths: Vec<Utf8PathBuf>,
+    /// The page to move to in the first document, starting at 1
+    pub page: Option<usize>,
+}
+
+/// Where the running viewer listens, recorded in a file only the user can read,
+/// so the other users' processes can neither find it nor send it requests
+#[derive(Debug, Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+impl Endpoint {
+    fn load() -> Option<Self> {
+        let endpoint = fs::read(endpoint_path().ok()?).ok()?;
+
+        serde_json::from_slice(&endpoint).ok()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = endpoint_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Created again so the permissions of a previous file don't apply
+        let _ = fs::remove_file(&path);
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(path)?
+            .write_all(&serde_json::to_vec(self).map_err(Error::Remote)?)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AuthenticatedRequest<R> {
+    token: String,
+    request: R,
+}
+
+fn endpoint_path() -> Result<Utf8PathBuf> {
+    let Some(home_dir) = home::home_dir() else {
+        return Err(Error::HomeDirNotFound);
+    };
+    let Ok(home_dir) = Utf8PathBuf::from_path_buf(home_dir) else {
+        return Err(Error::InvalidNonUtf8Path);
+    };
+
+    Ok(home_dir.join(".eco").join("viewer.json"))
+}
+
+/// 128 random bits, the keys of the std hashers are drawn from the OS
+fn random_token() -> String {
+    let [high, low] = [0, 1].map(|index| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u32(process::id());
+        hasher.write_u8(index);
+        hasher.finish()
+    });
+
+    format!("{high:016x}{low:016x}")
+}
+
+/// Forwards the request to the running viewer, returns `false` if no viewer is running
+///
+/// ## Errors
+///
+/// Fails if a viewer is running but the request can't be sent
+pub fn send(request: &RemoteRequest) -> Result<bool> {
+    let Some(Endpoint { port, token }) = Endpoint::load() else {
+        debug!("no running viewer found");
+        return Ok(false);
+    };
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let Ok(mut stream) = TcpStream::connect_timeout(&address, TIMEOUT) else {
+        debug!("no running viewer found on {address}");
+        return Ok(false);
+    };
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line =
+        serde_json::to_vec(&AuthenticatedRequest { token, request }).map_err(Error::Remote)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+    stream.flush()?;
+    // The viewer may have been closed, and its port reused by another program
+    let mut ack = String::new();
+    if BufReader::new(stream).read_line(&mut ack).is_err() || ack.trim_end() != ACK {
+        debug!("the program listening on {address} isn't a viewer");
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Listens to the requests of the other `eco view` processes in a background thread,
+/// they're forwarded to the viewer through the `sender`
+///
+/// ## Errors
+///
+/// Fails if the endpoint can't be bound or recorded
+pub fn listen(sender: UnboundedSender<RemoteRequest>) -> Result<()> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let endpoint = Endpoint {
+        port: listener.local_addr()?.port(),
+        token: random_token(),
+    };
+    endpoint.save()?;
+    debug!("listening to the remote requests on port {}", endpoint.port);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    warn!("remote connection failed: {err}");
+                    continue;
+                }
+            };
+            let sender = sender.clone();
+            let token = endpoint.token.clone();
+            // One thread per client, so an idle one doesn't hold the others
+            thread::spawn(move || {
+                if let Err(err) = serve(&stream, &token, &sender) {
+                    warn!("invalid remote request: {err}");
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+/// Reads a single request, it's only acknowledged once forwarded to the viewer
+fn serve(
+    mut stream: &TcpStream,
+    token: &str,
+    sender: &UnboundedSender<RemoteRequest>,
+) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let authenticated: AuthenticatedRequest<RemoteRequest> =
+        serde_json::from_str(&line).map_err(Error::Remote)?;
+    if authenticated.token != token {
+        return Err(Error::RemoteUnauthorized);
+    }
+    debug!("remote request received: {:?}", authenticated.request);
+    // The viewer has been closed, the client opens the documents itself
+    if sender.unbounded_send(authenticated.request).is_err() {
+        return Ok(());
+    }
+    stream.write_all(format!("{ACK}\n").as_bytes())?;
+
+    Ok(())
+}
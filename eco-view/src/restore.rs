@@ -0,0 +1,148 @@
+use std::{
+    fs, process,
+    sync::mpsc::{self, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::errors::{Error, Result};
+use crate::tabs::Tabs;
+
+/// The session is written once it hasn't changed for this long
+static SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Amount of session files kept, one per viewer that has been running, the oldest ones are removed
+const MAX_SAVED_SESSIONS: usize = 8;
+
+/// A document opened when the session was saved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedDoc {
+    pub path: Utf8PathBuf,
+    pub page: usize,
+}
+
+/// The documents opened in the viewer, persisted in the user's home directory
+/// each time they change so they survive a crash or a reboot
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSession {
+    pub docs: Vec<SavedDoc>,
+    /// The document of the active tab
+    pub active: Option<Utf8PathBuf>,
+}
+
+impl SavedSession {
+    #[must_use]
+    pub fn from_tabs(tabs: &Tabs) -> Self {
+        Self {
+            docs: tabs
+                .iter()
+                .map(|tab| SavedDoc {
+                    path: tab.doc.path.clone(),
+                    page: tab.current_page,
+                })
+                .collect(),
+            active: Some(tabs.active().doc.path.clone()),
+        }
+    }
+
+    /// Loads the session of the viewer that saved last, `None` if there is none or if it can't be read
+    #[must_use]
+    pub fn load() -> Option<Self> {
+        let dir = sessions_dir().ok()?;
+        let Ok(entries) = fs::read_dir(&dir) else {
+            debug!("no saved session found in {dir}");
+            return None;
+        };
+        let mut paths = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((modified, entry.path()))
+            })
+            .filter(|(_, path)| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>();
+        paths.sort_by(|(modified, _), (other, _)| other.cmp(modified));
+        for (_, path) in paths.iter().skip(MAX_SAVED_SESSIONS) {
+            let _ = fs::remove_file(path);
+        }
+
+        let (_, path) = paths.first()?;
+        let content = fs::read_to_string(path).ok()?;
+        match serde_json::from_str::<Self>(&content) {
+            Ok(session) => Some(session).filter(|session| !session.docs.is_empty()),
+            Err(err) => {
+                error!("invalid session file {}: {err}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Each viewer saves its session in its own file, so the viewers running at the same time don't overwrite each other
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the home directory can't be found or the session can't be written
+    pub fn save(&self) -> Result<()> {
+        let dir = sessions_dir()?;
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join(format!("{}.json", process::id())),
+            serde_json::to_string(self).map_err(Error::Session)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Saves the sessions in a background thread, the sessions sent in a quick succession are only written once
+#[derive(Debug, Clone)]
+pub struct SessionSaver {
+    sender: Sender<SavedSession>,
+}
+
+impl SessionSaver {
+    #[must_use]
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel::<SavedSession>();
+        thread::spawn(move || {
+            while let Ok(mut session) = receiver.recv() {
+                let disconnected = loop {
+                    match receiver.recv_timeout(SAVE_DELAY) {
+                        Ok(newer_session) => session = newer_session,
+                        Err(RecvTimeoutError::Timeout) => break false,
+                        Err(RecvTimeoutError::Disconnected) => break true,
+                    }
+                };
+                if let Err(err) = session.save() {
+                    error!("session couldn't be saved: {err}");
+                }
+                if disconnected {
+                    return;
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    pub fn save(&self, session: SavedSession) {
+        if self.sender.send(session).is_err() {
+            error!("session couldn't be saved: the saving thread has stopped");
+        }
+    }
+}
+
+fn sessions_dir() -> Result<Utf8PathBuf> {
+    let Some(home_dir) = home::home_dir() else {
+        return Err(Error::HomeDirNotFound);
+    };
+    let Ok(home_dir) = Utf8PathBuf::from_path_buf(home_dir) else {
+        return Err(Error::InvalidNonUtf8Path);
+    };
+
+    Ok(home_dir.join(".eco").join("sessions"))
+}
@@ -0,0 +1,116 @@
+use camino::Utf8Path;
+use tl::ParserOptions;
+
+use crate::errors::Result;
+
+/// Stops the search once this amount of matches is found
+static MAX_MATCHES: usize = 200;
+
+/// Amount of bytes kept around the match in the excerpt
+static EXCERPT_CONTEXT: usize = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    pub page: usize,
+    pub excerpt: String,
+}
+
+/// Searches the query in the text of all the epub pages, in spine order, case insensitively
+///
+/// ## Errors
+///
+/// Fails if the epub can't be opened or a page can't be parsed
+pub fn search_epub(path: &Utf8Path, query: &str) -> Result<Vec<SearchMatch>> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut doc = epub::doc::EpubDoc::new(path)?;
+    let mut matches = Vec::new();
+
+    for index in 0..doc.get_num_pages() {
+        doc.set_current_page(index);
+        let Ok(content) = doc.get_current_with_epub_uris() else {
+            continue;
+        };
+        let content = String::from_utf8_lossy(&content);
+        let dom = tl::parse(content.as_ref(), ParserOptions::default())?;
+        let Some(text) = dom
+            .query_selector("body")
+            .and_then(|mut node_handles| node_handles.next())
+            .and_then(|node_handle| node_handle.get(dom.parser()))
+            .map(|node| node.inner_text(dom.parser()).to_string())
+        else {
+            continue;
+        };
+        let lowercase_text = text.to_lowercase();
+        // Lowercasing may change the byte length of some characters,
+        // in which case the excerpt is taken from the lowercase text
+        let excerpt_source = if lowercase_text.len() == text.len() {
+            &text
+        } else {
+            &lowercase_text
+        };
+        for (start, _) in lowercase_text.match_indices(&query) {
+            matches.push(SearchMatch {
+                page: index + 1,
+                excerpt: excerpt(excerpt_source, start, query.len()),
+            });
+            if matches.len() >= MAX_MATCHES {
+                return Ok(matches);
+            }
+        }
+    }
+
+    Ok(matches)
+}
+
+fn excerpt(text: &str, start: usize, len: usize) -> String {
+    let mut from = start.saturating_sub(EXCERPT_CONTEXT);
+    while !text.is_char_boundary(from) {
+        from -= 1;
+    }
+    let mut to = (start + len + EXCERPT_CONTEXT).min(text.len());
+    while !text.is_char_boundary(to) {
+        to += 1;
+    }
+
+    text[from..to]
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A script wrapping the occurrences of the term in `mark` elements,
+/// and scrolling to the first one
+#[must_use]
+pub fn highlight_script(term: &str) -> String {
+    // The term is json encoded to produce a valid js string, `<` is escaped so it can't close the script tag
+    let term = serde_json::to_string(&term.trim().to_lowercase())
+        .unwrap_or_default()
+        .replace('<', "\\u003c");
+
+    format!(
+        r#"<script>
+document.addEventListener("DOMContentLoaded", () => {{
+  const term = {term};
+  if (!term) return;
+  const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+  const nodes = [];
+  while (walker.nextNode()) nodes.push(walker.currentNode);
+  let first = null;
+  for (const node of nodes) {{
+    const index = node.nodeValue.toLowerCase().indexOf(term);
+    if (index === -1) continue;
+    const range = document.createRange();
+    range.setStart(node, index);
+    range.setEnd(node, index + term.length);
+    const mark = document.createElement("mark");
+    range.surroundContents(mark);
+    if (first === null) first = mark;
+  }}
+  if (first !== null) first.scrollIntoView({{ block: "center" }});
+}});
+</script>"#
+    )
+}
@@ -0,0 +1,362 @@
+use std::fs;
+
+use camino::Utf8PathBuf;
+use dioxus_desktop::{
+    tao::{
+        dpi::{LogicalPosition, LogicalSize},
+        window::{Fullscreen, Window},
+    },
+    WindowBuilder,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::errors::{Error, Result};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    Black,
+}
+
+impl Theme {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Light => Self::Dark,
+            Self::Dark => Self::Black,
+            Self::Black => Self::Light,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Light => "light",
+            Self::Dark => "dark",
+            Self::Black => "black",
+        }
+    }
+
+    /// The default page background color for the theme
+    #[must_use]
+    pub fn background_color(self) -> &'static str {
+        match self {
+            Self::Light => "#ffffff",
+            Self::Dark => "#1f2937",
+            Self::Black => "#000000",
+        }
+    }
+
+    #[must_use]
+    pub fn foreground_color(self) -> &'static str {
+        match self {
+            Self::Light => "#111827",
+            Self::Dark => "#f3f4f6",
+            Self::Black => "#d1d5db",
+        }
+    }
+
+    /// Uses the system preferences to pick a theme
+    fn detect() -> Self {
+        match dark_light::detect() {
+            dark_light::Mode::Dark => Self::Dark,
+            dark_light::Mode::Light | dark_light::Mode::Default => Self::Light,
+        }
+    }
+}
+
+/// How images are scaled to fit the window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FitMode {
+    Width,
+    Height,
+    #[default]
+    Page,
+    Original,
+}
+
+impl FitMode {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Width => Self::Height,
+            Self::Height => Self::Page,
+            Self::Page => Self::Original,
+            Self::Original => Self::Width,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Width => "fit width",
+            Self::Height => "fit height",
+            Self::Page => "fit page",
+            Self::Original => "original size",
+        }
+    }
+
+    /// The classes applied on the image element
+    #[must_use]
+    pub fn img_class(self) -> &'static str {
+        match self {
+            Self::Width => "w-full h-auto max-w-none",
+            Self::Height => "h-full w-auto max-w-none",
+            Self::Page => "h-full w-full object-contain",
+            Self::Original => "max-w-none",
+        }
+    }
+}
+
+/// Display mode easing the reading of black on white pages at night
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NightMode {
+    #[default]
+    Off,
+    Invert,
+    Sepia,
+}
+
+impl NightMode {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Invert,
+            Self::Invert => Self::Sepia,
+            Self::Sepia => Self::Off,
+        }
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Off => "night mode off",
+            Self::Invert => "inverted",
+            Self::Sepia => "sepia",
+        }
+    }
+
+    /// The css filter functions applied on the page element.
+    /// The hue is rotated back after the inversion so the colored pages keep their hues.
+    #[must_use]
+    pub fn filter(self) -> &'static str {
+        match self {
+            Self::Off => "",
+            Self::Invert => "invert(0.9) hue-rotate(180deg)",
+            Self::Sepia => "sepia(0.6) brightness(0.8) contrast(0.9)",
+        }
+    }
+}
+
+/// User style applied on epub pages, overriding the style shipped with the book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpubStyle {
+    /// Font size, in percent of the default font size
+    pub font_size: u16,
+
+    pub line_height: f32,
+
+    /// Uses the book font when not set
+    pub font_family: Option<String>,
+
+    /// Horizontal margins, in `em`
+    pub margin: u16,
+}
+
+impl Default for EpubStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 100,
+            line_height: 1.5,
+            font_family: None,
+            margin: 2,
+        }
+    }
+}
+
+impl EpubStyle {
+    pub const MIN_FONT_SIZE: u16 = 50;
+    pub const MAX_FONT_SIZE: u16 = 300;
+
+    /// The css injected in the epub pages
+    #[must_use]
+    pub fn css(&self) -> String {
+        let mut css = format!(
+            "html, body {{ font-size: {}% !important; line-height: {} !important; }} \
+            body {{ margin: 0 {}em !important; }} \
+            p, span, div, li {{ font-size: inherit !important; line-height: inherit !important; }}",
+            self.font_size, self.line_height, self.margin
+        );
+        if let Some(font_family) = &self.font_family {
+            css.push_str(&format!(" * {{ font-family: {font_family} !important; }}"));
+        }
+        css
+    }
+}
+
+/// Size and position of the window, restored on the next launch
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    /// Inner width, in logical pixels
+    pub width: f64,
+
+    /// Inner height, in logical pixels
+    pub height: f64,
+
+    /// Outer position, in logical pixels, the system places the window when not set
+    pub position: Option<(f64, f64)>,
+
+    pub maximized: bool,
+
+    pub fullscreen: bool,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 600.0,
+            position: None,
+            maximized: false,
+            fullscreen: false,
+        }
+    }
+}
+
+impl WindowGeometry {
+    /// Reads the current geometry of the window
+    pub fn update(&mut self, window: &Window) {
+        let scale_factor = window.scale_factor();
+        self.maximized = window.is_maximized();
+        self.fullscreen = window.fullscreen().is_some();
+        // The size of a maximized window is not the one to restore once unmaximized
+        if self.maximized || self.fullscreen {
+            return;
+        }
+        let size = window.inner_size().to_logical::<f64>(scale_factor);
+        self.width = size.width;
+        self.height = size.height;
+        self.position = window.outer_position().ok().map(|position| {
+            let position = position.to_logical::<f64>(scale_factor);
+            (position.x, position.y)
+        });
+    }
+
+    #[must_use]
+    pub fn apply(&self, builder: WindowBuilder) -> WindowBuilder {
+        let mut builder = builder
+            .with_inner_size(LogicalSize::new(self.width, self.height))
+            .with_maximized(self.maximized);
+        if let Some((x, y)) = self.position {
+            builder = builder.with_position(LogicalPosition::new(x, y));
+        }
+        if self.fullscreen {
+            builder = builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        builder
+    }
+}
+
+/// The viewer settings, persisted in the user's home directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub theme: Theme,
+
+    /// Overrides the theme's page background color
+    pub background_color: Option<String>,
+
+    pub fit_mode: FitMode,
+
+    /// Trims the uniform margins of the pages when they are displayed
+    pub crop_margins: bool,
+
+    pub night_mode: NightMode,
+
+    /// Opens the next document of the directory when moving past the last page
+    pub auto_open_next: bool,
+
+    /// Delay between two pages in slideshow mode, in seconds
+    pub slideshow_interval: u64,
+
+    pub epub_style: EpubStyle,
+
+    pub window: WindowGeometry,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            theme: Theme::detect(),
+            background_color: None,
+            fit_mode: FitMode::default(),
+            crop_margins: false,
+            night_mode: NightMode::default(),
+            auto_open_next: false,
+            slideshow_interval: 5,
+            epub_style: EpubStyle::default(),
+            window: WindowGeometry::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Loads the settings from disk, or fallbacks to the default settings
+    /// if they don't exist yet or can't be read.
+    #[must_use]
+    pub fn load() -> Self {
+        let Ok(path) = settings_path() else {
+            return Self::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            debug!("no settings found at {path}");
+            return Self::default();
+        };
+        match serde_json::from_str(&content) {
+            Ok(settings) => settings,
+            Err(err) => {
+                error!("invalid settings file {path}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the home directory can't be found or the settings can't be written
+    pub fn save(&self) -> Result<()> {
+        let path = settings_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn background_color(&self) -> &str {
+        self.background_color
+            .as_deref()
+            .unwrap_or_else(|| self.theme.background_color())
+    }
+}
+
+fn settings_path() -> Result<Utf8PathBuf> {
+    let Some(home_dir) = home::home_dir() else {
+        return Err(Error::HomeDirNotFound);
+    };
+    let Ok(home_dir) = Utf8PathBuf::from_path_buf(home_dir) else {
+        return Err(Error::InvalidNonUtf8Path);
+    };
+
+    Ok(home_dir.join(".eco").join("view.json"))
+}
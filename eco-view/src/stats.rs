@@ -0,0 +1,239 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error};
+
+use crate::errors::{Error, Result};
+
+/// Sessions shorter than this, in seconds, are documents opened by mistake and are not recorded
+pub static MIN_SESSION_DURATION: u64 = 5;
+
+/// The time spent on a page is capped to this, beyond it the reader is considered away
+static MAX_PAGE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+/// Only the latest sessions of a document are kept, the older ones are merged in its totals
+const MAX_SESSIONS: usize = 100;
+
+/// A finished reading session of a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    /// Start of the session, in seconds since the unix epoch
+    pub started_at: u64,
+
+    /// Time spent reading, in seconds
+    pub duration: u64,
+
+    /// Amount of distinct pages seen during the session
+    pub pages_read: usize,
+
+    pub furthest_page: usize,
+}
+
+/// The reading statistics of a document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileStats {
+    pub max_page: usize,
+
+    /// Furthest page reached over all the sessions
+    pub furthest_page: usize,
+
+    /// The latest sessions, at most `MAX_SESSIONS`
+    pub sessions: Vec<Session>,
+
+    /// Amount of sessions merged in the totals below, no longer listed in `sessions`
+    pub merged_sessions: usize,
+
+    pub merged_duration: u64,
+
+    pub merged_pages_read: usize,
+}
+
+impl FileStats {
+    pub fn push(&mut self, max_page: usize, session: Session) {
+        self.max_page = max_page;
+        self.furthest_page = self.furthest_page.max(session.furthest_page);
+        self.sessions.push(session);
+        let excess = self.sessions.len().saturating_sub(MAX_SESSIONS);
+        for session in self.sessions.drain(..excess) {
+            self.merged_sessions += 1;
+            self.merged_duration += session.duration;
+            self.merged_pages_read += session.pages_read;
+        }
+    }
+
+    /// Amount of sessions, the merged ones included
+    #[must_use]
+    pub fn session_count(&self) -> usize {
+        self.merged_sessions + self.sessions.len()
+    }
+
+    /// Total time spent reading the document, in seconds
+    #[must_use]
+    pub fn duration(&self) -> u64 {
+        self.merged_duration
+            + self
+                .sessions
+                .iter()
+                .map(|session| session.duration)
+                .sum::<u64>()
+    }
+
+    /// Pages read over all the sessions, pages read again are counted again
+    #[must_use]
+    pub fn pages_read(&self) -> usize {
+        self.merged_pages_read
+            + self
+                .sessions
+                .iter()
+                .map(|session| session.pages_read)
+                .sum::<usize>()
+    }
+
+    /// Progress through the document, in percent
+    #[must_use]
+    pub fn progress(&self) -> usize {
+        if self.max_page == 0 {
+            return 0;
+        }
+        self.furthest_page.min(self.max_page) * 100 / self.max_page
+    }
+
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.max_page > 0 && self.furthest_page >= self.max_page
+    }
+}
+
+/// The reading statistics of all the documents ever opened, persisted in the user's home directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReadingStats {
+    pub files: BTreeMap<Utf8PathBuf, FileStats>,
+}
+
+impl ReadingStats {
+    /// Loads the statistics from disk, or starts with empty statistics
+    /// if they don't exist yet or can't be read.
+    #[must_use]
+    pub fn load() -> Self {
+        let Ok(path) = stats_path() else {
+            return Self::default();
+        };
+        let Ok(content) = fs::read_to_string(&path) else {
+            debug!("no reading statistics found at {path}");
+            return Self::default();
+        };
+        match serde_json::from_str(&content) {
+            Ok(stats) => stats,
+            Err(err) => {
+                error!("invalid reading statistics file {path}: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// ## Errors
+    ///
+    /// Fails if the home directory can't be found or the statistics can't be written
+    pub fn save(&self) -> Result<()> {
+        let path = stats_path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self).map_err(Error::Stats)?)?;
+
+        Ok(())
+    }
+
+    /// Adds the session to the statistics persisted on disk
+    ///
+    /// ## Errors
+    ///
+    /// Fails if the statistics can't be written
+    pub fn record(path: &Utf8Path, max_page: usize, session: Session) -> Result<()> {
+        let mut stats = Self::load();
+        stats
+            .files
+            .entry(path.to_path_buf())
+            .or_default()
+            .push(max_page, session);
+        stats.save()
+    }
+}
+
+/// The reading session of an opened document, recorded once the document is closed
+#[derive(Debug)]
+pub struct ReadingSession {
+    started_at: SystemTime,
+    /// Time spent on the previous pages
+    duration: Duration,
+    /// When the current page was reached
+    last_visit: Instant,
+    pages: BTreeSet<usize>,
+}
+
+/// Starts now, the pages are only counted once visited
+impl Default for ReadingSession {
+    fn default() -> Self {
+        Self {
+            started_at: SystemTime::now(),
+            duration: Duration::ZERO,
+            last_visit: Instant::now(),
+            pages: BTreeSet::new(),
+        }
+    }
+}
+
+impl ReadingSession {
+    pub fn visit(&mut self, page: usize) {
+        self.duration += self.current_page_duration();
+        self.last_visit = Instant::now();
+        self.pages.insert(page);
+    }
+
+    fn current_page_duration(&self) -> Duration {
+        self.last_visit.elapsed().min(MAX_PAGE_DURATION)
+    }
+
+    #[must_use]
+    pub fn session(&self) -> Session {
+        Session {
+            started_at: self
+                .started_at
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            duration: (self.duration + self.current_page_duration()).as_secs(),
+            pages_read: self.pages.len(),
+            furthest_page: self.pages.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Formats the duration, in seconds, as `1h 05m` or `3m 12s`
+#[must_use]
+pub fn format_duration(duration: u64) -> String {
+    let hours = duration / 3600;
+    let minutes = duration % 3600 / 60;
+    if hours > 0 {
+        return format!("{hours}h {minutes:02}m");
+    }
+    format!("{minutes}m {:02}s", duration % 60)
+}
+
+fn stats_path() -> Result<Utf8PathBuf> {
+    let Some(home_dir) = home::home_dir() else {
+        return Err(Error::HomeDirNotFound);
+    };
+    let Ok(home_dir) = Utf8PathBuf::from_path_buf(home_dir) else {
+        return Err(Error::InvalidNonUtf8Path);
+    };
+
+    Ok(home_dir.join(".eco").join("stats.json"))
+}
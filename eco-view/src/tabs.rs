@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use camino::Utf8Path;
+
+use tracing::error;
+
+use crate::cache::PageCache;
+use crate::loader;
+use crate::notes::Notes;
+use crate::stats::{ReadingSession, ReadingStats, MIN_SESSION_DURATION};
+use crate::transform::PageTransform;
+use crate::OpenedDoc;
+
+/// A document opened in the viewer, with its own navigation state
+pub struct Tab {
+    pub doc: OpenedDoc,
+    pub current_page: usize,
+    pub cache: PageCache,
+    pub transforms: HashMap<usize, PageTransform>,
+    pub highlight: Option<String>,
+    pub session: ReadingSession,
+    /// The thumbnails of the pages, loaded when the thumbnails grid is first shown
+    pub thumbnails: HashMap<usize, String>,
+    pub notes: Notes,
+}
+
+impl Tab {
+    /// Opens the document at the page, or at the first page if it doesn't exist
+    pub fn new(doc: OpenedDoc, page: usize) -> Self {
+        let notes = Notes::load(&doc.path);
+        let mut tab = Self {
+            doc,
+            current_page: 1,
+            cache: PageCache::default(),
+            transforms: HashMap::new(),
+            highlight: None,
+            session: ReadingSession::default(),
+            thumbnails: HashMap::new(),
+            notes,
+        };
+        if !tab.go_to_page(page) {
+            tab.go_to_page(1);
+        }
+        tab
+    }
+
+    /// Moves to the page and requests the pages around it that are not in the cache yet,
+    /// returns `false` if the page doesn't exist.
+    pub fn go_to_page(&mut self, page: usize) -> bool {
+        if page == 0 || page > self.doc.max_page {
+            return false;
+        }
+        self.current_page = page;
+        self.session.visit(page);
+        let missing_pages = loader::window(page, self.doc.max_page)
+            .into_iter()
+            .filter(|page| {
+                if self.cache.contains(*page) {
+                    self.cache.touch(*page);
+                    return false;
+                }
+                true
+            })
+            .collect();
+        self.doc.loader.request(missing_pages);
+        true
+    }
+
+    /// Empties the cache and loads the pages around the current page again
+    pub fn reload(&mut self) {
+        let had_thumbnails = !self.thumbnails.is_empty();
+        self.cache = PageCache::default();
+        self.thumbnails.clear();
+        self.go_to_page(self.current_page);
+        if had_thumbnails {
+            self.request_thumbnails();
+        }
+    }
+
+    /// Requests the thumbnails that are not loaded yet, the closest to the current page first
+    pub fn request_thumbnails(&self) {
+        let mut missing_pages = (1..=self.doc.max_page)
+            .filter(|page| !self.thumbnails.contains_key(page))
+            .collect::<Vec<_>>();
+        missing_pages.sort_by_key(|page| page.abs_diff(self.current_page));
+        self.doc.loader.request_thumbnails(missing_pages);
+    }
+
+    pub fn go_to_next_page(&mut self) -> bool {
+        self.go_to_page(self.current_page + 1)
+    }
+
+    /// Records the reading session of the document in the reading statistics
+    pub fn end_session(&self) {
+        let session = self.session.session();
+        if session.duration < MIN_SESSION_DURATION {
+            return;
+        }
+        if let Err(err) = ReadingStats::record(&self.doc.path, self.doc.max_page, session) {
+            error!("reading session couldn't be recorded: {err}");
+        }
+    }
+
+    pub fn rotate_current_page(&mut self) {
+        let transform = self.transforms.entry(self.current_page).or_default();
+        transform.rotation = transform.rotation.next();
+    }
+
+    #[must_use]
+    pub fn current_content(&self) -> Option<&String> {
+        self.cache.get(self.current_page)
+    }
+
+    #[must_use]
+    pub fn current_transform(&self) -> PageTransform {
+        self.transforms
+            .get(&self.current_page)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// The documents opened in the viewer, there is always at least one tab opened
+pub struct Tabs {
+    tabs: Vec<Tab>,
+    active: usize,
+    next_id: usize,
+    /// Id of the document displayed next to the active one in comparison mode
+    compared: Option<usize>,
+}
+
+impl Tabs {
+    /// ## Panics
+    ///
+    /// Panics if no tabs are provided
+    pub fn new(tabs: Vec<Tab>) -> Self {
+        assert!(!tabs.is_empty(), "at least one tab must be opened");
+        let next_id = tabs
+            .iter()
+            .map(|tab| tab.doc.id + 1)
+            .max()
+            .unwrap_or_default();
+        Self {
+            tabs,
+            active: 0,
+            next_id,
+            compared: None,
+        }
+    }
+
+    /// Returns a new unique document id
+    pub fn next_id(&mut self) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    #[must_use]
+    pub fn active(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    #[must_use]
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    /// Opens the tab and makes it the active one
+    pub fn open(&mut self, tab: Tab) {
+        self.tabs.push(tab);
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// Replaces the active tab, its reading session is recorded
+    pub fn replace_active(&mut self, tab: Tab) {
+        std::mem::replace(&mut self.tabs[self.active], tab).end_session();
+    }
+
+    pub fn select(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.active = index;
+        }
+    }
+
+    /// Closes the tab, the last remaining tab can't be closed
+    pub fn close(&mut self, index: usize) {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(index).end_session();
+        if self.active > index || self.active == self.tabs.len() {
+            self.active -= 1;
+        }
+    }
+
+    /// Index of the tab showing the document, the path must be canonical
+    #[must_use]
+    pub fn position_by_path(&self, path: &Utf8Path) -> Option<usize> {
+        self.tabs.iter().position(|tab| tab.doc.path == path)
+    }
+
+    /// Displays the document next to the active one, both are moved to the same pages
+    pub fn compare(&mut self, doc_id: usize) {
+        self.compared = Some(doc_id);
+        let page = self.active().current_page;
+        if let Some(tab) = self.compared_mut() {
+            tab.go_to_page(page);
+        }
+    }
+
+    /// The tab displayed next to the active one, if it's still opened and not the active one
+    #[must_use]
+    pub fn compared(&self) -> Option<&Tab> {
+        let doc_id = self.compared?;
+        self.tabs
+            .iter()
+            .find(|tab| tab.doc.id == doc_id && tab.doc.id != self.active().doc.id)
+    }
+
+    pub fn compared_mut(&mut self) -> Option<&mut Tab> {
+        let doc_id = self.compared?;
+        let active_id = self.active().doc.id;
+        self.tabs
+            .iter_mut()
+            .find(|tab| tab.doc.id == doc_id && tab.doc.id != active_id)
+    }
+
+    /// Moves the active tab to the page, and the compared tab too so the navigation is synchronized.
+    /// Returns `false` if the page doesn't exist in the active document.
+    pub fn go_to_page(&mut self, page: usize) -> bool {
+        if !self.active_mut().go_to_page(page) {
+            return false;
+        }
+        if let Some(tab) = self.compared_mut() {
+            tab.go_to_page(page);
+        }
+        true
+    }
+
+    pub fn find_by_doc_id_mut(&mut self, doc_id: usize) -> Option<&mut Tab> {
+        self.tabs.iter_mut().find(|tab| tab.doc.id == doc_id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Tab> {
+        self.tabs.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tab> {
+        self.tabs.iter_mut()
+    }
+
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.tabs.len()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::settings::NightMode;
+
+/// Clockwise rotation of a page
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Quarter,
+    Half,
+    ThreeQuarters,
+}
+
+impl Rotation {
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Quarter,
+            Self::Quarter => Self::Half,
+            Self::Half => Self::ThreeQuarters,
+            Self::ThreeQuarters => Self::None,
+        }
+    }
+
+    #[must_use]
+    pub fn degrees(self) -> u16 {
+        match self {
+            Self::None => 0,
+            Self::Quarter => 90,
+            Self::Half => 180,
+            Self::ThreeQuarters => 270,
+        }
+    }
+}
+
+/// Color adjustments applied on all the pages, using the same values as the
+/// `--contrast` and `--brightness` options of `eco pack` so they can be previewed.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ColorAdjustments {
+    pub contrast: f32,
+    pub brightness: i32,
+    pub invert: bool,
+}
+
+impl ColorAdjustments {
+    /// Id of the svg filter rendered by `svg_filter`
+    pub const FILTER_ID: &'static str = "color-adjustments";
+
+    /// An svg filter applying the same computation as the `image` crate:
+    /// the contrast is adjusted first, then the brightness is added to each channel.
+    #[must_use]
+    pub fn svg_filter(self) -> String {
+        let contrast_slope = ((100.0 + self.contrast) / 100.0).powi(2);
+        let contrast_intercept = 0.5 - 0.5 * contrast_slope;
+        #[allow(clippy::cast_precision_loss)]
+        let brightness_intercept = self.brightness as f32 / 255.0;
+        let funcs = |slope: f32, intercept: f32| {
+            ["R", "G", "B"]
+                .iter()
+                .map(|channel| {
+                    format!(r#"<feFunc{channel} type="linear" slope="{slope}" intercept="{intercept}"/>"#)
+                })
+                .collect::<String>()
+        };
+
+        format!(
+            r#"<svg width="0" height="0" style="position: absolute"><filter id="{}" color-interpolation-filters="sRGB"><feComponentTransfer>{}</feComponentTransfer><feComponentTransfer>{}</feComponentTransfer></filter></svg>"#,
+            Self::FILTER_ID,
+            funcs(contrast_slope, contrast_intercept),
+            funcs(1.0, brightness_intercept),
+        )
+    }
+
+    /// The css filter applied on the page element, the night mode filter is applied last
+    #[must_use]
+    pub fn style(self, night_mode: NightMode) -> String {
+        let invert = if self.invert { " invert(1)" } else { "" };
+        format!(
+            "filter: url(#{}){invert} {};",
+            Self::FILTER_ID,
+            night_mode.filter()
+        )
+    }
+}
+
+/// Transformations applied when a page is displayed, the document itself is never modified
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PageTransform {
+    pub rotation: Rotation,
+}
+
+impl PageTransform {
+    /// The inline style applied on the page element
+    #[must_use]
+    pub fn style(self) -> String {
+        format!("transform: rotate({}deg);", self.rotation.degrees())
+    }
+}
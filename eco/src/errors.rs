@@ -1,16 +1,125 @@
+use std::io::ErrorKind;
+
+use camino::Utf8PathBuf;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
+    #[error("{0} not found")]
+    InputNotFound(Utf8PathBuf),
+
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("cbz error {0}")]
+    Cbz(#[from] eco_cbz::Error),
+
+    #[error("json error {0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("convert error {0}")]
     Convert(#[from] eco_convert::Error),
 
+    #[error("{0} pages don't match the manifest")]
+    ManifestMismatch(usize),
+
+    #[error("{0} inputs failed, the output was written without them")]
+    InputsFailed(usize),
+
+    #[error("the metadata format of {} can't be guessed, use --format", .0.as_deref().map_or("stdout", |file| file.as_str()))]
+    UnknownMetadataFormat(Option<Utf8PathBuf>),
+
     #[error("merge error {0}")]
     Merge(#[from] eco_merge::Error),
 
+    #[error("library error {0}")]
+    Library(#[from] eco_library::Error),
+
     #[error("pack error {0}")]
     Pack(#[from] eco_pack::Error),
 
+    #[error("serve error {0}")]
+    Serve(#[from] eco_serve::Error),
+
+    #[error("stats error {0}")]
+    Stats(#[from] eco_stats::Error),
+
+    #[error("thumbs error {0}")]
+    Thumbs(#[from] eco_thumbs::Error),
+
     #[error("view error {0}")]
     View(#[from] eco_view::Error),
 }
 
+/// The classes of failures, their exit codes are stable so that scripts can rely on them.
+/// The exit code 2 is used by the arguments parser for invalid arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    Other = 1,
+    InputNotFound = 3,
+    UnsupportedFormat = 4,
+    NoImages = 5,
+    OutputWrite = 6,
+    PartialFailure = 7,
+}
+
+impl FailureKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::InputNotFound => "input-not-found",
+            Self::UnsupportedFormat => "unsupported-format",
+            Self::NoImages => "no-images",
+            Self::OutputWrite => "output-write",
+            Self::PartialFailure => "partial-failure",
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> FailureKind {
+        match self {
+            Self::InputNotFound(_) => FailureKind::InputNotFound,
+            Self::InputsFailed(_) => FailureKind::PartialFailure,
+            Self::UnknownMetadataFormat(_) => FailureKind::UnsupportedFormat,
+            Self::Convert(eco_convert::Error::NoImages)
+            | Self::Merge(eco_merge::Error::NoImages)
+            | Self::Pack(eco_pack::Error::NoImages) => FailureKind::NoImages,
+            Self::Convert(
+                eco_convert::Error::InvalidMobiVersion(_)
+                | eco_convert::Error::DrmProtected(_)
+                | eco_convert::Error::Mobi(_)
+                | eco_convert::Error::Pdf(_)
+                | eco_convert::Error::Epub(_),
+            ) => FailureKind::UnsupportedFormat,
+            _ => self.source_kind().unwrap_or(FailureKind::Other),
+        }
+    }
+
+    /// The archives and pages errors are wrapped by each library, so the first error of the chain whose kind is known decides:
+    /// the writers report the outputs that can't be written, the readers the inputs that can't be read,
+    /// and only a missing file is told apart among the other io errors
+    fn source_kind(&self) -> Option<FailureKind> {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(self);
+        while let Some(err) = source {
+            if let Some(err) = err.downcast_ref::<eco_cbz::Error>() {
+                if matches!(err, eco_cbz::Error::Write(_)) {
+                    return Some(FailureKind::OutputWrite);
+                }
+                if err.is_unsupported_format() {
+                    return Some(FailureKind::UnsupportedFormat);
+                }
+            }
+            if let Some(err) = err.downcast_ref::<std::io::Error>() {
+                return (err.kind() == ErrorKind::NotFound).then_some(FailureKind::InputNotFound);
+            }
+            source = err.source();
+        }
+        None
+    }
+}
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
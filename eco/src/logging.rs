@@ -0,0 +1,38 @@
+use std::fs::File;
+
+use camino::Utf8Path;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::errors::Result;
+
+/// Sets up the logs, `RUST_LOG` takes precedence over the verbosity when it's set, quiet mode included.
+/// The log file is handy for the viewer, whose stderr is usually invisible.
+/// It's written in a background thread, flushed when the returned guard is dropped.
+pub fn init(verbose: u8, quiet: bool, log_file: Option<&Utf8Path>) -> Result<Option<WorkerGuard>> {
+    let level = match verbose {
+        _ if quiet => "error",
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    let (file_layer, guard) = match log_file {
+        Some(path) => {
+            let (writer, guard) = tracing_appender::non_blocking(File::create(path)?);
+            (
+                Some(fmt::layer().with_ansi(false).with_writer(writer)),
+                Some(guard),
+            )
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .init();
+
+    Ok(guard)
+}
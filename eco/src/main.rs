@@ -1,13 +1,22 @@
 #![deny(clippy::all, clippy::pedantic)]
 
-use camino::Utf8PathBuf;
+use std::process::ExitCode;
+
+use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Parser, Subcommand};
+use eco_cbz::edit::PageRef;
+use eco_convert::PageSize;
+use eco_pack::{FilterCommand, OcrCommand, PageRanges};
 use types::FileType;
 
-use crate::errors::Result;
-use crate::types::{Format, ReadingOrder};
+use crate::errors::{Error, Result};
+use crate::types::{
+    Compression, Corner, Format, MetadataFormat, OutputFormat, PageFormat, ReadingOrder,
+    Resampling, SidecarPolicy, SortOrder,
+};
 
 mod errors;
+mod logging;
 mod types;
 
 #[derive(Debug, Parser)]
@@ -22,7 +31,134 @@ struct Args {
 
 #[derive(Debug, clap::Args)]
 struct GlobalOpts {
-    verbose: bool,
+    /// Increase the logs verbosity: -v for debug, and -vv for trace (`RUST_LOG` takes precedence)
+    #[clap(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Only log the errors, the commands still print their output path
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Also write the logs to this file
+    #[clap(long, global = true)]
+    log_file: Option<Utf8PathBuf>,
+
+    /// Print the time spent decoding, transforming, encoding, and writing the pages (convert, merge, pack, and repack only)
+    #[clap(long, global = true)]
+    timings: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct TitlePageOpts {
+    /// Insert a generated title page at the start of each chapter, showing the series, the volume, and the chapter name
+    #[clap(long, action)]
+    title_page: bool,
+
+    /// The series shown on the title pages
+    #[clap(long, requires = "title_page")]
+    title_series: Option<String>,
+
+    /// The volume shown on the title pages
+    #[clap(long, requires = "title_page")]
+    title_volume: Option<String>,
+
+    /// Background of the title pages, a color like `#1f2937`, or the path to an image
+    #[clap(long, requires = "title_page")]
+    title_background: Option<eco_pack::TitleBackground>,
+}
+
+impl TitlePageOpts {
+    fn into_title_page(self) -> Option<eco_pack::TitlePage> {
+        self.title_page.then(|| eco_pack::TitlePage {
+            series: self.title_series,
+            volume: self.title_volume,
+            background: self.title_background.unwrap_or_default(),
+        })
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct TransformOpts {
+    /// Convert the colors of the images embedding an ICC profile (e.g. Adobe RGB scans) to sRGB
+    #[clap(long, action)]
+    srgb: bool,
+
+    /// Transform each image with this external command, before the other transformations, e.g. `"waifu2x -i {in} -o {out}"`
+    #[clap(long)]
+    filter_cmd: Option<FilterCommand>,
+
+    /// Adjust images contrast
+    #[clap(long)]
+    contrast: Option<f32>,
+
+    /// Adjust images brightness
+    #[clap(long)]
+    brightness: Option<i32>,
+
+    /// Blur image (slow with big numbers)
+    #[clap(long)]
+    blur: Option<f32>,
+
+    /// Remove the noise of the scanned pages with a median filter of this radius (slow with big numbers)
+    #[clap(long)]
+    denoise: Option<u32>,
+
+    /// Remove the halftone patterns of the scanned pages
+    #[clap(long, action)]
+    descreen: bool,
+
+    /// Automatically split landscape images into 2 pages
+    #[clap(long, action)]
+    autosplit: bool,
+
+    /// Shrink the pages bigger than this size, e.g. `1072x1448`, the aspect ratio is kept and the spreads are split first
+    #[clap(long)]
+    resize: Option<PageSize>,
+
+    /// The filter used to resize the pages
+    #[clap(long, value_enum, default_value_t = Resampling::Lanczos3, requires = "resize")]
+    resampling: Resampling,
+
+    /// Reading order
+    #[clap(long, default_value_t = ReadingOrder::Rtl)]
+    reading_order: ReadingOrder,
+
+    /// Encode the pages in this format, instead of keeping the format of each image
+    #[clap(long, value_enum)]
+    output_format: Option<PageFormat>,
+
+    /// Quality of the pages encoded in a lossy format (jpeg, webp, or avif), between 1 and 100
+    #[clap(long, requires = "output_format", value_parser = clap::value_parser!(u8).range(1..=100))]
+    quality: Option<u8>,
+
+    /// How the pages are compressed, `auto` stores the jpeg, webp, avif, jxl, and gif pages as is and deflates the other ones
+    #[clap(long, value_enum, default_value_t = Compression::Auto)]
+    compression: Compression,
+}
+
+impl TransformOpts {
+    fn into_transform(self) -> eco_pack::Transform {
+        eco_pack::Transform {
+            srgb: self.srgb,
+            filter: self.filter_cmd,
+            contrast: self.contrast,
+            brightness: self.brightness,
+            blur: self.blur,
+            denoise: self.denoise,
+            descreen: self.descreen,
+            autosplit: self.autosplit,
+            resize: self.resize.map(|size| eco_pack::Resize {
+                max_width: size.width,
+                max_height: size.height,
+                resampling: self.resampling.into(),
+            }),
+            reading_order: self.reading_order.into(),
+            watermark: None,
+            page_format: self.output_format.map(Into::into),
+            quality: self.quality,
+            compression: self.compression.into(),
+        }
+    }
 }
 
 #[derive(Debug, Subcommand)]
@@ -35,38 +171,77 @@ enum Command {
         #[clap(long, short)]
         from: Format,
 
+        /// Output format, a cbz archive, a directory of images, or a kepub for Kobo devices
+        #[clap(long, value_enum, default_value_t = OutputFormat::Cbz)]
+        to: OutputFormat,
+
         /// Dir to output images
         #[clap(long, short)]
         outdir: Utf8PathBuf,
 
-        /// The archive name
+        /// The archive name, or the images directory name
         #[clap(long, short)]
         name: String,
 
-        /// Adjust images contrast
+        #[clap(flatten)]
+        transform: TransformOpts,
+
+        /// Pdf only: render the pages without any extractable image (requires the pdfium library)
+        #[clap(long, action)]
+        rasterize: bool,
+
+        /// Resolution of the rendered pages
+        #[clap(long, default_value_t = 300, requires = "rasterize")]
+        dpi: u16,
+
+        /// Pdf only: keep all the images of a page (e.g. tiled scans) as successive pages
+        #[clap(long, action)]
+        all_images: bool,
+
+        /// Only convert the selected pages, e.g. `1-50,60,70-`
         #[clap(long)]
-        contrast: Option<f32>,
+        pages: Option<PageRanges>,
+
+        /// Pdf only: join the spreads stored as 2 halves on consecutive pages
+        #[clap(long, action)]
+        join_spreads: bool,
+
+        /// Epub only: paginate and rasterize the reflowable chapters with a headless Chromium (requires the pdfium library)
+        #[clap(long, action)]
+        render: bool,
+
+        /// Epub only: size of the rendered pages, in pixels
+        #[clap(long, default_value = "1072x1448", requires = "render")]
+        page_size: PageSize,
 
-        /// Adjust images brightness
+        /// Epub only: the Chromium (or Chrome) executable used to render the chapters
+        #[clap(long, default_value = "chromium", requires = "render")]
+        browser: String,
+
+        /// Store the text of each page in a sidecar, recognized by this command, e.g. `"tesseract {} -"`
         #[clap(long)]
-        brightness: Option<i32>,
+        ocr: Option<OcrCommand>,
 
-        /// Blur image (slow with big numbers)
+        /// Insert this image as the first page (e.g. the storefront cover missing from the pdfs)
         #[clap(long)]
-        blur: Option<f32>,
+        cover_file: Option<Utf8PathBuf>,
 
-        /// Automatically split landscape images into 2 pages
-        #[clap(long, action)]
-        autosplit: bool,
+        /// Mobi and azw3 only: write the intermediate html to this path
+        #[clap(long)]
+        debug_dump: Option<Utf8PathBuf>,
 
-        /// Reading order
-        #[clap(long, default_value_t = ReadingOrder::Rtl)]
-        reading_order: ReadingOrder,
+        /// Leave out the pages that can't be converted and report them at the end, instead of stopping
+        #[clap(long, action)]
+        keep_going: bool,
     },
     Merge {
-        /// A glob that matches all the archive to merge
+        /// The archives to merge, in this order
+        #[clap(required_unless_present = "archives_glob")]
+        archives: Vec<Utf8PathBuf>,
+
+        /// A glob that matches all the archive to merge, the matches are merged after the explicit archives
         #[clap(short, long)]
-        archives_glob: String,
+        archives_glob: Option<String>,
 
         /// The output directory for the merged archive
         #[clap(short, long)]
@@ -75,10 +250,71 @@ enum Command {
         /// The merged archive name
         #[clap(short, long)]
         name: String,
+
+        /// Keep the original pages names instead of renaming them with a counter
+        #[clap(long, action)]
+        preserve_names: bool,
+
+        /// What to do with the metadata files (e.g. `ComicInfo.xml`) of the merged archives
+        #[clap(long, value_enum, default_value_t = SidecarPolicy::KeepFirst)]
+        sidecars: SidecarPolicy,
+
+        /// Split the output in several archives of at most this size, e.g. `300MB`, or this amount of pages, e.g. `500pages`.
+        /// The archives are split at 65535 pages otherwise, bigger amounts of pages are written in the ZIP64 format
+        #[clap(long)]
+        split: Option<eco_merge::Split>,
+
+        /// Password of the protected archives
+        #[clap(long)]
+        password: Option<String>,
+
+        /// Embed a manifest with the checksum of each page, see `eco verify`
+        #[clap(long, action)]
+        manifest: bool,
+
+        /// How the pages are compressed, `auto` stores the jpeg, webp, avif, jxl, and gif pages as is and deflates the other ones
+        #[clap(long, value_enum, default_value_t = Compression::Auto)]
+        compression: Compression,
+
+        /// Insert this image as the first page
+        #[clap(long)]
+        cover_file: Option<Utf8PathBuf>,
+
+        #[clap(flatten)]
+        title_page: TitlePageOpts,
+
+        /// Order of the merged archives, natural compares the numbers by value (`vol2` before `vol10`)
+        #[clap(long, value_enum, default_value_t = SortOrder::Natural)]
+        sort: SortOrder,
+
+        /// Amount of archives read in parallel, defaults to the amount of cores
+        #[clap(short, long)]
+        jobs: Option<usize>,
+
+        /// Leave out the archives that can't be read and report them at the end, instead of stopping
+        #[clap(long, action)]
+        keep_going: bool,
     },
     Pack {
         /// A glob that matches all the files to pack
-        files_descriptor: String,
+        #[clap(required_unless_present = "urls")]
+        files_descriptor: Option<String>,
+
+        /// Download the images from the urls listed in this file (one per line, `-` reads them from stdin) instead
+        #[clap(long, conflicts_with = "files_descriptor")]
+        urls: Option<Utf8PathBuf>,
+
+        /// Amount of images downloaded at the same time
+        #[clap(long, default_value_t = 4, requires = "urls")]
+        concurrency: usize,
+
+        /// Amount of retries of the downloads failing with a transient error
+        #[clap(long, default_value_t = 3, requires = "urls")]
+        retries: u32,
+
+        /// Maximum amount of downloads started per second
+        #[clap(long, requires = "urls")]
+        rate_limit: Option<u32>,
 
         /// The output directory for the merged archive
         #[clap(short, long, default_value = "./")]
@@ -88,94 +324,986 @@ enum Command {
         #[clap(short, long)]
         name: String,
 
-        /// Adjust images contrast
+        #[clap(flatten)]
+        transform: TransformOpts,
+
+        /// Only pack the selected files, in alphabetical order, e.g. `1-50,60,70-`
         #[clap(long)]
-        contrast: Option<f32>,
+        pages: Option<PageRanges>,
 
-        /// Adjust images brightness
+        /// Drop this amount of files at the start (e.g. the credits pages)
+        #[clap(long, default_value_t = 0)]
+        skip_first: usize,
+
+        /// Drop this amount of files at the end (e.g. the ads)
+        #[clap(long, default_value_t = 0)]
+        skip_last: usize,
+
+        /// A yaml or json manifest listing the operations (rotate, crop, skip) to apply to some pages only, e.g. `ops.yaml`
         #[clap(long)]
-        brightness: Option<i32>,
+        manifest: Option<Utf8PathBuf>,
 
-        /// Blur image (slow with big numbers)
+        /// Store the text of each page in a sidecar, recognized by this command, e.g. `"tesseract {} -"`
         #[clap(long)]
-        blur: Option<f32>,
+        ocr: Option<OcrCommand>,
+
+        /// Render the page number on each page
+        #[clap(long, action, conflicts_with = "watermark")]
+        page_numbers: bool,
+
+        /// Render this text on each page, `{page}` is replaced with the page number
+        #[clap(long)]
+        watermark: Option<String>,
+
+        /// Corner of the page number or watermark
+        #[clap(long, value_enum, default_value_t = Corner::BottomRight)]
+        watermark_corner: Corner,
+
+        /// Opacity of the page number or watermark, between 0 and 1
+        #[clap(long, default_value_t = 0.8)]
+        watermark_opacity: f32,
 
-        /// Automatically split landscape images into 2 pages
+        #[clap(flatten)]
+        title_page: TitlePageOpts,
+
+        /// Leave out the images that can't be read or downloaded and report them at the end, instead of stopping
         #[clap(long, action)]
-        autosplit: bool,
+        keep_going: bool,
+    },
+    /// Apply the pack transformations to the pages of existing archives, their metadata are kept
+    Repack {
+        /// The archives to repack
+        #[clap(required = true)]
+        archives: Vec<Utf8PathBuf>,
+
+        /// The output directory, each archive is written next to its source as `<name>-repacked.cbz` by default
+        #[clap(short, long)]
+        outdir: Option<Utf8PathBuf>,
 
-        /// Reading order
-        #[clap(long, default_value_t = ReadingOrder::Rtl)]
-        reading_order: ReadingOrder,
+        #[clap(flatten)]
+        transform: TransformOpts,
+
+        /// Leave out the archives that can't be repacked and report them at the end, instead of stopping
+        #[clap(long, action)]
+        keep_going: bool,
     },
     View {
-        /// The path to the e-book file to view
-        path: Utf8PathBuf,
+        /// The paths to the e-book files to view, each one is opened in its own tab.
+        /// The documents of the previous session are opened when none is provided
+        paths: Vec<Utf8PathBuf>,
 
         /// Type of the file
         #[clap(long = "type")]
         type_: Option<FileType>,
+
+        /// Password of the protected archives (cbz and cbr only)
+        #[clap(long)]
+        password: Option<String>,
+
+        /// The page the first document is opened at, starting at 1
+        #[clap(long, value_name = "PAGE")]
+        goto: Option<usize>,
+
+        /// Sends the documents to the viewer already running instead of starting a new one
+        #[clap(long)]
+        single_instance: bool,
+
+        /// Displays the two documents side by side, on the same pages
+        #[clap(long)]
+        compare: bool,
+    },
+    Serve {
+        /// The directory containing the cbz and epub files to serve
+        #[clap(default_value = "./")]
+        dir: Utf8PathBuf,
+
+        /// The host to listen on, use `0.0.0.0` to make the server reachable from the local network
+        #[clap(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// The port to listen on
+        #[clap(short, long, default_value_t = 8080)]
+        port: u16,
+    },
+    Repair {
+        /// The path to the damaged archive
+        path: Utf8PathBuf,
+
+        /// The path to the repaired archive, defaults to `<name>-repaired.cbz` next to the damaged one
+        #[clap(short, long)]
+        output: Option<Utf8PathBuf>,
+    },
+    /// Rewrite a zip of images into a proper archive: pages ordered naturally and flattened, junk files dropped, extensions fixed
+    Normalize {
+        /// The path to the zip
+        path: Utf8PathBuf,
+
+        /// The path to the normalized archive, defaults to `<name>.cbz` (or `<name>-normalized.cbz`) next to the zip
+        #[clap(short, long)]
+        output: Option<Utf8PathBuf>,
+    },
+    /// Report aggregated stats about a directory of archives
+    Stats {
+        /// The directory containing the archives, its sub directories are walked too
+        #[clap(default_value = "./")]
+        dir: Utf8PathBuf,
+
+        /// Read all the pages to detect the corrupted archives, slower but more accurate
+        #[clap(long, action)]
+        deep: bool,
+
+        /// Print the stats as json
+        #[clap(long, action)]
+        json: bool,
+    },
+    /// Generate thumbnails, or contact sheets, of the first pages of archives
+    Thumbs {
+        /// A glob that matches all the archives
+        archives_glob: String,
+
+        /// The output directory for the thumbnails
+        #[clap(short, long, default_value = "./")]
+        outdir: Utf8PathBuf,
+
+        /// Amount of pages per archive, starting with the cover
+        #[clap(short, long, default_value_t = 1)]
+        pages: usize,
+
+        /// Maximum width and height of the thumbnails
+        #[clap(long, default_value_t = 300, value_parser = clap::value_parser!(u32).range(1..))]
+        size: u32,
+
+        /// The filter used to resize the pages, `lanczos3` keeps the screentones sharp but is the slowest, a fast approximation is used by default
+        #[clap(long, value_enum)]
+        resampling: Option<Resampling>,
+
+        /// Write a single contact sheet per archive instead of one file per page
+        #[clap(long, action)]
+        contact_sheet: bool,
+
+        /// Amount of thumbnails per row in the contact sheets
+        #[clap(long, default_value_t = 4)]
+        columns: u32,
+    },
+    /// Compare the pages and the metadata of two archives
+    Diff {
+        /// The path to the first archive
+        left: Utf8PathBuf,
+
+        /// The path to the second archive
+        right: Utf8PathBuf,
+
+        /// Print the differences as json
+        #[clap(long, action)]
+        json: bool,
+    },
+    /// List the dimensions and formats of the pages of an archive
+    Info {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// Decode the pages and flag the ones whose resolution is far below the median, or that look upscaled
+        #[clap(long, action)]
+        quality: bool,
+
+        /// Pages with less pixels than this ratio of the median are flagged as low resolution
+        #[clap(long, default_value_t = 0.5, requires = "quality")]
+        min_resolution_ratio: f64,
+
+        /// Pages with a sharpness below this ratio of the median are flagged as blurry
+        #[clap(long, default_value_t = 0.4, requires = "quality")]
+        min_sharpness_ratio: f64,
+
+        /// Print the pages as json
+        #[clap(long, action)]
+        json: bool,
+    },
+    /// Check the pages of an archive against its manifest
+    Verify {
+        /// The path to the archive
+        path: Utf8PathBuf,
+    },
+    /// Edit the pages of an archive in place
+    Edit {
+        #[clap(subcommand)]
+        command: EditCommand,
+    },
+    /// Manage the metadata of an archive
+    Meta {
+        #[clap(subcommand)]
+        command: MetaCommand,
+    },
+    /// Catalog and query a library of archives
+    Library {
+        #[clap(subcommand)]
+        command: LibraryCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum LibraryCommand {
+    /// Scan a directory and write the catalog of its archives: metadata, page counts, cover thumbnails, and hashes
+    Index {
+        /// The root of the library, its sub directories are walked too
+        #[clap(default_value = "./")]
+        dir: Utf8PathBuf,
+
+        /// Path to the catalog, defaults to `eco-library.json` in the library root
+        #[clap(short, long)]
+        output: Option<Utf8PathBuf>,
+
+        /// Maximum width and height of the cover thumbnails
+        #[clap(long, default_value_t = 256)]
+        thumbnail_size: u32,
+
+        /// Don't generate the cover thumbnails
+        #[clap(long, action)]
+        no_thumbnails: bool,
+    },
+    /// List the archives with the same content, even when their names differ, the library must be indexed first
+    Dupes {
+        /// The root of the library
+        #[clap(default_value = "./")]
+        dir: Utf8PathBuf,
+
+        /// Path to the catalog, defaults to `eco-library.json` in the library root
+        #[clap(short, long)]
+        catalog: Option<Utf8PathBuf>,
+
+        /// The archives sharing at least this part of their pages (between 0 and 1) are reported as similar
+        #[clap(long, default_value_t = 0.9)]
+        min_similarity: f32,
+
+        /// Print the duplicates as json
+        #[clap(long, action)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MetaCommand {
+    /// Convert the metadata to another format, the original metadata are kept
+    Migrate {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// The format to convert the metadata to
+        #[clap(long, value_enum)]
+        to: MetadataFormat,
+    },
+    /// List the chapters bookmarked in the `ComicInfo.xml`, or replace them
+    Bookmarks {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// Bookmark a page, as `PAGE:TITLE` with the page starting at 1 (e.g. `--set "25:Chapter 2"`), replaces all the bookmarks
+        #[clap(long = "set", value_name = "PAGE:TITLE")]
+        bookmarks: Vec<eco_cbz::Bookmark>,
+
+        /// Remove all the bookmarks
+        #[clap(long, action, conflicts_with = "bookmarks")]
+        clear: bool,
+    },
+    /// Write the metadata to a file, to be edited by scripts or spreadsheets and imported back
+    Export {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// The metadata file, printed on stdout when missing
+        #[clap(short, long)]
+        output: Option<Utf8PathBuf>,
+
+        /// The format of the metadata file, guessed from its extension (`json` or `xml`) by default
+        #[clap(long, value_enum, required_unless_present = "output")]
+        format: Option<MetadataFormat>,
+    },
+    /// Replace the metadata with the content of a file, the metadata in the other format are kept
+    Import {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// The metadata file, a ComicBookInfo json or a `ComicInfo.xml`
+        file: Utf8PathBuf,
+
+        /// The format of the metadata file, guessed from its extension (`json` or `xml`) by default
+        #[clap(long, value_enum)]
+        format: Option<MetadataFormat>,
     },
 }
 
-fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+#[derive(Debug, Subcommand)]
+enum EditCommand {
+    /// Remove a page
+    Remove {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// The page number (starting at 1) or the page name
+        page: PageRef,
+    },
+    /// Replace the content of a page
+    Replace {
+        /// The path to the archive
+        path: Utf8PathBuf,
+
+        /// The page number (starting at 1) or the page name
+        page: PageRef,
+
+        /// The path to the new image
+        image: Utf8PathBuf,
+    },
+}
+
+impl Command {
+    /// The files and directories read by the command
+    fn inputs(&self) -> Vec<&Utf8Path> {
+        match self {
+            Command::Convert {
+                path,
+                cover_file: None,
+                ..
+            }
+            | Command::Repair { path, .. }
+            | Command::Normalize { path, .. }
+            | Command::Info { path, .. }
+            | Command::Verify { path }
+            | Command::Edit {
+                command: EditCommand::Remove { path, .. },
+            }
+            | Command::Meta {
+                command:
+                    MetaCommand::Migrate { path, .. }
+                    | MetaCommand::Bookmarks { path, .. }
+                    | MetaCommand::Export { path, .. },
+            } => vec![path],
+            Command::Meta {
+                command: MetaCommand::Import { path, file, .. },
+            } => vec![path, file],
+            Command::Edit {
+                command: EditCommand::Replace { path, image, .. },
+            } => vec![path, image],
+            Command::Convert {
+                path,
+                cover_file: Some(cover_file),
+                ..
+            } => vec![path, cover_file],
+            Command::Diff { left, right, .. } => vec![left, right],
+            Command::Repack { archives, .. } => archives.iter().map(AsRef::as_ref).collect(),
+            Command::View { paths, .. } => paths.iter().map(AsRef::as_ref).collect(),
+            Command::Serve { dir, .. }
+            | Command::Stats { dir, .. }
+            | Command::Library {
+                command: LibraryCommand::Index { dir, .. },
+            } => vec![dir],
+            Command::Library {
+                command: LibraryCommand::Dupes { dir, catalog, .. },
+            } => vec![catalog.as_deref().unwrap_or(dir)],
+            Command::Pack { manifest, urls, .. } => manifest
+                .iter()
+                .chain(urls.iter().filter(|urls| *urls != "-"))
+                .map(AsRef::as_ref)
+                .collect(),
+            Command::Merge {
+                archives,
+                cover_file,
+                ..
+            } => archives
+                .iter()
+                .chain(cover_file)
+                .map(AsRef::as_ref)
+                .collect(),
+            Command::Thumbs { .. } => Vec::new(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
     let args = Args::parse();
+    // Kept until the end, so the log file gets everything
+    let _log_guard = match logging::init(
+        args.global_opts.verbose,
+        args.global_opts.quiet,
+        args.global_opts.log_file.as_deref(),
+    ) {
+        Ok(log_guard) => log_guard,
+        Err(err) => {
+            eprintln!("logs couldn't be initialized: {err}");
+            None
+        }
+    };
 
-    match args.command {
+    match run(
+        args.command,
+        args.global_opts.quiet,
+        args.global_opts.timings,
+    ) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let kind = err.kind();
+            // A single line summary, easy to parse by the scripts
+            eprintln!(
+                "eco-error kind={} code={} message={:?}",
+                kind.name(),
+                kind.code(),
+                err.to_string()
+            );
+            ExitCode::from(kind.code())
+        }
+    }
+}
+
+/// The output paths are printed alone on stdout for the scripts, the details go to stderr
+fn print_report(report: &eco_cbz::Report, quiet: bool) {
+    for output in &report.outputs {
+        println!("{output}");
+    }
+    if !quiet {
+        eprint!("{report}");
+    }
+}
+
+/// Printed on stderr, like the report
+fn print_timings(timings: &eco_cbz::Timings) {
+    for timing in timings.stages() {
+        eprintln!(
+            "{}: {:.2}s ({} times)",
+            timing.stage,
+            timing.total.as_secs_f64(),
+            timing.count
+        );
+    }
+}
+
+/// The output is written in keep going mode, but the command still fails when some inputs were left out
+fn check_failures(failures: &[eco_cbz::Failure]) -> Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+    Err(Error::InputsFailed(failures.len()))
+}
+
+/// The provided format, or the one guessed from the metadata file extension
+fn metadata_format(
+    format: Option<MetadataFormat>,
+    file: Option<&Utf8Path>,
+) -> Result<eco_cbz::migrate::MetadataFormat> {
+    format
+        .map(Into::into)
+        .or_else(|| file.and_then(eco_cbz::migrate::MetadataFormat::from_path))
+        .ok_or_else(|| Error::UnknownMetadataFormat(file.map(Utf8Path::to_path_buf)))
+}
+
+fn print_quality(report: &eco_cbz::quality::QualityReport) {
+    println!(
+        "{} pages, {} flagged (median: {} pixels, sharpness {:.2})",
+        report.pages,
+        report.flagged.len(),
+        report.median_pixels,
+        report.median_sharpness
+    );
+    for page in &report.flagged {
+        let issues = page
+            .issues
+            .iter()
+            .map(|issue| match issue {
+                eco_cbz::quality::QualityIssue::LowResolution => "low resolution",
+                eco_cbz::quality::QualityIssue::Blurry => "blurry",
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sharpness = page.sharpness.map_or_else(
+            || "unknown".to_string(),
+            |sharpness| format!("{sharpness:.2}"),
+        );
+        println!(
+            "  page {} ({}): {}x{}, sharpness {sharpness}, {issues}",
+            page.page, page.name, page.width, page.height
+        );
+    }
+}
+
+fn print_diff(diff: &eco_cbz::diff::Diff) {
+    if diff.is_identical() {
+        println!("identical");
+        return;
+    }
+    println!("pages: {} -> {}", diff.left_pages, diff.right_pages);
+    for name in &diff.removed {
+        println!("  - {name}");
+    }
+    for name in &diff.added {
+        println!("  + {name}");
+    }
+    if diff.reordered {
+        println!("the pages are the same but in a different order");
+    }
+    for page in &diff.pages {
+        match (&page.left, &page.right) {
+            (Some(left), Some(right))
+                if (left.width, left.height) != (right.width, right.height) =>
+            {
+                println!(
+                    "page {}: {}x{} -> {}x{}",
+                    page.page, left.width, left.height, right.width, right.height
+                );
+            }
+            (Some(_), Some(_)) => println!("page {}: content differs", page.page),
+            (Some(_), None) => println!("page {}: only in the first archive", page.page),
+            (None, _) => println!("page {}: only in the second archive", page.page),
+        }
+    }
+    for name in &diff.metadata {
+        println!("metadata {name} differs");
+    }
+}
+
+#[allow(clippy::too_many_lines)]
+fn run(command: Command, quiet: bool, show_timings: bool) -> Result<()> {
+    if let Some(input) = command.inputs().into_iter().find(|input| !input.exists()) {
+        return Err(Error::InputNotFound(input.to_path_buf()));
+    }
+    // Collected by convert, merge, pack, and repack
+    let timings = eco_cbz::Timings::default();
+
+    match command {
         Command::Convert {
             path,
             from,
+            to,
             outdir,
             name,
-            contrast,
-            brightness,
-            blur,
-            autosplit,
-            reading_order,
-        } => eco_convert::convert(eco_convert::ConvertOptions {
-            path,
-            from: from.into(),
-            outdir,
-            name,
-            contrast,
-            brightness,
-            blur,
-            autosplit,
-            reading_order: reading_order.into(),
-        })?,
+            transform,
+            rasterize,
+            dpi,
+            all_images,
+            pages,
+            join_spreads,
+            render,
+            page_size,
+            browser,
+            ocr,
+            cover_file,
+            debug_dump,
+            keep_going,
+        } => {
+            let report = eco_convert::convert_with_progress(
+                eco_convert::ConvertOptions {
+                    path,
+                    from: from.into(),
+                    to: to.into(),
+                    outdir,
+                    name,
+                    transform: transform.into_transform(),
+                    rasterize_dpi: rasterize.then_some(dpi),
+                    all_images,
+                    pages,
+                    join_spreads,
+                    render: render.then(|| eco_convert::RenderOptions {
+                        page_size,
+                        browser,
+                        ..eco_convert::RenderOptions::default()
+                    }),
+                    ocr,
+                    cover_file,
+                    debug_dump,
+                    keep_going,
+                    cancel: eco_cbz::CancellationToken::default(),
+                },
+                &timings,
+            )?;
+            print_report(&report, quiet);
+            if show_timings {
+                print_timings(&timings);
+            }
+            check_failures(&report.failures)?;
+        }
         Command::Merge {
+            archives,
             archives_glob,
             outdir,
             name,
-        } => eco_merge::merge(eco_merge::MergeOptions {
-            archives_glob,
-            outdir,
-            name,
-        })?,
+            preserve_names,
+            sidecars,
+            split,
+            password,
+            manifest,
+            compression,
+            cover_file,
+            title_page,
+            sort,
+            jobs,
+            keep_going,
+        } => {
+            let report = eco_merge::merge_with_progress(
+                eco_merge::MergeOptions {
+                    archives_glob,
+                    archives,
+                    outdir,
+                    name,
+                    preserve_names,
+                    sidecars: sidecars.into(),
+                    split,
+                    password,
+                    manifest,
+                    compression: compression.into(),
+                    cover_file,
+                    title_page: title_page.into_title_page(),
+                    sort: sort.into(),
+                    jobs,
+                    keep_going,
+                    cancel: eco_cbz::CancellationToken::default(),
+                },
+                &timings,
+            )?;
+            print_report(&report, quiet);
+            if show_timings {
+                print_timings(&timings);
+            }
+            check_failures(&report.failures)?;
+        }
         Command::Pack {
             files_descriptor,
+            urls,
+            concurrency,
+            retries,
+            rate_limit,
             outdir,
             name,
-            contrast,
-            brightness,
-            blur,
-            autosplit,
-            reading_order,
-        } => eco_pack::pack(eco_pack::PackOptions {
-            files_descriptor,
+            transform,
+            pages,
+            skip_first,
+            skip_last,
+            manifest,
+            ocr,
+            page_numbers,
+            watermark,
+            watermark_corner,
+            watermark_opacity,
+            title_page,
+            keep_going,
+        } => {
+            let watermark = watermark
+                .or_else(|| page_numbers.then(|| "{page}".to_string()))
+                .map(|text| eco_pack::Watermark {
+                    text,
+                    corner: watermark_corner.into(),
+                    opacity: watermark_opacity,
+                });
+            let source = match urls {
+                Some(path) => eco_pack::Source::Urls {
+                    urls: eco_pack::parse_urls(&if path == "-" {
+                        std::io::read_to_string(std::io::stdin())?
+                    } else {
+                        std::fs::read_to_string(path)?
+                    }),
+                    download: eco_pack::DownloadOptions {
+                        concurrency,
+                        retries,
+                        rate_limit,
+                    },
+                },
+                None => eco_pack::Source::Glob(files_descriptor.unwrap_or_default()),
+            };
+            let report = eco_pack::pack_with_progress(
+                eco_pack::PackOptions {
+                    source,
+                    outdir,
+                    name,
+                    transform: eco_pack::Transform {
+                        watermark,
+                        ..transform.into_transform()
+                    },
+                    pages,
+                    skip_first,
+                    skip_last,
+                    manifest,
+                    ocr,
+                    title_page: title_page.into_title_page(),
+                    keep_going,
+                    cancel: eco_cbz::CancellationToken::default(),
+                },
+                &timings,
+            )?;
+            print_report(&report, quiet);
+            if show_timings {
+                print_timings(&timings);
+            }
+            check_failures(&report.failures)?;
+        }
+        Command::Repack {
+            archives,
             outdir,
-            name,
-            contrast,
-            brightness,
-            blur,
-            autosplit,
-            reading_order: reading_order.into(),
-        })?,
-        Command::View { path, type_ } => eco_view::view(eco_view::ViewOptions {
-            path,
+            transform,
+            keep_going,
+        } => {
+            let (reports, failures) = eco_pack::repack_with_progress(
+                &eco_pack::RepackOptions {
+                    archives,
+                    outdir,
+                    transform: transform.into_transform(),
+                    keep_going,
+                    cancel: eco_cbz::CancellationToken::default(),
+                },
+                &timings,
+            )?;
+            for report in &reports {
+                print_report(report, quiet);
+            }
+            if !quiet {
+                for failure in &failures {
+                    eprintln!("{failure}");
+                }
+            }
+            if show_timings {
+                print_timings(&timings);
+            }
+            check_failures(&failures)?;
+        }
+        Command::View {
+            paths,
+            type_,
+            password,
+            goto,
+            single_instance,
+            compare,
+        } => eco_view::view(eco_view::ViewOptions {
+            paths,
             type_: type_.map(Into::into),
+            password,
+            page: goto,
+            compare,
+            single_instance,
+        })?,
+        Command::Serve { dir, host, port } => eco_serve::serve(eco_serve::ServeOptions {
+            dir,
+            address: format!("{host}:{port}"),
         })?,
+        Command::Normalize { path, output } => {
+            let output = output.unwrap_or_else(|| {
+                let output = path.with_extension("cbz");
+                if output == path {
+                    path.with_file_name(format!(
+                        "{}-normalized.cbz",
+                        path.file_stem().unwrap_or("archive")
+                    ))
+                } else {
+                    output
+                }
+            });
+            let report = eco_cbz::normalize::normalize_file(&path, &output)?;
+            println!("{} pages written to {output}", report.pages);
+            if !report.dropped.is_empty() {
+                println!("{} files dropped:", report.dropped.len());
+                for name in report.dropped {
+                    println!("  {name}");
+                }
+            }
+        }
+        Command::Repair { path, output } => {
+            let output = output.unwrap_or_else(|| {
+                path.with_file_name(format!(
+                    "{}-repaired.cbz",
+                    path.file_stem().unwrap_or("archive")
+                ))
+            });
+            let report = eco_cbz::repair::repair_file(&path, &output)?;
+            println!("{} pages recovered into {output}", report.recovered);
+            if !report.lost.is_empty() {
+                println!("{} pages lost:", report.lost.len());
+                for name in report.lost {
+                    println!("  {name}");
+                }
+            }
+        }
+        Command::Stats { dir, deep, json } => {
+            let stats = eco_stats::stats(&eco_stats::StatsOptions { dir, deep })?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                eco_stats::print_tables(&stats)?;
+            }
+        }
+        Command::Thumbs {
+            archives_glob,
+            outdir,
+            pages,
+            size,
+            resampling,
+            contact_sheet,
+            columns,
+        } => {
+            let outputs = eco_thumbs::thumbs(eco_thumbs::ThumbsOptions {
+                archives_glob,
+                outdir,
+                pages,
+                size,
+                resampling: resampling.map(Into::into),
+                contact_sheet,
+                columns,
+            })?;
+            for output in outputs {
+                println!("{output}");
+            }
+        }
+        Command::Diff { left, right, json } => {
+            let diff = eco_cbz::diff::diff_files(&left, &right)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print_diff(&diff);
+            }
+        }
+        Command::Info {
+            path,
+            quality: true,
+            min_resolution_ratio,
+            min_sharpness_ratio,
+            json,
+        } => {
+            let report = eco_cbz::quality::audit_file(
+                &path,
+                &eco_cbz::quality::QualityOptions {
+                    min_resolution_ratio,
+                    min_sharpness_ratio,
+                },
+            )?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_quality(&report);
+            }
+        }
+        Command::Info { path, json, .. } => {
+            let pages = eco_cbz::CbzReader::try_from_path(&path)?.pages_info()?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&pages)?);
+            } else {
+                println!("{} pages", pages.len());
+                for page in &pages {
+                    let format = page.format.as_deref().unwrap_or("unknown");
+                    println!("  {}: {}x{} {format}", page.name, page.width, page.height);
+                }
+            }
+        }
+        Command::Verify { path } => {
+            let report = eco_cbz::CbzReader::try_from_path(&path)?.verify_manifest()?;
+            println!("{} pages verified", report.verified);
+            for name in &report.mismatched {
+                println!("  {name} doesn't match");
+            }
+            for name in &report.missing {
+                println!("  {name} is missing");
+            }
+            if !report.is_valid() {
+                return Err(Error::ManifestMismatch(
+                    report.mismatched.len() + report.missing.len(),
+                ));
+            }
+        }
+        Command::Edit {
+            command: EditCommand::Remove { path, page },
+        } => {
+            let name = eco_cbz::edit::remove_page(&path, &page)?;
+            println!("{name} removed from {path}");
+        }
+        Command::Edit {
+            command: EditCommand::Replace { path, page, image },
+        } => {
+            let bytes = std::fs::read(&image)?;
+            let name = eco_cbz::edit::replace_page(&path, &page, &bytes)?;
+            println!("{name} replaced in {path}");
+        }
+        Command::Meta {
+            command: MetaCommand::Migrate { path, to },
+        } => eco_cbz::migrate::migrate_metadata(&path, to.into())?,
+        Command::Meta {
+            command:
+                MetaCommand::Bookmarks {
+                    path,
+                    bookmarks,
+                    clear,
+                },
+        } => {
+            if clear || !bookmarks.is_empty() {
+                eco_cbz::migrate::set_bookmarks(&path, bookmarks)?;
+            } else {
+                for bookmark in eco_cbz::CbzReader::try_from_path(&path)?.bookmarks()? {
+                    println!("{}: {}", bookmark.page + 1, bookmark.title);
+                }
+            }
+        }
+        Command::Meta {
+            command:
+                MetaCommand::Export {
+                    path,
+                    output,
+                    format,
+                },
+        } => {
+            let format = metadata_format(format, output.as_deref())?;
+            let content = eco_cbz::migrate::export_metadata(&path, format)?;
+            match output {
+                Some(output) => {
+                    std::fs::write(&output, content)?;
+                    println!("{output}");
+                }
+                None => println!("{content}"),
+            }
+        }
+        Command::Meta {
+            command: MetaCommand::Import { path, file, format },
+        } => {
+            let format = metadata_format(format, Some(&file))?;
+            let content = std::fs::read_to_string(&file)?;
+            eco_cbz::migrate::import_metadata(&path, &content, format)?;
+        }
+        Command::Library {
+            command:
+                LibraryCommand::Index {
+                    dir,
+                    output,
+                    thumbnail_size,
+                    no_thumbnails,
+                },
+        } => {
+            let output = eco_library::index(&eco_library::IndexOptions {
+                dir,
+                output,
+                thumbnail_size: (!no_thumbnails).then_some(thumbnail_size),
+            })?;
+            println!("{output}");
+        }
+        Command::Library {
+            command:
+                LibraryCommand::Dupes {
+                    dir,
+                    catalog,
+                    min_similarity,
+                    json,
+                },
+        } => {
+            let catalog = eco_library::Catalog::try_from_path(
+                catalog.unwrap_or_else(|| dir.join(eco_library::CATALOG_FILE_NAME)),
+            )?;
+            let duplicates = eco_library::find_duplicates(&catalog, min_similarity)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&duplicates)?);
+            } else {
+                for duplicate in duplicates {
+                    let kind = match duplicate.kind {
+                        eco_library::DuplicateKind::Identical => "identical".to_string(),
+                        eco_library::DuplicateKind::SamePages => "same pages".to_string(),
+                        eco_library::DuplicateKind::Similar => {
+                            format!("{:.0}% similar", duplicate.similarity * 100.0)
+                        }
+                    };
+                    println!("{kind}:");
+                    for archive in duplicate.archives {
+                        println!("  {archive}");
+                    }
+                }
+            }
+        }
     }
 
     Ok(())
@@ -30,12 +30,162 @@ impl Display for ReadingOrder {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SidecarPolicy {
+    Skip,
+    KeepFirst,
+    KeepLast,
+    KeepAll,
+}
+
+impl From<SidecarPolicy> for eco_merge::SidecarPolicy {
+    fn from(value: SidecarPolicy) -> Self {
+        match value {
+            SidecarPolicy::Skip => Self::Skip,
+            SidecarPolicy::KeepFirst => Self::KeepFirst,
+            SidecarPolicy::KeepLast => Self::KeepLast,
+            SidecarPolicy::KeepAll => Self::KeepAll,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    Natural,
+    Lexical,
+}
+
+impl From<SortOrder> for eco_merge::SortOrder {
+    fn from(value: SortOrder) -> Self {
+        match value {
+            SortOrder::Natural => Self::Natural,
+            SortOrder::Lexical => Self::Lexical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    /// The already compressed formats are stored as is, the other ones are deflated
+    Auto,
+    Stored,
+    Deflated,
+}
+
+impl From<Compression> for eco_cbz::Compression {
+    fn from(value: Compression) -> Self {
+        match value {
+            Compression::Auto => Self::Auto,
+            Compression::Stored => Self::Stored,
+            Compression::Deflated => Self::Deflated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl From<Corner> for eco_pack::Corner {
+    fn from(value: Corner) -> Self {
+        match value {
+            Corner::TopLeft => Self::TopLeft,
+            Corner::TopRight => Self::TopRight,
+            Corner::BottomLeft => Self::BottomLeft,
+            Corner::BottomRight => Self::BottomRight,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MetadataFormat {
+    /// ComicBookInfo, stored in the zip comment
+    ComicBookInfo,
+    /// ComicInfo.xml
+    ComicInfo,
+}
+
+impl From<MetadataFormat> for eco_cbz::migrate::MetadataFormat {
+    fn from(value: MetadataFormat) -> Self {
+        match value {
+            MetadataFormat::ComicBookInfo => Self::ComicBookInfo,
+            MetadataFormat::ComicInfo => Self::ComicInfo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Cbz,
+    /// A directory containing the images
+    Dir,
+    /// A fixed layout epub for Kobo devices
+    Kepub,
+}
+
+impl From<OutputFormat> for eco_convert::OutputFormat {
+    fn from(value: OutputFormat) -> Self {
+        match value {
+            OutputFormat::Cbz => Self::Cbz,
+            OutputFormat::Dir => Self::Dir,
+            OutputFormat::Kepub => Self::Kepub,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Resampling {
+    Nearest,
+    Triangle,
+    Catmullrom,
+    Lanczos3,
+}
+
+impl From<Resampling> for eco_cbz::Resampling {
+    fn from(value: Resampling) -> Self {
+        match value {
+            Resampling::Nearest => Self::Nearest,
+            Resampling::Triangle => Self::Triangle,
+            Resampling::Catmullrom => Self::CatmullRom,
+            Resampling::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PageFormat {
+    Jpeg,
+    Png,
+    Webp,
+    /// Lossless JPEG XL, much smaller than png for the line art
+    Jxl,
+    Avif,
+}
+
+impl From<PageFormat> for eco_cbz::PageFormat {
+    fn from(value: PageFormat) -> Self {
+        match value {
+            PageFormat::Jpeg => Self::Jpeg,
+            PageFormat::Png => Self::Png,
+            PageFormat::Webp => Self::WebP,
+            PageFormat::Jxl => Self::Jxl,
+            PageFormat::Avif => Self::Avif,
+        }
+    }
+}
+
 // TODO: Format and FileType can, and should, be merged together, but the underlying should support them
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum Format {
     Mobi,
     Azw3,
     Pdf,
+    Epub,
+    Cbz,
 }
 
 impl From<Format> for eco_convert::Format {
@@ -44,6 +194,8 @@ impl From<Format> for eco_convert::Format {
             Format::Azw3 => Self::Azw3,
             Format::Mobi => Self::Mobi,
             Format::Pdf => Self::Pdf,
+            Format::Epub => Self::Epub,
+            Format::Cbz => Self::Cbz,
         }
     }
 }
@@ -52,15 +204,27 @@ impl From<Format> for eco_convert::Format {
 pub enum FileType {
     #[clap(name = "cbz")]
     Cbz,
+    #[clap(name = "cbr")]
+    Cbr,
     #[clap(skip, name = "epub")]
     EPub,
+    #[clap(name = "mobi")]
+    Mobi,
+    #[clap(name = "azw3")]
+    Azw3,
+    #[clap(name = "pdf")]
+    Pdf,
 }
 
 impl From<FileType> for eco_view::FileType {
     fn from(value: FileType) -> Self {
         match value {
             FileType::Cbz => Self::Cbz,
+            FileType::Cbr => Self::Cbr,
             FileType::EPub => Self::EPub,
+            FileType::Mobi => Self::Mobi,
+            FileType::Azw3 => Self::Azw3,
+            FileType::Pdf => Self::Pdf,
         }
     }
 }